@@ -0,0 +1,257 @@
+//! `grammar!`: a lexer and a parser written inline, generated at compile time
+//!
+//! ```ignore
+//! syntax_analyzer_macros::grammar! {
+//!     pub mod calc;
+//!
+//!     lexer {
+//!         let digit = [0-9]
+//!         rule tokens =
+//!             digit+   { return "NUM"; }
+//!           | '+'      { return "PLUS"; }
+//!           | ' '      { return "WS"; }
+//!     }
+//!
+//!     parser {
+//!         %token NUM PLUS
+//!         IGNORE WS
+//!         %%
+//!         e: e PLUS NUM | NUM ;
+//!     }
+//! }
+//! ```
+//!
+//! `lexer` is a .yal and `parser` a .yalp; both must also be valid Rust
+//! tokens (no `'` inside comments, no unknown escapes in strings). The macro
+//! expands to a module like the one of `codegen::build`, so the crate using
+//! it also depends on `syntax_analyzer`. Syntax errors, grammar errors and
+//! LR conflicts become `compile_error!`s pointing into the grammar.
+
+use std::{collections::HashSet, panic, str::FromStr};
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use syntax_analyzer::{
+    codegen::emit::module_source,
+    lex::{
+        lex_analyzer::LexAnalyzer,
+        yl_parser::{parse_yal, Loc},
+        yl_reader::{action_token, lexer_data},
+    },
+    syn::{
+        conflicts::find_conflicts,
+        syn_analyzer::SynAnalyzer,
+        validation::{validate_grammar, GrammarIssue, Severity},
+        yp_parser::parse_yalp,
+        yp_reader::grammar_info,
+    },
+    utility::read_config::Config,
+};
+
+/// `::core::compile_error!(msg)`, spanned so the error points at `span`
+fn compile_error(msg: &str, span: Span) -> TokenStream {
+    let mut message = Literal::string(msg);
+    message.set_span(span);
+    let mut tokens = vec![
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(Ident::new("core", span)),
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(Ident::new("compile_error", span)),
+        TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, TokenTree::Literal(message).into())),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ];
+    for t in &mut tokens {
+        if !matches!(t, TokenTree::Literal(_)) {
+            t.set_span(span);
+        }
+    }
+    tokens.into_iter().collect()
+}
+
+struct Input {
+    public: bool,
+    name: Ident,
+    lexer: Group,
+    parser: Group,
+}
+
+fn expect_ident(tokens: &mut impl Iterator<Item = TokenTree>, what: &str, last: &mut Span) -> Result<Ident, (String, Span)> {
+    match tokens.next() {
+        Some(TokenTree::Ident(i)) if what.is_empty() || i.to_string() == what => {
+            *last = i.span();
+            Ok(i)
+        }
+        Some(t) => Err((format!("expected `{}`, found `{}`", if what.is_empty() { "a name" } else { what }, t), t.span())),
+        None => Err((format!("expected `{}`", if what.is_empty() { "a name" } else { what }), *last)),
+    }
+}
+
+fn expect_block(tokens: &mut impl Iterator<Item = TokenTree>, what: &str, last: &mut Span) -> Result<Group, (String, Span)> {
+    expect_ident(tokens, what, last)?;
+    match tokens.next() {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => Ok(g),
+        Some(t) => Err((format!("expected `{} {{ ... }}`", what), t.span())),
+        None => Err((format!("expected `{} {{ ... }}`", what), *last)),
+    }
+}
+
+/// `[pub] mod name; lexer { ... } parser { ... }`
+fn parse_input(input: TokenStream) -> Result<Input, (String, Span)> {
+    let mut tokens = input.into_iter().peekable();
+    let mut last = Span::call_site();
+    let public = matches!(tokens.peek(), Some(TokenTree::Ident(i)) if i.to_string() == "pub");
+    if public {
+        tokens.next();
+    }
+    expect_ident(&mut tokens, "mod", &mut last)?;
+    let name = expect_ident(&mut tokens, "", &mut last)?;
+    match tokens.next() {
+        Some(TokenTree::Punct(p)) if p.as_char() == ';' => {}
+        Some(t) => return Err(("expected `;`".to_string(), t.span())),
+        None => return Err(("expected `;`".to_string(), last)),
+    }
+    let lexer = expect_block(&mut tokens, "lexer", &mut last)?;
+    let parser = expect_block(&mut tokens, "parser", &mut last)?;
+    if let Some(t) = tokens.next() {
+        return Err(("unexpected tokens after `parser { ... }`".to_string(), t.span()));
+    }
+    Ok(Input { public, name, lexer, parser })
+}
+
+/// Contents of a braced block as written, so line breaks and comments stay
+fn block_source(group: &Group) -> String {
+    match group.span().source_text() {
+        Some(text) if text.len() >= 2 => text[1..text.len() - 1].to_string(),
+        _ => group.stream().to_string(),
+    }
+}
+
+/// Every token of `stream`, nested ones included, with where it starts
+fn spans(stream: TokenStream, out: &mut Vec<(usize, usize, TokenTree)>) {
+    for t in stream {
+        let span = match &t {
+            TokenTree::Group(g) => g.span_open(),
+            t => t.span(),
+        };
+        out.push((span.line(), span.column(), t.clone()));
+        if let TokenTree::Group(g) = t {
+            spans(g.stream(), out);
+        }
+    }
+}
+
+/// Span of the token at `loc`, a position inside the block's source
+fn loc_span(group: &Group, loc: Loc) -> Span {
+    let open = group.span_open();
+    let (line, col) = match loc.line {
+        1 => (open.line(), open.column() + loc.col),
+        l => (open.line() + l - 1, loc.col),
+    };
+    let mut tokens = Vec::new();
+    spans(group.stream(), &mut tokens);
+    tokens
+        .iter()
+        .filter(|(l, c, _)| (*l, *c) <= (line, col))
+        .max_by_key(|(l, c, _)| (*l, *c))
+        .map(|(_, _, t)| t.span())
+        .unwrap_or_else(|| group.span())
+}
+
+/// Span of the first use of `symbol` in the block
+fn symbol_span(group: &Group, symbol: &str) -> Span {
+    let mut tokens = Vec::new();
+    spans(group.stream(), &mut tokens);
+    tokens
+        .iter()
+        .find(|(_, _, t)| matches!(t, TokenTree::Ident(_) | TokenTree::Literal(_)) && t.to_string() == symbol)
+        .map(|(_, _, t)| t.span())
+        .unwrap_or_else(|| group.span())
+}
+
+fn issue_symbol(issue: &GrammarIssue) -> &str {
+    match issue {
+        GrammarIssue::UnusedTerminal(s)
+        | GrammarIssue::UnusedNonTerminal(s)
+        | GrammarIssue::Unreachable(s)
+        | GrammarIssue::NonProductive(s)
+        | GrammarIssue::EmptyLanguage(s)
+        | GrammarIssue::TokenNeverProduced(s) => s,
+        GrammarIssue::UndefinedNonTerminal { symbol, .. } => symbol,
+        GrammarIssue::DuplicateProduction { head, .. } => head,
+        GrammarIssue::UnknownLexerToken { token, .. } => token,
+        GrammarIssue::MisspelledToken { declared, .. } => declared,
+    }
+}
+
+fn expand(input: &Input) -> Result<TokenStream, TokenStream> {
+    // 1. Lexer
+    let yal = parse_yal(&block_source(&input.lexer))
+        .map_err(|e| compile_error(&format!("lexer: {}", e.msg), loc_span(&input.lexer, e.loc)))?;
+    let data = lexer_data(&yal);
+    let produced: HashSet<String> = data.actions.values().filter_map(|a| action_token(a)).collect();
+
+    // 2. Grammar, with every error of the validation at its symbol
+    let yalp = parse_yalp(&block_source(&input.parser))
+        .map_err(|e| compile_error(&format!("parser: {}", e.msg), loc_span(&input.parser, e.loc)))?;
+    let grammar = grammar_info(&yalp)
+        .map_err(|e| compile_error(&format!("parser: {}", e.msg), loc_span(&input.parser, e.loc)))?;
+    let errors: TokenStream = validate_grammar(&grammar, Some(&produced))
+        .iter()
+        .filter(|i| i.severity() == Severity::Error)
+        .map(|i| compile_error(&format!("grammar error: {}", i), symbol_span(&input.parser, issue_symbol(i))))
+        .collect();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    // 3. Conflicts, all of them and in a stable order, at their token
+    let config = Config::default();
+    let conflicts: TokenStream = find_conflicts(&grammar, config.parse_method)
+        .iter()
+        .map(|c| compile_error(&c.to_string(), symbol_span(&input.parser, &c.token)))
+        .collect();
+    if !conflicts.is_empty() {
+        return Err(conflicts);
+    }
+
+    // 4. Analyzers; anything else wrong with the tables panics
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let built = panic::catch_unwind(|| {
+        let lex = LexAnalyzer::from_lexer_data(data, &config);
+        let syn = SynAnalyzer::from_grammar(grammar, "grammar!", &config, Some(&produced));
+        module_source(&lex, &syn)
+    });
+    panic::set_hook(hook);
+    let source = built.map_err(|e| {
+        let msg = e
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| e.downcast_ref::<&str>().map(|s| s.to_string()))
+            .unwrap_or_else(|| "the analyzers couldn't be generated".to_string());
+        compile_error(&msg, input.parser.span())
+    })?;
+
+    // 5. `[pub] mod name { ... }`
+    let body = TokenStream::from_str(&source)
+        .map_err(|e| compile_error(&format!("generated code doesn't parse: {}", e), Span::call_site()))?;
+    let mut out: Vec<TokenTree> = Vec::new();
+    if input.public {
+        out.push(TokenTree::Ident(Ident::new("pub", Span::call_site())));
+    }
+    out.push(TokenTree::Ident(Ident::new("mod", Span::call_site())));
+    out.push(TokenTree::Ident(input.name.clone()));
+    out.push(TokenTree::Group(Group::new(Delimiter::Brace, body)));
+    Ok(out.into_iter().collect())
+}
+
+#[proc_macro]
+pub fn grammar(input: TokenStream) -> TokenStream {
+    let input = match parse_input(input) {
+        Ok(input) => input,
+        Err((msg, span)) => return compile_error(&msg, span),
+    };
+    expand(&input).unwrap_or_else(|e| e)
+}
@@ -0,0 +1,54 @@
+use syntax_analyzer::codegen::runtime::{parse, Kind, Lexer};
+
+syntax_analyzer_macros::grammar! {
+    mod calc;
+
+    lexer {
+        let digit = [0-9]
+        rule tokens =
+            digit+    { return "NUM"; }
+          | '+'       { return "PLUS"; }
+          | '*'       { return "TIMES"; }
+          | ' '       { return "WS"; }
+    }
+
+    parser {
+        /* Sums of products */
+        %token NUM
+        %token PLUS
+        %token TIMES
+        IGNORE WS
+        %%
+        e: e PLUS t | t ;
+        t: t TIMES NUM | NUM ;
+    }
+}
+
+#[test]
+fn inline_grammar_parses_valid_input() {
+    let (lex, syn) = (calc::lexer(), calc::parser());
+    let lexer = Lexer::new(&lex, calc::actions);
+    let (_, _, error) = parse(&lexer, &syn, "1 + 2 * 3").unwrap();
+    assert!(error.is_none(), "{:?}", error);
+    let (_, _, error) = parse(&lexer, &syn, "1 + * 3").unwrap();
+    assert!(error.is_some());
+}
+
+#[test]
+fn inline_grammar_tokens_are_typed() {
+    let lex = calc::lexer();
+    let kinds: Vec<calc::TokenKind> = Lexer::new(&lex, calc::actions)
+        .tokens("12+3")
+        .unwrap()
+        .iter()
+        .map(|t| t.token_name)
+        .collect();
+    assert_eq!(kinds, vec![calc::TokenKind::NUM, calc::TokenKind::PLUS, calc::TokenKind::NUM]);
+    assert_eq!(calc::TokenKind::TIMES.name(), "TIMES");
+}
+
+#[test]
+fn grammar_errors_point_into_the_grammar() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
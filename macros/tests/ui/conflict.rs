@@ -0,0 +1,18 @@
+syntax_analyzer_macros::grammar! {
+    mod calc;
+
+    lexer {
+        rule tokens =
+            '1'    { return "NUM"; }
+          | '+'    { return "PLUS"; }
+    }
+
+    parser {
+        %token NUM
+        %token PLUS
+        %%
+        e: e PLUS e | NUM ;
+    }
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+syntax_analyzer_macros::grammar! {
+    lexer {
+        rule tokens = '1' { return "NUM"; }
+    }
+
+    parser {
+        %token NUM
+        %%
+        e: NUM ;
+    }
+}
+
+fn main() {}
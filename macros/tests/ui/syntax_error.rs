@@ -0,0 +1,17 @@
+syntax_analyzer_macros::grammar! {
+    mod calc;
+
+    lexer {
+        rule tokens =
+            '1'    { return "NUM"; }
+    }
+
+    parser {
+        %token NUM
+        %%
+        e: NUM
+           | ;;
+    }
+}
+
+fn main() {}
@@ -62,13 +62,8 @@ fn main() -> std::io::Result<()> {
             let tem = actions(s.token.parse::<i32>().unwrap());
             if !tem.is_empty() {
                 symbol_table.push(Symbol {
-                    id: s.id,
-                    token: s.token.clone(),
-                    start: s.start,
-                    end: s.end,
-                    content: s.content.clone(),
                     token_name: tem.to_string(),
-                    line: s.line,
+                    ..s.clone()
                 });
             }
         }
@@ -89,9 +84,9 @@ fn main() -> std::io::Result<()> {
             let _steps_rslt = print_table::print_parse_steps(&steps, &path);
         }
 
-        if let Some((visual_msg, detailed_msg)) = error_msg {
-            println!("{}", visual_msg);
-            println!("{}", detailed_msg);
+        if let Some(error) = error_msg {
+            println!("{}", error.visual());
+            println!("{}", error.message);
         };
     }
     Ok(())
@@ -0,0 +1,107 @@
+//! Compact binary tables, `.bin` files instead of the `.ron` ones
+//!
+//! A file is a short header followed by the analyzer in postcard, a compact
+//! binary encoding of serde:
+//!
+//! ```text
+//! "SYNA"        magic, 4 bytes
+//! version       FORMAT_VERSION, u16 little endian
+//! kind          b'L' for a lexer, b'P' for a parser
+//! generator     length (u8) and the version of syntax_analyzer that wrote it
+//! tables        postcard
+//! ```
+//!
+//! postcard is not self-describing, so files of another `FORMAT_VERSION`
+//! are rejected instead of misread. It must change whenever a serialized
+//! field of `LexAnalyzer` or `SynAnalyzer` does.
+
+use std::{error::Error, fmt};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{lex::lex_analyzer::LexAnalyzer, syn::syn_analyzer::SynAnalyzer};
+
+pub const MAGIC: &[u8; 4] = b"SYNA";
+pub const FORMAT_VERSION: u16 = 2;
+pub const LEXER: u8 = b'L';
+pub const PARSER: u8 = b'P';
+
+/// Why a `.bin` couldn't be loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BinaryError {
+    NotTables,
+    WrongKind { expected: u8, found: u8 },
+    Version { found: u16, generator: String },
+    Corrupt(String),
+}
+
+fn kind_name(kind: u8) -> &'static str {
+    match kind {
+        LEXER => "lexer",
+        PARSER => "parser",
+        _ => "unknown",
+    }
+}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryError::NotTables => write!(f, "not a syntax_analyzer table file"),
+            BinaryError::WrongKind { expected, found } => {
+                write!(f, "expected {} tables, found {} tables", kind_name(*expected), kind_name(*found))
+            }
+            BinaryError::Version { found, generator } => write!(
+                f,
+                "written by syntax_analyzer {} in format {}, this one reads format {}; generate it again",
+                generator, found, FORMAT_VERSION
+            ),
+            BinaryError::Corrupt(msg) => write!(f, "corrupt tables: {}", msg),
+        }
+    }
+}
+
+impl Error for BinaryError {}
+
+fn encode<T: Serialize>(kind: u8, tables: &T) -> Vec<u8> {
+    let generator = env!("CARGO_PKG_VERSION");
+    let mut out = Vec::from(&MAGIC[..]);
+    out.extend(FORMAT_VERSION.to_le_bytes());
+    out.push(kind);
+    out.push(generator.len() as u8);
+    out.extend(generator.as_bytes());
+    // Only fails on types serde can't write, none of them here
+    postcard::to_extend(tables, out).expect("~ Error Syn: tables can't be serialized")
+}
+
+fn decode<T: DeserializeOwned>(kind: u8, bytes: &[u8]) -> Result<T, BinaryError> {
+    let rest = bytes.strip_prefix(&MAGIC[..]).ok_or(BinaryError::NotTables)?;
+    let truncated = || BinaryError::Corrupt("the header is cut short".to_string());
+    let (head, rest) = rest.split_first_chunk::<4>().ok_or_else(truncated)?;
+    let [v0, v1, found, len] = *head;
+    let (generator, tables) = rest.split_at_checked(len as usize).ok_or_else(truncated)?;
+    let version = u16::from_le_bytes([v0, v1]);
+    if version != FORMAT_VERSION {
+        let generator = String::from_utf8_lossy(generator).to_string();
+        return Err(BinaryError::Version { found: version, generator });
+    }
+    if found != kind {
+        return Err(BinaryError::WrongKind { expected: kind, found });
+    }
+    postcard::from_bytes(tables).map_err(|e| BinaryError::Corrupt(e.to_string()))
+}
+
+pub fn encode_lexer(lex: &LexAnalyzer) -> Vec<u8> {
+    encode(LEXER, lex)
+}
+
+pub fn encode_parser(syn: &SynAnalyzer) -> Vec<u8> {
+    encode(PARSER, syn)
+}
+
+pub fn decode_lexer(bytes: &[u8]) -> Result<LexAnalyzer, BinaryError> {
+    decode(LEXER, bytes)
+}
+
+pub fn decode_parser(bytes: &[u8]) -> Result<SynAnalyzer, BinaryError> {
+    decode(PARSER, bytes)
+}
@@ -0,0 +1,49 @@
+//! Entry point for `build.rs`, to generate the analyzers at compile time
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     syntax_analyzer::codegen::build::generate_parser("grammar/lexer.yal", "grammar/syn.yalp").unwrap();
+//! }
+//!
+//! // src/lib.rs
+//! mod parser {
+//!     include!(concat!(env!("OUT_DIR"), "/parser.rs"));
+//! }
+//! ```
+//!
+//! The included module is the one of `emit::module_source`: `TokenKind`,
+//! `actions`, `lexer()` and `parser()`, with the tables as static data.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::emit::module_source;
+use crate::{lex::lex_analyzer::LexAnalyzer, syn::syn_analyzer::SynAnalyzer, utility::read_config::Config};
+
+/// Name of the generated file inside the output directory
+pub const PARSER_FILE: &str = "parser.rs";
+
+/// Generates `$OUT_DIR/parser.rs` and tells cargo to run the build script
+/// again when either grammar changes. Grammar errors panic, failing the build.
+pub fn generate_parser(lex_path: &str, syn_path: &str) -> io::Result<PathBuf> {
+    let out_dir = env::var_os("OUT_DIR")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "OUT_DIR isn't set, call this from build.rs"))?;
+    for path in [lex_path, syn_path] {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+    generate_parser_to(lex_path, syn_path, Path::new(&out_dir), &Config::default())
+}
+
+/// `generate_parser` into any directory and with any `config`, without the
+/// messages for cargo. Returns the path of the generated file.
+pub fn generate_parser_to(lex_path: &str, syn_path: &str, out_dir: &Path, config: &Config) -> io::Result<PathBuf> {
+    let lex = LexAnalyzer::generate(lex_path, config);
+    let syn = SynAnalyzer::generate(syn_path, config, Some(&lex.token_names()));
+    fs::create_dir_all(out_dir)?;
+    let path = out_dir.join(PARSER_FILE);
+    fs::write(&path, module_source(&lex, &syn))?;
+    Ok(path)
+}
@@ -0,0 +1,379 @@
+//! Writes a generated parser as a crate of its own
+//!
+//! `out_dir` gets a Cargo.toml, the serialized tables under `src/`, a
+//! `lib.rs` with the actions of the .yal and a `main.rs` that hands them to
+//! `codegen::runtime`. Nothing in this repository is overwritten, so several
+//! parsers can be generated side by side. The crate depends on the
+//! `syntax_analyzer` given as `Runtime`, a checkout or a published version.
+//!
+//! With `TableFormat::Static` the tables are written as `static` arrays in
+//! `src/tables.rs` instead, so the binary deserializes nothing at startup;
+//! with `TableFormat::Binary`, as the compact `.bin` of `codegen::binary`.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use ron::ser::{to_string_pretty, PrettyConfig};
+
+use super::binary::{encode_lexer, encode_parser};
+use crate::{
+    lex::{lex_analyzer::LexAnalyzer, yl_reader::action_token},
+    syn::{slr_automata::Element, syn_analyzer::SynAnalyzer},
+};
+
+const LEX_RON: &str = "lex_analyzer.ron";
+const SYN_RON: &str = "syn_analyzer.ron";
+const LEX_BIN: &str = "lex_analyzer.bin";
+const SYN_BIN: &str = "syn_analyzer.bin";
+
+/// How the lexer and parser tables are embedded in the generated crate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    /// `.ron` files, included in the binary and deserialized on startup
+    Ron,
+    /// `static` arrays in `src/tables.rs`
+    Static,
+    /// `.bin` files, see `codegen::binary`
+    Binary,
+}
+
+/// Cargo package name out of the last component of `out_dir`
+pub fn package_name(out_dir: &Path) -> String {
+    let raw = out_dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("parser");
+    let mut name: String = raw
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name.insert_str(0, "parser_");
+    }
+    name
+}
+
+/// Where the generated crate takes `syntax_analyzer` from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Runtime {
+    /// A checkout, written relative to `out_dir` when both share a root
+    Path(PathBuf),
+    /// A version of the registry
+    Version(String),
+}
+
+impl Runtime {
+    /// This very crate, where it was built from
+    pub fn local() -> Runtime {
+        Runtime::Path(PathBuf::from(env!("CARGO_MANIFEST_DIR")))
+    }
+
+    /// The right side of `syntax_analyzer = ...` in the Cargo.toml
+    fn dependency(&self, out_dir: &Path) -> String {
+        match self {
+            Runtime::Path(path) => {
+                let path = relative_path(path, out_dir).unwrap_or_else(|| path.clone());
+                format!("{{ path = {:?} }}", path.to_string_lossy().replace('\\', "/"))
+            }
+            Runtime::Version(version) => format!("{:?}", version),
+        }
+    }
+}
+
+/// `path` as seen from `base`, `None` if they are on different roots
+fn relative_path(path: &Path, base: &Path) -> Option<PathBuf> {
+    let path = path.canonicalize().ok()?;
+    let base = base.canonicalize().ok()?;
+    let (mut to, mut from) = (path.components().peekable(), base.components().peekable());
+    if to.peek() != from.peek() {
+        return None;
+    }
+    while to.peek().is_some() && to.peek() == from.peek() {
+        to.next();
+        from.next();
+    }
+    let mut relative: PathBuf = from.map(|_| Component::ParentDir).collect();
+    relative.extend(to);
+    match relative.as_os_str().is_empty() {
+        true => Some(PathBuf::from(".")),
+        false => Some(relative),
+    }
+}
+
+// The manifest of the runtime, to depend on the same `ron` as it does
+const MANIFEST: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"));
+
+/// Requirement of `ron` in the manifest of the runtime. The .yal headers
+/// use it, and the crate gets the version the runtime was built with.
+fn ron_requirement() -> &'static str {
+    MANIFEST
+        .lines()
+        .find_map(|l| l.strip_prefix("ron = "))
+        .expect("the runtime depends on ron")
+}
+
+fn cargo_toml(name: &str, runtime: &str) -> String {
+    format!(
+        "[package]
+name = \"{}\"
+version = \"0.1.0\"
+edition = \"2021\"
+
+[dependencies]
+syntax_analyzer = {}
+ron = {}
+",
+        name,
+        runtime,
+        ron_requirement()
+    )
+}
+
+/// Rust name of the `TokenKind` variant for `token`. Quoted terminals such
+/// as `'+'` become `Lit_2B`, after the code of every char.
+pub fn variant_name(token: &str) -> String {
+    let quoted = token.len() > 2 && (token.starts_with('\'') || token.starts_with('"'));
+    let name: String = if quoted {
+        let codes: Vec<String> = token[1..token.len() - 1].chars().map(|c| format!("{:X}", c as u32)).collect();
+        format!("Lit_{}", codes.join("_"))
+    } else {
+        token.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect()
+    };
+    match name.starts_with(|c: char| c.is_ascii_digit()) || name.is_empty() {
+        true => format!("T_{}", name),
+        false => name,
+    }
+}
+
+/// Terminals of the grammar, IGNORE ones included, then the lexer tokens
+/// the grammar doesn't know, each with its variant name
+fn token_kinds(lex: &LexAnalyzer, syn: &SynAnalyzer) -> Vec<(String, String, bool)> {
+    let mut declared: Vec<&String> = syn
+        .productions
+        .values()
+        .flatten()
+        .filter_map(|e| match e {
+            Element::Terminal(t) => Some(t),
+            Element::NonTerminal(_) => None,
+        })
+        .chain(&syn.ignore)
+        .collect();
+    declared.sort();
+    declared.dedup();
+    let mut extra: Vec<String> = lex.token_names().into_iter().filter(|t| !declared.contains(&t)).collect();
+    extra.sort();
+
+    let mut taken: Vec<String> = Vec::new();
+    let mut kinds = Vec::new();
+    let tokens = declared.into_iter().map(|t| (t.clone(), true)).chain(extra.into_iter().map(|t| (t, false)));
+    for (token, in_grammar) in tokens {
+        let mut variant = variant_name(&token);
+        while taken.contains(&variant) {
+            variant.push('_');
+        }
+        taken.push(variant.clone());
+        kinds.push((token, variant, in_grammar));
+    }
+    kinds
+}
+
+/// The `TokenKind` enum and the `actions` of the .yal that return it
+pub fn tokens_source(lex: &LexAnalyzer, syn: &SynAnalyzer) -> String {
+    let mut out = String::new();
+
+    // 1. One variant per token, named after the grammar
+    let kinds = token_kinds(lex, syn);
+    out += "/// Terminals of the grammar, as the lexer returns them\n";
+    out += "#[allow(non_camel_case_types, clippy::upper_case_acronyms)]\n";
+    out += "#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]\npub enum TokenKind {\n";
+    for (_, variant, in_grammar) in &kinds {
+        if !in_grammar {
+            out += "    /// Returned by the lexer, but not a token of the grammar\n";
+        }
+        out += &format!("    {},\n", variant);
+    }
+    out += "}\n\n";
+    out += "impl syntax_analyzer::codegen::runtime::Kind for TokenKind {\n";
+    out += "    fn name(&self) -> &'static str {\n        match self {\n";
+    for (token, variant, _) in &kinds {
+        out += &format!("            TokenKind::{} => {:?},\n", variant, token);
+    }
+    out += "        }\n    }\n}\n\n";
+
+    // 2. Actions, a rule returning an unknown token doesn't compile
+    let variants: HashMap<&String, &String> = kinds.iter().map(|(t, v, _)| (t, v)).collect();
+    let mut ids: Vec<(&usize, String)> =
+        lex.actions.iter().filter_map(|(id, a)| action_token(a).map(|t| (id, t))).collect();
+    ids.sort();
+    out += "/// Token returned by the lexer rule `id`, `None` if it returns nothing\n";
+    out += "pub fn actions(id: usize) -> Option<TokenKind> {\n    match id {\n";
+    for (id, token) in ids {
+        out += &format!("        {} => Some(TokenKind::{}),\n", id, variants[&token]);
+    }
+    out += "        _ => None,\n    }\n}\n\n";
+    out
+}
+
+const STATIC_LOADERS: &str = "pub fn lexer() -> syntax_analyzer::lex::lex_analyzer::LexAnalyzer {
+    tables::LEXER.load()
+}
+
+pub fn parser() -> syntax_analyzer::syn::syn_analyzer::SynAnalyzer {
+    tables::PARSER.load()
+}
+";
+
+/// `lib.rs`: the .yal header, the `TokenKind` enum, the actions of the
+/// .yal and the embedded tables
+pub fn lib_source(lex: &LexAnalyzer, syn: &SynAnalyzer, format: TableFormat) -> String {
+    let mut out = String::from("#![allow(unused_imports)]\n");
+    for h in &lex.header {
+        out += h;
+        out += "\n";
+    }
+    out += "\n";
+    out += &tokens_source(lex, syn);
+
+    // Paths are spelled out, the header may already import these names
+    out += &match format {
+        TableFormat::Ron => format!(
+            "pub fn lexer() -> syntax_analyzer::lex::lex_analyzer::LexAnalyzer {{
+    syntax_analyzer::codegen::runtime::load_lexer(include_str!(\"{}\"))
+}}
+
+pub fn parser() -> syntax_analyzer::syn::syn_analyzer::SynAnalyzer {{
+    syntax_analyzer::codegen::runtime::load_parser(include_str!(\"{}\"))
+}}
+",
+            LEX_RON, SYN_RON
+        ),
+        TableFormat::Binary => format!(
+            "pub fn lexer() -> syntax_analyzer::lex::lex_analyzer::LexAnalyzer {{
+    syntax_analyzer::codegen::runtime::load_lexer_bin(include_bytes!(\"{}\"))
+}}
+
+pub fn parser() -> syntax_analyzer::syn::syn_analyzer::SynAnalyzer {{
+    syntax_analyzer::codegen::runtime::load_parser_bin(include_bytes!(\"{}\"))
+}}
+",
+            LEX_BIN, SYN_BIN
+        ),
+        TableFormat::Static => format!("mod tables;\n\n{}", STATIC_LOADERS),
+    };
+    out
+}
+
+/// A single file to `include!`, with the tables in an inline `tables`
+/// module. The .yal header is left out: it holds the imports of a `main`.
+pub fn module_source(lex: &LexAnalyzer, syn: &SynAnalyzer) -> String {
+    let mut out = String::from("// Generated by syntax_analyzer, do not edit\n\n");
+    out += &tokens_source(lex, syn);
+    out += STATIC_LOADERS;
+    out += "\nmod tables {\n";
+    for line in tables_source(lex, syn).lines().skip(1) {
+        if !line.is_empty() {
+            out += "    ";
+            out += line;
+        }
+        out += "\n";
+    }
+    out += "}\n";
+    out
+}
+
+fn sorted<T: Ord>(mut items: Vec<T>) -> Vec<T> {
+    items.sort();
+    items
+}
+
+/// `tables.rs`: both tables as `static` data, sorted so the output is stable.
+/// Chars and strings are written with `{:?}`, which is valid Rust.
+pub fn tables_source(lex: &LexAnalyzer, syn: &SynAnalyzer) -> String {
+    let mut out = String::from(
+        "// Generated by syntax_analyzer, do not edit
+use syntax_analyzer::codegen::runtime::{StaticElement::*, StaticLexer, StaticParser};
+
+",
+    );
+
+    // 1. Lexer DFA
+    out += &format!("pub static LEXER: StaticLexer = StaticLexer {{\n    start: {:?},\n", lex.start);
+    out += &format!("    accept: &{:?},\n", sorted(lex.accept.iter().collect()));
+    out += "    map: &[\n";
+    for state in sorted(lex.map.keys().collect()) {
+        let edges: Vec<String> = sorted(lex.map[state].iter().collect())
+            .iter()
+            .map(|(k, to)| format!("({:?}, {:?})", k, to))
+            .collect();
+        out += &format!("        ({:?}, &[{}]),\n", state, edges.join(", "));
+    }
+    out += &format!("    ],\n    token_list: &{:?},\n}};\n\n", lex.token_list);
+
+    // 2. LR tables
+    out += "pub static PARSER: StaticParser = StaticParser {\n    productions: &[\n";
+    for id in sorted(syn.productions.keys().collect()) {
+        let body: Vec<String> = syn.productions[id]
+            .iter()
+            .map(|e| match e {
+                Element::Terminal(t) => format!("Terminal({:?})", t),
+                Element::NonTerminal(nt) => format!("NonTerminal({:?})", nt),
+            })
+            .collect();
+        out += &format!("        ({}, &[{}]),\n", id, body.join(", "));
+    }
+    out += "    ],\n    action: &[\n";
+    for ((st, t), a) in sorted(syn.action.iter().collect()) {
+        out += &format!("        ({}, {:?}, {:?}),\n", st, t, a);
+    }
+    out += "    ],\n    goto: &[\n";
+    for ((st, nt), to) in sorted(syn.goto.iter().collect()) {
+        out += &format!("        ({}, {:?}, {}),\n", st, nt, to);
+    }
+    out += "    ],\n";
+    out += &format!("    ignore: &{:?},\n", sorted(syn.ignore.iter().collect()));
+    out += &format!("    flatten: &{:?},\n}};\n", sorted(syn.flatten.iter().collect()));
+    out
+}
+
+fn main_source(name: &str) -> String {
+    format!(
+        "fn main() -> std::process::ExitCode {{
+    syntax_analyzer::codegen::runtime::run(&{0}::lexer(), &{0}::parser(), {0}::actions)
+}}
+",
+        name.replace('-', "_")
+    )
+}
+
+/// Writes the crate into `out_dir`, creating it if needed
+pub fn emit_crate(
+    out_dir: &Path,
+    lex: &LexAnalyzer,
+    syn: &SynAnalyzer,
+    format: TableFormat,
+    runtime: &Runtime,
+) -> io::Result<()> {
+    let src = out_dir.join("src");
+    fs::create_dir_all(&src)?;
+    let name = package_name(out_dir);
+    let to_ron = |e: ron::Error| io::Error::other(e);
+
+    fs::write(out_dir.join("Cargo.toml"), cargo_toml(&name, &runtime.dependency(out_dir)))?;
+    match format {
+        TableFormat::Ron => {
+            fs::write(src.join(LEX_RON), to_string_pretty(lex, PrettyConfig::default()).map_err(to_ron)?)?;
+            fs::write(src.join(SYN_RON), to_string_pretty(syn, PrettyConfig::default()).map_err(to_ron)?)?;
+        }
+        TableFormat::Static => fs::write(src.join("tables.rs"), tables_source(lex, syn))?,
+        TableFormat::Binary => {
+            fs::write(src.join(LEX_BIN), encode_lexer(lex))?;
+            fs::write(src.join(SYN_BIN), encode_parser(syn))?;
+        }
+    }
+    fs::write(src.join("lib.rs"), lib_source(lex, syn, format))?;
+    fs::write(src.join("main.rs"), main_source(&name))?;
+    Ok(())
+}
@@ -0,0 +1,198 @@
+//! Runtime used by the generated parser crates
+//!
+//! The generated crate holds the tables and the `actions` of its .yal; the
+//! lexing, the parse and the command line driver live here, so every
+//! generated parser behaves the same.
+
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::Path,
+    process::ExitCode,
+};
+
+use super::binary::{decode_lexer, decode_parser};
+use crate::{
+    lex::lex_analyzer::{LexAnalyzer, Symbol},
+    syn::{
+        slr_automata::Element,
+        syn_analyzer::{ParseError, ParsingStep, SynAnalyzer},
+    },
+    utility::read_config::Config,
+    view::print_table::{print_parse_steps, print_symbol_table},
+};
+
+/// Token type of a generated parser, the `TokenKind` enum of its lib.rs
+pub use crate::lex::lex_analyzer::Kind;
+
+/// Maps a lexer rule id to the token its action returns, `None` to drop it
+pub type Actions<K> = fn(usize) -> Option<K>;
+
+/// Symbols given to the parser, its steps and the error, if any
+pub type ParseResult<K = String> = (Vec<Symbol<K>>, Vec<ParsingStep>, Option<ParseError>);
+
+/// A lexer whose symbols carry the kind returned by the action of their
+/// rule. The actions are called once per rule, here, not once per symbol.
+pub struct Lexer<'a, K> {
+    pub analyzer: &'a LexAnalyzer,
+    // By rule id, as `Symbol::token` holds it; rules returning nothing are left out
+    kinds: HashMap<String, K>,
+}
+
+impl<'a, K: Kind> Lexer<'a, K> {
+    pub fn new(analyzer: &'a LexAnalyzer, actions: impl Fn(usize) -> Option<K>) -> Lexer<'a, K> {
+        let kinds = analyzer
+            .token_list
+            .iter()
+            .filter_map(|id| Some((id.clone(), actions(id.parse().ok()?)?)))
+            .collect();
+        Lexer { analyzer, kinds }
+    }
+
+    /// Kind of a symbol of the analyzer, `None` if its action returns nothing
+    pub fn kind(&self, symbol: &Symbol) -> Option<&K> {
+        self.kinds.get(&symbol.token)
+    }
+
+    /// Lexes `input`, dropping the symbols whose action returns nothing;
+    /// `None` if there were lexic errors
+    pub fn tokens(&self, input: &str) -> Option<Vec<Symbol<K>>> {
+        let raw = self.analyzer.simulate(input.to_string())?;
+        let typed = raw.into_iter().filter_map(|s| {
+            let kind = self.kind(&s)?.clone();
+            Some(s.with_kind(kind))
+        });
+        Some(typed.collect())
+    }
+}
+
+pub fn load_lexer(ron_src: &str) -> LexAnalyzer {
+    ron::from_str(ron_src).expect("~ Error Lex: generated lexer tables are corrupt")
+}
+
+pub fn load_parser(ron_src: &str) -> SynAnalyzer {
+    ron::from_str(ron_src).expect("~ Error Syn: generated parser tables are corrupt")
+}
+
+/// `load_lexer` for the `.bin` of `emit::TableFormat::Binary`
+pub fn load_lexer_bin(bytes: &[u8]) -> LexAnalyzer {
+    decode_lexer(bytes).unwrap_or_else(|e| panic!("~ Error Lex: generated lexer tables: {}", e))
+}
+
+pub fn load_parser_bin(bytes: &[u8]) -> SynAnalyzer {
+    decode_parser(bytes).unwrap_or_else(|e| panic!("~ Error Syn: generated parser tables: {}", e))
+}
+
+/// Lexer DFA written as Rust source by `emit::TableFormat::Static`
+pub struct StaticLexer {
+    pub start: char,
+    pub accept: &'static [char],
+    pub map: &'static [(char, &'static [(&'static str, char)])],
+    pub token_list: &'static [&'static str],
+}
+
+impl StaticLexer {
+    /// The actions stay in the generated `actions` function
+    pub fn load(&self) -> LexAnalyzer {
+        LexAnalyzer {
+            map: self
+                .map
+                .iter()
+                .map(|(state, edges)| (*state, edges.iter().map(|(k, to)| (k.to_string(), *to)).collect()))
+                .collect(),
+            accept: self.accept.iter().copied().collect(),
+            start: self.start,
+            token_list: self.token_list.iter().map(|t| t.to_string()).collect(),
+            actions: HashMap::new(),
+            header: Vec::new(),
+            tokens: HashMap::new(),
+        }
+    }
+}
+
+/// Symbol of a production in a `StaticParser`
+pub enum StaticElement {
+    Terminal(&'static str),
+    NonTerminal(&'static str),
+}
+
+/// LR tables written as Rust source by `emit::TableFormat::Static`. Every
+/// production starts with its head, as in `SynAnalyzer::productions`.
+pub struct StaticParser {
+    pub productions: &'static [(u8, &'static [StaticElement])],
+    pub action: &'static [(u8, &'static str, &'static str)],
+    pub goto: &'static [(u8, &'static str, u8)],
+    pub ignore: &'static [&'static str],
+    pub flatten: &'static [&'static str],
+}
+
+impl StaticParser {
+    pub fn load(&self) -> SynAnalyzer {
+        let element = |e: &StaticElement| match e {
+            StaticElement::Terminal(t) => Element::Terminal(t.to_string()),
+            StaticElement::NonTerminal(nt) => Element::NonTerminal(nt.to_string()),
+        };
+        SynAnalyzer {
+            productions: self.productions.iter().map(|(id, body)| (*id, body.iter().map(element).collect())).collect(),
+            action: self.action.iter().map(|(st, t, a)| ((*st, t.to_string()), a.to_string())).collect(),
+            goto: self.goto.iter().map(|(st, nt, to)| ((*st, nt.to_string()), *to)).collect(),
+            ignore: self.ignore.iter().map(|t| t.to_string()).collect::<HashSet<String>>(),
+            flatten: self.flatten.iter().map(|nt| nt.to_string()).collect(),
+            states: Vec::new(),
+            compressed: None,
+        }
+    }
+}
+
+/// Parses `input`, skipping the IGNORE tokens of the grammar
+pub fn parse<K: Kind>(lexer: &Lexer<K>, syn: &SynAnalyzer, input: &str) -> Option<ParseResult<K>> {
+    let mut symbols = lexer.tokens(input)?;
+    symbols.retain(|x| !syn.ignore.contains(x.token_name.name()));
+    let (steps, error) = syn.parse(&symbols);
+    Some((symbols, steps, error))
+}
+
+/// `main` of a generated parser: `parser ./path/to/input.txt`. The
+/// visualizations of `config.json` are written if the file is found. Exits
+/// with 1 on I/O errors, a malformed config.json included, 2 on wrong
+/// arguments, 4 on lexical errors and 5 on syntax errors.
+pub fn run<K: Kind>(lex: &LexAnalyzer, syn: &SynAnalyzer, actions: Actions<K>) -> ExitCode {
+    match run_args(lex, syn, actions) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err((code, msg)) => {
+            eprintln!("{}", msg);
+            ExitCode::from(code)
+        }
+    }
+}
+
+fn run_args<K: Kind>(lex: &LexAnalyzer, syn: &SynAnalyzer, actions: Actions<K>) -> Result<(), (u8, String)> {
+    let io = |e: &dyn std::fmt::Display| (1, format!("~ Error: {}", e));
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 2 {
+        return Err((2, format!("~ Error: arguments must be '{} ./path/to/input.txt'", args[0])));
+    }
+    let contents = fs::read_to_string(&args[1]).map_err(|e| io(&format!("couldn't read '{}': {}", args[1], e)))?;
+    let config = match Path::new("config.json").is_file() {
+        true => Some(Config::from_file("config.json").map_err(|e| io(&format!("couldn't read 'config.json': {}", e)))?),
+        false => None,
+    };
+
+    let (symbols, steps, error) = parse(&Lexer::new(lex, actions), syn, &contents)
+        .ok_or((4, "~ Error Lex: the input has lexical errors".to_string()))?;
+    if let Some(vis) = config.as_ref().map(|c| &c.vis) {
+        if let Some(path) = &vis.symbol_table {
+            print_symbol_table(&symbols, path).map_err(|e| io(&e))?;
+        }
+        if let Some(path) = &vis.parse_steps {
+            print_parse_steps(&steps, path).map_err(|e| io(&e))?;
+        }
+    }
+    match error {
+        Some(error) => {
+            println!("{}", error.visual());
+            Err((5, format!("~ Error Syn: {}", error.message)))
+        }
+        None => Ok(()),
+    }
+}
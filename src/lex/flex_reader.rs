@@ -0,0 +1,700 @@
+//! Reader for the common subset of flex .l files
+//!
+//! Definitions become `let`s and the rules section becomes a single rule, so
+//! the result goes through the same `lexer_data` as a parsed .yal file.
+//! Supported: `%{ %}` blocks (skipped, as they hold C code), `%s`/`%x`
+//! declarations, `<SC>` prefixes and `<SC>{ ... }` scopes, `|` actions and
+//! the usual pattern syntax (`.`, `[^...]`, `[:alpha:]`, `{name}`, `{n,m}`).
+//! Anchors (`^`, `$`) and trailing context (`/`) are rejected.
+//!
+//! The lexer has no start conditions at runtime, so only the rules active in
+//! `INITIAL` are kept; the others are reported in `FlexFile::skipped`.
+//! C actions are turned into the `return "TOKEN";` form of .yal actions.
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs,
+};
+
+use super::{
+    yl_parser::{Entry, LetDef, Loc, Regex, Rule, SetItem, YalError, YalFile},
+    yl_reader::{lexer_data, LexerData},
+};
+
+/// A flex file read as .yal
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlexFile {
+    pub yal: YalFile,
+    // Rules that were left out, and why
+    pub skipped: Vec<(Loc, String)>,
+}
+
+// Repetitions are expanded, so `{n,m}` can't get out of hand
+const MAX_REPEAT: usize = 64;
+
+fn error<T>(loc: Loc, msg: String) -> Result<T, YalError> {
+    Err(YalError { msg, loc })
+}
+
+/// Characters `.` and `[^...]` can match
+fn universe() -> BTreeSet<char> {
+    let mut all: BTreeSet<char> = (' '..='~').collect();
+    all.extend(['\t', '\n', '\r']);
+    all
+}
+
+/// Set items for a group of chars. Ranges are only used within lowercase
+/// letters, uppercase letters or digits, the ones the regex tokenizer reads.
+fn set_items(chars: &BTreeSet<char>) -> Vec<SetItem> {
+    let class = |c: char| {
+        if c.is_ascii_lowercase() {
+            1
+        } else if c.is_ascii_uppercase() {
+            2
+        } else if c.is_ascii_digit() {
+            3
+        } else {
+            0
+        }
+    };
+    let mut items = Vec::new();
+    let list: Vec<char> = chars.iter().copied().collect();
+    let mut i = 0;
+    while i < list.len() {
+        let mut j = i;
+        while class(list[i]) != 0
+            && j + 1 < list.len()
+            && class(list[j + 1]) == class(list[i])
+            && list[j + 1] as u32 == list[j] as u32 + 1
+        {
+            j += 1;
+        }
+        if j > i {
+            items.push(SetItem::Range(list[i], list[j]));
+        } else {
+            items.push(SetItem::Char(list[i]));
+        }
+        i = j + 1;
+    }
+    items
+}
+
+struct Cursor {
+    chars: Vec<char>,
+    pos: usize,
+    loc: Loc,
+}
+
+impl Cursor {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, n: usize) -> Option<char> {
+        self.chars.get(self.pos + n).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.get(self.pos).copied()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.loc.line += 1;
+            self.loc.col = 1;
+        } else {
+            self.loc.col += 1;
+        }
+        Some(c)
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.loc.col == 1
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars().enumerate().all(|(i, c)| self.peek_at(i) == Some(c))
+    }
+
+    fn rest_of_line(&mut self) -> String {
+        let mut line = String::new();
+        while let Some(c) = self.bump() {
+            if c == '\n' {
+                break;
+            }
+            line.push(c);
+        }
+        line
+    }
+
+    fn skip_blanks(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.bump();
+        }
+    }
+
+    // From `%{` to the `%}` line
+    fn skip_code_block(&mut self, start: Loc) -> Result<(), YalError> {
+        self.rest_of_line();
+        loop {
+            if self.peek().is_none() {
+                return error(start, "unterminated `%{` block".to_string());
+            }
+            if self.starts_with("%}") {
+                self.rest_of_line();
+                return Ok(());
+            }
+            self.rest_of_line();
+        }
+    }
+
+    fn skip_comment(&mut self, start: Loc) -> Result<(), YalError> {
+        while !self.starts_with("*/") {
+            if self.bump().is_none() {
+                return error(start, "unterminated comment".to_string());
+            }
+        }
+        self.bump();
+        self.bump();
+        Ok(())
+    }
+
+    /*──────────────────────────────────────────────────────────────*/
+    /* PATTERNS                                                     */
+    /*──────────────────────────────────────────────────────────────*/
+
+    fn pattern_ends(&self) -> bool {
+        matches!(self.peek(), None | Some(' ') | Some('\t') | Some('\n') | Some('\r'))
+    }
+
+    fn regex(&mut self, defs: &HashSet<String>) -> Result<Regex, YalError> {
+        let mut options = vec![self.concat(defs)?];
+        while self.peek() == Some('|') {
+            self.bump();
+            options.push(self.concat(defs)?);
+        }
+        Ok(if options.len() == 1 { options.remove(0) } else { Regex::Union(options) })
+    }
+
+    fn concat(&mut self, defs: &HashSet<String>) -> Result<Regex, YalError> {
+        let mut parts = Vec::new();
+        while !self.pattern_ends() && !matches!(self.peek(), Some('|') | Some(')')) {
+            parts.push(self.postfix(defs)?);
+        }
+        match parts.len() {
+            0 => error(self.loc, "expected a pattern".to_string()),
+            1 => Ok(parts.remove(0)),
+            _ => Ok(Regex::Concat(parts)),
+        }
+    }
+
+    fn postfix(&mut self, defs: &HashSet<String>) -> Result<Regex, YalError> {
+        let mut regex = self.atom(defs)?;
+        loop {
+            regex = match self.peek() {
+                Some('{') if self.peek_at(1).is_some_and(|c| c.is_ascii_digit()) => self.repeat(regex)?,
+                Some(c) if "*+?".contains(c) => {
+                    self.bump();
+                    match c {
+                        '*' => Regex::Kleene(Box::new(regex)),
+                        '+' => Regex::Plus(Box::new(regex)),
+                        _ => Regex::Optional(Box::new(regex)),
+                    }
+                }
+                _ => return Ok(regex),
+            };
+        }
+    }
+
+    // `{n}`, `{n,}` and `{n,m}`, expanded into copies of the operand
+    fn repeat(&mut self, regex: Regex) -> Result<Regex, YalError> {
+        let start = self.loc;
+        self.bump();
+        let number = |cur: &mut Cursor| {
+            let mut n = String::new();
+            while let Some(d) = cur.peek().filter(|c| c.is_ascii_digit()) {
+                n.push(d);
+                cur.bump();
+            }
+            n.parse::<usize>().ok()
+        };
+        let min = number(self).unwrap_or(0);
+        let max = if self.peek() == Some(',') {
+            self.bump();
+            number(self)
+        } else {
+            Some(min)
+        };
+        if self.bump() != Some('}') {
+            return error(start, "expected `}` to close the repetition".to_string());
+        }
+        if max.is_some_and(|m| m < min || m == 0) || min.max(max.unwrap_or(0)) > MAX_REPEAT {
+            return error(start, format!("invalid repetition, the bounds go from 0 to {}", MAX_REPEAT));
+        }
+        let mut parts = vec![regex.clone(); min];
+        match max {
+            Some(m) => parts.extend(vec![Regex::Optional(Box::new(regex)); m - min]),
+            None => parts.push(Regex::Kleene(Box::new(regex))),
+        }
+        Ok(if parts.len() == 1 { parts.remove(0) } else { Regex::Concat(parts) })
+    }
+
+    fn escape(&mut self, start: Loc) -> Result<char, YalError> {
+        let c = match self.bump() {
+            Some(c) => c,
+            None => return error(start, "unfinished escape".to_string()),
+        };
+        let code = |cur: &mut Cursor, mut digits: String, radix: u32, len: usize| {
+            while digits.len() < len {
+                match cur.peek().filter(|d| d.is_digit(radix)) {
+                    Some(d) => {
+                        digits.push(d);
+                        cur.bump();
+                    }
+                    None => break,
+                }
+            }
+            u32::from_str_radix(&digits, radix).ok().and_then(char::from_u32)
+        };
+        let escaped = match c {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            'f' => Some('\x0c'),
+            'v' => Some('\x0b'),
+            'a' => Some('\x07'),
+            'b' => Some('\x08'),
+            'x' => code(self, String::new(), 16, 2),
+            '0'..='7' => code(self, c.to_string(), 8, 3),
+            c => Some(c),
+        };
+        match escaped {
+            Some(c) => Ok(c),
+            None => error(start, "invalid escape".to_string()),
+        }
+    }
+
+    fn atom(&mut self, defs: &HashSet<String>) -> Result<Regex, YalError> {
+        let start = self.loc;
+        match self.bump() {
+            Some('(') => {
+                let inner = self.regex(defs)?;
+                if self.bump() != Some(')') {
+                    return error(start, "unclosed `(`".to_string());
+                }
+                Ok(inner)
+            }
+            Some('"') => {
+                let mut s = String::new();
+                loop {
+                    match self.bump() {
+                        Some('"') => break,
+                        Some('\\') => s.push(self.escape(start)?),
+                        Some('\n') | None => return error(start, "unterminated string".to_string()),
+                        Some(c) => s.push(c),
+                    }
+                }
+                if s.is_empty() {
+                    return error(start, "empty string".to_string());
+                }
+                Ok(Regex::Literal(s))
+            }
+            Some('[') => self.set(start),
+            Some('{') => {
+                let mut name = String::new();
+                while let Some(c) = self.peek().filter(|c| c.is_alphanumeric() || *c == '_' || *c == '-') {
+                    name.push(c);
+                    self.bump();
+                }
+                if self.bump() != Some('}') {
+                    return error(start, "expected `}` after the definition name".to_string());
+                }
+                if !defs.contains(&name) {
+                    return error(start, format!("undefined definition `{}`", name));
+                }
+                Ok(Regex::Var(name, start))
+            }
+            Some('.') => {
+                let mut any = universe();
+                any.remove(&'\n');
+                Ok(Regex::Set(set_items(&any)))
+            }
+            Some('\\') => Ok(Regex::Literal(self.escape(start)?.to_string())),
+            Some('^') | Some('$') => error(start, "anchors (`^`, `$`) are not supported".to_string()),
+            Some('/') => error(start, "trailing context (`/`) is not supported".to_string()),
+            Some(c) if "*+?|)".contains(c) => error(start, format!("unexpected `{}`", c)),
+            Some(c) => Ok(Regex::Literal(c.to_string())),
+            None => error(start, "expected a pattern".to_string()),
+        }
+    }
+
+    // After `[`: chars, ranges, `[:class:]` and a leading `^`
+    fn set(&mut self, start: Loc) -> Result<Regex, YalError> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.bump();
+        }
+        let mut chars: BTreeSet<char> = BTreeSet::new();
+        let mut first = true;
+        loop {
+            let c = match self.bump() {
+                Some(']') if !first => break,
+                Some('[') if self.peek() == Some(':') => {
+                    let mut class = String::new();
+                    self.bump();
+                    while let Some(c) = self.bump() {
+                        if c == ':' {
+                            break;
+                        }
+                        class.push(c);
+                    }
+                    if self.bump() != Some(']') {
+                        return error(start, "expected `:]` to close the class".to_string());
+                    }
+                    let members: Vec<char> = universe()
+                        .into_iter()
+                        .filter(|c| match class.as_str() {
+                            "alpha" => c.is_ascii_alphabetic(),
+                            "digit" => c.is_ascii_digit(),
+                            "alnum" => c.is_ascii_alphanumeric(),
+                            "upper" => c.is_ascii_uppercase(),
+                            "lower" => c.is_ascii_lowercase(),
+                            "space" => c.is_ascii_whitespace(),
+                            "xdigit" => c.is_ascii_hexdigit(),
+                            "punct" => c.is_ascii_punctuation(),
+                            _ => false,
+                        })
+                        .collect();
+                    if members.is_empty() {
+                        return error(start, format!("unknown class `[:{}:]`", class));
+                    }
+                    chars.extend(members);
+                    first = false;
+                    continue;
+                }
+                Some('\\') => self.escape(start)?,
+                Some('\n') | None => return error(start, "unterminated character set".to_string()),
+                Some(c) => c,
+            };
+            first = false;
+            if self.peek() == Some('-') && self.peek_at(1).is_some_and(|n| n != ']') {
+                self.bump();
+                let to = match self.bump() {
+                    Some('\\') => self.escape(start)?,
+                    Some(t) => t,
+                    None => return error(start, "unterminated character set".to_string()),
+                };
+                if to < c {
+                    return error(start, format!("invalid range '{}-{}'", c, to));
+                }
+                chars.extend(c..=to);
+            } else {
+                chars.insert(c);
+            }
+        }
+        if negated {
+            chars = universe().difference(&chars).copied().collect();
+        }
+        if chars.is_empty() {
+            return error(start, "empty character set".to_string());
+        }
+        Ok(Regex::Set(set_items(&chars)))
+    }
+
+    /*──────────────────────────────────────────────────────────────*/
+    /* ACTIONS                                                      */
+    /*──────────────────────────────────────────────────────────────*/
+
+    // `{ ... }` across lines, or the rest of the line
+    fn action(&mut self) -> Result<String, YalError> {
+        let start = self.loc;
+        if self.peek() != Some('{') {
+            return Ok(self.rest_of_line().trim().to_string());
+        }
+        let mut code = String::new();
+        let mut depth = 0;
+        loop {
+            if self.starts_with("/*") {
+                self.skip_comment(start)?;
+                continue;
+            }
+            let c = match self.bump() {
+                Some(c) => c,
+                None => return error(start, "unterminated action block".to_string()),
+            };
+            code.push(c);
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                '"' | '\'' => {
+                    while let Some(n) = self.bump() {
+                        code.push(n);
+                        if n == '\\' {
+                            if let Some(e) = self.bump() {
+                                code.push(e);
+                            }
+                        } else if n == c || n == '\n' {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.rest_of_line();
+        Ok(code[1..code.len() - 1].trim().to_string())
+    }
+}
+
+enum Returns {
+    Nothing,
+    Token(String),
+    // `return yytext[0];` and the like: the matched char is the token
+    MatchedChar,
+}
+
+fn returned_token(code: &str) -> Returns {
+    let Some(at) = code.find("return") else {
+        return Returns::Nothing;
+    };
+    // Blanks and parentheses go, except inside a quoted literal
+    let mut value = String::new();
+    let mut quote: Option<char> = None;
+    let mut chars = code[at + "return".len()..].chars();
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) => {
+                value.push(c);
+                if c == '\\' {
+                    value.extend(chars.next());
+                } else if c == q {
+                    quote = None;
+                }
+            }
+            None if c == ';' => break,
+            None if c.is_whitespace() || c == '(' || c == ')' => {}
+            None => {
+                if c == '\'' || c == '"' {
+                    quote = Some(c);
+                }
+                value.push(c);
+            }
+        }
+    }
+    if value == "yytext[0]" || value == "*yytext" {
+        Returns::MatchedChar
+    } else if value.is_empty() {
+        Returns::Nothing
+    } else {
+        Returns::Token(value)
+    }
+}
+
+fn token_action(token: &str) -> String {
+    format!("return {:?};", token)
+}
+
+/// Parses the contents of a flex .l file
+pub fn parse_flex(src: &str) -> Result<FlexFile, YalError> {
+    let mut cur = Cursor {
+        chars: src.chars().collect(),
+        pos: 0,
+        loc: Loc { line: 1, col: 1 },
+    };
+    let mut lets: Vec<LetDef> = Vec::new();
+    let mut defs: HashSet<String> = HashSet::new();
+
+    // 1. Definitions
+    loop {
+        let start = cur.loc;
+        if cur.peek().is_none() {
+            return error(start, "expected `%%` before the rules".to_string());
+        }
+        if cur.starts_with("%%") {
+            cur.rest_of_line();
+            break;
+        }
+        if cur.starts_with("%{") || cur.starts_with("%top{") {
+            cur.skip_code_block(start)?;
+        } else if cur.starts_with("/*") {
+            cur.skip_comment(start)?;
+        } else if matches!(cur.peek(), Some(' ') | Some('\t') | Some('\n') | Some('\r') | Some('%')) {
+            // Indented code, blank lines and %option, %s, %x, ...
+            cur.rest_of_line();
+        } else {
+            let mut name = String::new();
+            while let Some(c) = cur.peek().filter(|c| c.is_alphanumeric() || *c == '_' || *c == '-') {
+                name.push(c);
+                cur.bump();
+            }
+            if name.is_empty() || !matches!(cur.peek(), Some(' ') | Some('\t')) {
+                return error(start, "expected a definition `name pattern`".to_string());
+            }
+            cur.skip_blanks();
+            let regex = cur.regex(&defs)?;
+            cur.rest_of_line();
+            if !defs.insert(name.clone()) {
+                return error(start, format!("`{}` is defined twice", name));
+            }
+            lets.push(LetDef { name, regex, loc: start });
+        }
+    }
+
+    // 2. Rules
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut skipped: Vec<(Loc, String)> = Vec::new();
+    let mut scopes: Vec<Vec<String>> = Vec::new();
+    // Patterns waiting for the action of a `|` chain: (regex, loc, active)
+    let mut pending: Vec<(Regex, Loc, Option<String>)> = Vec::new();
+    let mut trailer = None;
+    while cur.peek().is_some() {
+        let start = cur.loc;
+        if cur.at_line_start() && cur.starts_with("%%") {
+            cur.rest_of_line();
+            trailer = Some(cur.chars[cur.pos..].iter().collect());
+            break;
+        }
+        if cur.starts_with("%{") {
+            cur.skip_code_block(start)?;
+            continue;
+        }
+        if cur.starts_with("/*") {
+            cur.skip_comment(start)?;
+            continue;
+        }
+        if matches!(cur.peek(), Some(' ') | Some('\t') | Some('\n') | Some('\r')) {
+            let line = cur.rest_of_line();
+            if line.trim() == "}" && scopes.pop().is_none() {
+                return error(start, "`}` without a start condition scope".to_string());
+            }
+            continue;
+        }
+        if cur.peek() == Some('}') && !scopes.is_empty() {
+            scopes.pop();
+            cur.rest_of_line();
+            continue;
+        }
+
+        // <SC1,SC2> prefix, or a <SC>{ scope
+        let mut conditions: Vec<String> = scopes.last().cloned().unwrap_or_default();
+        if cur.peek() == Some('<') && !cur.starts_with("<<EOF>>") {
+            cur.bump();
+            let mut list = String::new();
+            while let Some(c) = cur.bump() {
+                if c == '>' {
+                    break;
+                }
+                list.push(c);
+            }
+            conditions = list.split(',').map(|s| s.trim().to_string()).collect();
+            if cur.peek() == Some('{') && matches!(cur.peek_at(1), Some('\n') | Some('\r')) {
+                cur.rest_of_line();
+                scopes.push(conditions);
+                continue;
+            }
+        }
+        let inactive = if conditions.is_empty() || conditions.iter().any(|c| c == "INITIAL" || c == "*") {
+            None
+        } else {
+            Some(format!("only active in the start condition `{}`", conditions.join(",")))
+        };
+
+        if cur.starts_with("<<EOF>>") {
+            for _ in 0..7 {
+                cur.bump();
+            }
+            cur.skip_blanks();
+            cur.action()?;
+            skipped.push((start, "`<<EOF>>` rules have no equivalent".to_string()));
+            continue;
+        }
+
+        let regex = cur.regex(&defs)?;
+        cur.skip_blanks();
+        let action = cur.action()?;
+        pending.push((regex, start, inactive));
+        if action == "|" {
+            continue;
+        }
+
+        // 3. Every pattern of the chain shares the action
+        let returns = returned_token(&action);
+        for (regex, loc, inactive) in pending.drain(..) {
+            if let Some(reason) = inactive {
+                skipped.push((loc, reason));
+                continue;
+            }
+            match &returns {
+                Returns::Nothing => entries.push(Entry { regex, action: String::new(), loc }),
+                Returns::Token(t) => entries.push(Entry { regex, action: token_action(t), loc }),
+                Returns::MatchedChar => {
+                    // One entry per char, each returning its own char literal
+                    let chars: Vec<char> = match &regex {
+                        Regex::Literal(s) if s.chars().count() == 1 => s.chars().collect(),
+                        Regex::Set(items) if items.iter().all(|i| matches!(i, SetItem::Char(_))) => items
+                            .iter()
+                            .filter_map(|i| if let SetItem::Char(c) = i { Some(*c) } else { None })
+                            .collect(),
+                        _ => {
+                            skipped.push((loc, "returns the matched char, but can match more than a single char".to_string()));
+                            continue;
+                        }
+                    };
+                    for c in chars {
+                        let literal = match c {
+                            '\n' => "'\\n'".to_string(),
+                            '\t' => "'\\t'".to_string(),
+                            '\'' | '\\' => format!("'\\{}'", c),
+                            c => format!("'{}'", c),
+                        };
+                        entries.push(Entry { regex: Regex::Literal(c.to_string()), action: token_action(&literal), loc });
+                    }
+                }
+            }
+        }
+    }
+    if let Some((_, loc, _)) = pending.first() {
+        return error(*loc, "the last rule uses `|` but no rule follows it".to_string());
+    }
+    if entries.is_empty() {
+        return error(cur.loc, "no rules are active in the INITIAL start condition".to_string());
+    }
+
+    Ok(FlexFile {
+        yal: YalFile {
+            // C code from `%{ %}` can't go into the generated Rust parser
+            header: None,
+            lets,
+            rules: vec![Rule {
+                name: "flex".to_string(),
+                args: Vec::new(),
+                entries,
+                loc: Loc { line: 1, col: 1 },
+            }],
+            trailer,
+        },
+        skipped,
+    })
+}
+
+/// Reads a flex file as .yal, warning about the rules it leaves out
+pub fn read_flex_file(filename: &str) -> YalFile {
+    let source = fs::read_to_string(filename)
+        .unwrap_or_else(|e| panic!("~ Error Lex: couldn't read '{}': {}", filename, e));
+    match parse_flex(&source) {
+        Ok(flex) => {
+            for (loc, reason) in &flex.skipped {
+                eprintln!("~ Warning Lex: {}:{}: rule skipped, {}", filename, loc, reason);
+            }
+            flex.yal
+        }
+        Err(e) => panic!("~ Error Lex: {}:{}", filename, e),
+    }
+}
+
+pub fn read_flex(filename: &str) -> LexerData {
+    lexer_data(&read_flex_file(filename))
+}
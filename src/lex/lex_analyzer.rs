@@ -1,4 +1,5 @@
 use crate::{
+    codegen::binary::decode_lexer,
     utility::{read_config::Config, writer::write_to_file},
     view::{logging::print_log, render::render_dfa},
 };
@@ -7,10 +8,16 @@ use ron::error;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
+    fmt::Debug,
+    hash::Hash,
     rc::Rc,
 };
 
-use super::{direct_afd, grammar_tree, minimize, tokenizer, yl_reader::read_yalex};
+use super::{
+    direct_afd, grammar_tree, minimize, tokenizer,
+    flex_reader::read_flex,
+    yl_reader::{action_token, read_yalex, LexerData},
+};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct LexAnalyzer {
@@ -20,26 +27,76 @@ pub struct LexAnalyzer {
     pub token_list: Vec<String>,
     pub actions: HashMap<usize, String>,
     pub header: Vec<String>,
+    // Token returned by each action, so a serialized lexer can be run
+    // without compiling its actions. Empty in .ron written before it.
+    #[serde(default)]
+    pub tokens: HashMap<usize, String>,
 }
 
-pub struct Symbol {
+/// A lexeme. `token` is the id of the rule that matched it and
+/// `token_name` what its action returns: the name of the token, or the
+/// `TokenKind` of a generated parser.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symbol<K = String> {
     pub id: usize,
     pub token: String,
-    pub token_name: String,
+    pub token_name: K,
     pub start: usize,
     pub end: usize,
     pub line: usize,
     pub content: String,
 }
 
+impl Symbol {
+    /// The same lexeme, carrying `kind` as its token
+    pub fn with_kind<K>(self, kind: K) -> Symbol<K> {
+        Symbol {
+            id: self.id,
+            token: self.token,
+            token_name: kind,
+            start: self.start,
+            end: self.end,
+            line: self.line,
+            content: self.content,
+        }
+    }
+}
+
+/// Token type of the symbols the parser takes. `name` is the terminal as
+/// the ACTION table knows it.
+pub trait Kind: Clone + Eq + Hash + Debug {
+    fn name(&self) -> &str;
+}
+
+/// Untyped tokens, named as they are
+impl Kind for String {
+    fn name(&self) -> &str {
+        self
+    }
+}
+
+impl Kind for &str {
+    fn name(&self) -> &str {
+        self
+    }
+}
+
 impl LexAnalyzer {
     pub fn generate(filename: &str, config: &Config) -> LexAnalyzer {
+        // 1. Read YALex
+        let lexer_data = if filename.ends_with(".l") {
+            read_flex(filename)
+        } else {
+            read_yalex(filename)
+        };
+        Self::from_lexer_data(lexer_data, config)
+    }
+
+    /// `generate` for a lexer already read, see `yl_reader::lexer_data`
+    pub fn from_lexer_data(lexer_data: LexerData, config: &Config) -> LexAnalyzer {
         let blue = Style::new().blue().bold();
         let green = Style::new().green().bold();
 
-        // 1. Read YALex
-        let lexer_data = read_yalex(filename);
-
         if config.debug.generation {
             print!("\n");
             print_log("~ L: Tokenizing Regex", 1, 6, &blue);
@@ -81,6 +138,11 @@ impl LexAnalyzer {
         }
 
         // 6. Collect Relevant Info
+        let tokens = lexer_data
+            .actions
+            .iter()
+            .filter_map(|(id, a)| action_token(a).map(|t| (*id, t)))
+            .collect();
         let la = LexAnalyzer {
             map: minimized_map,
             accept: minimized_accept_states,
@@ -88,6 +150,7 @@ impl LexAnalyzer {
             actions: lexer_data.actions,
             header: lexer_data.imports,
             token_list: token_list,
+            tokens,
         };
 
         if config.debug.generation {
@@ -99,6 +162,35 @@ impl LexAnalyzer {
         la
     }
 
+    /// Reads a lexer serialized by `generate`; the tokens of older files are
+    /// taken from their actions
+    pub fn from_ron<P: AsRef<std::path::Path>>(path: P) -> Result<LexAnalyzer, Box<dyn std::error::Error>> {
+        let mut lex: LexAnalyzer = ron::from_str(&std::fs::read_to_string(path)?)?;
+        if lex.tokens.is_empty() {
+            lex.tokens = lex
+                .actions
+                .iter()
+                .filter_map(|(id, a)| action_token(a).map(|t| (*id, t)))
+                .collect();
+        }
+        Ok(lex)
+    }
+
+    /// Reads a lexer written by `generate --binary`, see `codegen::binary`
+    pub fn from_bin<P: AsRef<std::path::Path>>(path: P) -> Result<LexAnalyzer, Box<dyn std::error::Error>> {
+        Ok(decode_lexer(&std::fs::read(path)?)?)
+    }
+
+    /// Token returned by the action `id`, if it returns one
+    pub fn token(&self, id: usize) -> Option<&str> {
+        self.tokens.get(&id).map(|t| t.as_str())
+    }
+
+    /// Names of the tokens returned by the actions
+    pub fn token_names(&self) -> HashSet<String> {
+        self.actions.values().filter_map(|a| action_token(a)).collect()
+    }
+
     fn leer_cadena(&self, input: &str) -> HashSet<char> {
         let mut current_states = HashSet::new();
         let mut next_state = HashSet::new();
@@ -205,7 +297,20 @@ impl LexAnalyzer {
         end
     }
 
+    /// Symbols of `input`, printing the lexic errors if there are any
     pub fn simulate(&self, input: String) -> Option<Vec<Symbol>> {
+        let (symbols, unknown) = self.scan(input);
+        if unknown.is_empty() {
+            Some(symbols)
+        } else {
+            print_lexic_errors(&symbols, &unknown);
+            None
+        }
+    }
+
+    /// Every symbol of `input`, unknown characters included as `!error`
+    /// symbols, and the span of each of those; nothing is printed
+    pub fn scan(&self, input: String) -> (Vec<Symbol>, Vec<(usize, usize)>) {
         let line_breaks = get_line_breaks(&input);
         let mut tk_list: Vec<String> = Vec::new();
         let mut symbols: Vec<Symbol> = Vec::new();
@@ -217,6 +322,22 @@ impl LexAnalyzer {
         let mut is_continuous = false;
         let mut tem_error: (usize, usize) = (0, 0);
         while !condition {
+            // Unknown characters up to the end of the input
+            if last_start >= len {
+                if is_continuous {
+                    unknown.push(tem_error);
+                    symbols.push(Symbol {
+                        id: counter,
+                        token: "!error".to_string(),
+                        start: tem_error.0,
+                        end: tem_error.1,
+                        content: input[tem_error.0..tem_error.1].to_string(),
+                        token_name: String::new(),
+                        line: get_line(&line_breaks, tem_error.1),
+                    });
+                }
+                break;
+            }
             let mut lexem = String::new();
             let mut greedy_match = String::new();
             let mut greedy_end = 0;
@@ -299,12 +420,7 @@ impl LexAnalyzer {
             }
             counter += 1;
         }
-        if unknown.is_empty() {
-            return Some(symbols);
-        } else {
-            print_lexic_errors(&symbols, &unknown);
-            return None
-        }
+        (symbols, unknown)
     }
     fn get_token_type(&self, input: String) -> String {
         let mini = self.asignar_token(&input);
@@ -0,0 +1,552 @@
+//! Tokenizer and recursive-descent parser for the .yal format
+//!
+//! ```text
+//! file    := block? let* rule ("and" rule)* block?
+//! let     := "let" IDENT "=" regex
+//! rule    := "rule" IDENT IDENT* "=" "parse"? "|"? entry ("|" entry)*
+//! entry   := regex block
+//! regex   := concat ("|" concat)*
+//! concat  := postfix+
+//! postfix := atom ("*" | "+" | "?")*
+//! atom    := STRING | CHAR | SET | IDENT | "(" regex ")"
+//! ```
+//! Comments `(* ... *)` are removed beforehand, keeping every position intact.
+
+use std::{collections::HashSet, fmt};
+
+use crate::utility::comments::{strip_comments, YAL_COMMENTS};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Loc {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for Loc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}
+
+/// One item of a character set: a single char or an inclusive range
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetItem {
+    Char(char),
+    Range(char, char),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Regex {
+    // "abc" or 'a', already unescaped
+    Literal(String),
+    Set(Vec<SetItem>),
+    Var(String, Loc),
+    Concat(Vec<Regex>),
+    Union(Vec<Regex>),
+    Kleene(Box<Regex>),
+    Plus(Box<Regex>),
+    Optional(Box<Regex>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LetDef {
+    pub name: String,
+    pub regex: Regex,
+    pub loc: Loc,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub regex: Regex,
+    // Code between the braces, trimmed
+    pub action: String,
+    pub loc: Loc,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub name: String,
+    pub args: Vec<String>,
+    pub entries: Vec<Entry>,
+    pub loc: Loc,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct YalFile {
+    pub header: Option<String>,
+    pub lets: Vec<LetDef>,
+    pub rules: Vec<Rule>,
+    pub trailer: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct YalError {
+    pub msg: String,
+    pub loc: Loc,
+}
+
+impl fmt::Display for YalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.loc, self.msg)
+    }
+}
+
+impl std::error::Error for YalError {}
+
+fn error<T>(loc: Loc, msg: String) -> Result<T, YalError> {
+    Err(YalError { msg, loc })
+}
+
+/*──────────────────────────────────────────────────────────────*/
+/* TOKENIZER                                                    */
+/*──────────────────────────────────────────────────────────────*/
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    Str(String),
+    Set(Vec<SetItem>),
+    Block(String),
+    Eq,
+    Bar,
+    Star,
+    Plus,
+    Question,
+    LParen,
+    RParen,
+    Eof,
+}
+
+impl fmt::Display for Tok {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tok::Ident(s) => write!(f, "`{}`", s),
+            Tok::Str(s) => write!(f, "string {:?}", s),
+            Tok::Set(_) => write!(f, "character set"),
+            Tok::Block(_) => write!(f, "`{{ ... }}` block"),
+            Tok::Eq => write!(f, "`=`"),
+            Tok::Bar => write!(f, "`|`"),
+            Tok::Star => write!(f, "`*`"),
+            Tok::Plus => write!(f, "`+`"),
+            Tok::Question => write!(f, "`?`"),
+            Tok::LParen => write!(f, "`(`"),
+            Tok::RParen => write!(f, "`)`"),
+            Tok::Eof => write!(f, "end of file"),
+        }
+    }
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    loc: Loc,
+}
+
+impl Lexer {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.get(self.pos).copied()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.loc.line += 1;
+            self.loc.col = 1;
+        } else {
+            self.loc.col += 1;
+        }
+        Some(c)
+    }
+
+    fn tokens(mut self) -> Result<Vec<(Tok, Loc)>, YalError> {
+        let mut tokens = Vec::new();
+        loop {
+            while self.peek().is_some_and(char::is_whitespace) {
+                self.bump();
+            }
+            let start = self.loc;
+            let Some(c) = self.bump() else {
+                tokens.push((Tok::Eof, start));
+                return Ok(tokens);
+            };
+            let tok = match c {
+                '=' => Tok::Eq,
+                '|' => Tok::Bar,
+                '*' => Tok::Star,
+                '+' => Tok::Plus,
+                '?' => Tok::Question,
+                '(' => Tok::LParen,
+                ')' => Tok::RParen,
+                '"' => Tok::Str(self.string(start)?),
+                '\'' => Tok::Str(self.char_literal(start)?),
+                '[' => Tok::Set(self.set(start)?),
+                '{' => Tok::Block(self.block(start)?),
+                c if c.is_alphabetic() || c == '_' => {
+                    let mut name = c.to_string();
+                    while let Some(n) = self.peek().filter(|n| n.is_alphanumeric() || *n == '_') {
+                        name.push(n);
+                        self.bump();
+                    }
+                    Tok::Ident(name)
+                }
+                c => return error(start, format!("unexpected character '{}'", c)),
+            };
+            tokens.push((tok, start));
+        }
+    }
+
+    fn escape(&mut self, start: Loc) -> Result<char, YalError> {
+        match self.bump() {
+            Some('n') => Ok('\n'),
+            Some('t') => Ok('\t'),
+            Some('r') => Ok('\r'),
+            // Any other escaped char stands for itself
+            Some(c) => Ok(c),
+            None => error(start, "unterminated escape sequence".to_string()),
+        }
+    }
+
+    fn string(&mut self, start: Loc) -> Result<String, YalError> {
+        let mut content = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(content),
+                Some('\\') => content.push(self.escape(start)?),
+                Some(c) => content.push(c),
+                None => return error(start, "unterminated string literal".to_string()),
+            }
+        }
+    }
+
+    fn char_literal(&mut self, start: Loc) -> Result<String, YalError> {
+        let c = match self.bump() {
+            Some('\\') => self.escape(start)?,
+            Some(c) if c != '\'' => c,
+            _ => return error(start, "empty character literal".to_string()),
+        };
+        if self.bump() != Some('\'') {
+            return error(start, "character literal must hold exactly one char".to_string());
+        }
+        Ok(c.to_string())
+    }
+
+    fn set(&mut self, start: Loc) -> Result<Vec<SetItem>, YalError> {
+        // Every char, and whether it was quoted: a quoted '-' is no range
+        let mut chars: Vec<(char, bool)> = Vec::new();
+        loop {
+            match self.bump() {
+                Some(']') => break,
+                Some('\\') => chars.push((self.escape(start)?, false)),
+                // Quotes inside sets are optional: ['a'-'z'] == [a-z], ['"'] is `"`
+                Some(q) if q == '\'' || q == '"' => loop {
+                    match self.bump() {
+                        Some(c) if c == q => break,
+                        Some('\\') => chars.push((self.escape(start)?, true)),
+                        Some(c) => chars.push((c, true)),
+                        None => return error(start, "unterminated quote in character set".to_string()),
+                    }
+                },
+                Some('^') if chars.is_empty() => {
+                    return error(start, "negated character sets are not supported".to_string())
+                }
+                Some(c) => chars.push((c, false)),
+                None => return error(start, "unterminated character set".to_string()),
+            }
+        }
+        let mut items = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            if i + 2 < chars.len() && chars[i + 1] == ('-', false) {
+                let (from, to) = (chars[i].0, chars[i + 2].0);
+                let same_kind = (from.is_alphabetic() && to.is_alphabetic())
+                    || (from.is_numeric() && to.is_numeric());
+                if from > to || !same_kind {
+                    return error(start, format!("invalid range '{}-{}'", from, to));
+                }
+                items.push(SetItem::Range(from, to));
+                i += 3;
+            } else {
+                items.push(SetItem::Char(chars[i].0));
+                i += 1;
+            }
+        }
+        if items.is_empty() {
+            return error(start, "empty character set".to_string());
+        }
+        Ok(items)
+    }
+
+    // Host code in braces; braces inside nested blocks and literals are balanced
+    fn block(&mut self, start: Loc) -> Result<String, YalError> {
+        let mut content = String::new();
+        let mut depth = 1;
+        loop {
+            let Some(c) = self.bump() else {
+                return error(start, "unterminated `{` block".to_string());
+            };
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(content.trim().to_string());
+                    }
+                }
+                '"' => {
+                    content.push(c);
+                    while let Some(s) = self.bump() {
+                        content.push(s);
+                        if s == '\\' {
+                            if let Some(e) = self.bump() {
+                                content.push(e);
+                            }
+                        } else if s == '"' {
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                // Char literals like '}' or '\'', but not lifetimes like 'a
+                '\'' if self.peek() == Some('\\') || self.chars.get(self.pos + 1) == Some(&'\'') => {
+                    content.push(c);
+                    if let Some(first) = self.bump() {
+                        content.push(first);
+                        if first == '\\' {
+                            if let Some(e) = self.bump() {
+                                content.push(e);
+                            }
+                        }
+                    }
+                    while let Some(s) = self.bump() {
+                        content.push(s);
+                        if s == '\'' {
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            content.push(c);
+        }
+    }
+}
+
+/*──────────────────────────────────────────────────────────────*/
+/* PARSER                                                       */
+/*──────────────────────────────────────────────────────────────*/
+
+const KEYWORDS: [&str; 4] = ["let", "rule", "and", "parse"];
+
+struct Parser {
+    tokens: Vec<(Tok, Loc)>,
+    pos: usize,
+    defined: HashSet<String>,
+}
+
+impl Parser {
+    fn peek(&self) -> &Tok {
+        &self.tokens[self.pos].0
+    }
+
+    fn loc(&self) -> Loc {
+        self.tokens[self.pos].1
+    }
+
+    fn next(&mut self) -> (Tok, Loc) {
+        let tok = self.tokens[self.pos].clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn is_keyword(&self, kw: &str) -> bool {
+        matches!(self.peek(), Tok::Ident(s) if s == kw)
+    }
+
+    fn expect(&mut self, expected: Tok, context: &str) -> Result<(), YalError> {
+        let (tok, loc) = self.next();
+        if tok != expected {
+            return error(loc, format!("expected {} {}, found {}", expected, context, tok));
+        }
+        Ok(())
+    }
+
+    fn ident(&mut self, context: &str) -> Result<(String, Loc), YalError> {
+        match self.next() {
+            (Tok::Ident(name), loc) if !KEYWORDS.contains(&name.as_str()) => Ok((name, loc)),
+            (tok, loc) => error(loc, format!("expected a name {}, found {}", context, tok)),
+        }
+    }
+
+    fn file(&mut self) -> Result<YalFile, YalError> {
+        let header = match self.peek() {
+            Tok::Block(_) => match self.next().0 {
+                Tok::Block(code) => Some(code),
+                _ => unreachable!(),
+            },
+            _ => None,
+        };
+
+        let mut lets = Vec::new();
+        while self.is_keyword("let") {
+            lets.push(self.let_def()?);
+        }
+
+        if !self.is_keyword("rule") {
+            let (tok, loc) = self.next();
+            return error(loc, format!("expected `let` or `rule`, found {}", tok));
+        }
+        let mut rules = vec![self.rule()?];
+        while self.is_keyword("and") {
+            rules.push(self.rule()?);
+        }
+
+        let trailer = match self.next() {
+            (Tok::Block(code), _) => Some(code),
+            (Tok::Eof, _) => return Ok(YalFile { header, lets, rules, trailer: None }),
+            (tok, loc) => return error(loc, format!("expected `|`, `and` or end of file, found {}", tok)),
+        };
+        match self.next() {
+            (Tok::Eof, _) => Ok(YalFile { header, lets, rules, trailer }),
+            (tok, loc) => error(loc, format!("unexpected {} after the trailer", tok)),
+        }
+    }
+
+    fn let_def(&mut self) -> Result<LetDef, YalError> {
+        let loc = self.next().1; // let
+        let (name, name_loc) = self.ident("after `let`")?;
+        if self.defined.contains(&name) {
+            return error(name_loc, format!("variable `{}` is defined twice", name));
+        }
+        self.expect(Tok::Eq, &format!("after `let {}`", name))?;
+        let regex = self.regex()?;
+        self.defined.insert(name.clone());
+        Ok(LetDef { name, regex, loc })
+    }
+
+    fn rule(&mut self) -> Result<Rule, YalError> {
+        let loc = self.next().1; // rule | and
+        let (name, _) = self.ident("for the rule")?;
+        let mut args = Vec::new();
+        while let Tok::Ident(arg) = self.peek() {
+            if KEYWORDS.contains(&arg.as_str()) {
+                break;
+            }
+            args.push(arg.clone());
+            self.next();
+        }
+        self.expect(Tok::Eq, &format!("after `rule {}`", name))?;
+        if self.is_keyword("parse") {
+            self.next();
+        }
+        if *self.peek() == Tok::Bar {
+            self.next();
+        }
+        let mut entries = vec![self.entry()?];
+        while *self.peek() == Tok::Bar {
+            self.next();
+            entries.push(self.entry()?);
+        }
+        Ok(Rule { name, args, entries, loc })
+    }
+
+    fn entry(&mut self) -> Result<Entry, YalError> {
+        let loc = self.loc();
+        let regex = self.regex()?;
+        match self.next() {
+            (Tok::Block(action), _) => Ok(Entry { regex, action, loc }),
+            (tok, loc) => error(loc, format!("expected `{{ action }}` after the regex, found {}", tok)),
+        }
+    }
+
+    fn regex(&mut self) -> Result<Regex, YalError> {
+        let mut options = vec![self.concat()?];
+        // A `|` right after a regex is a union; after an action it starts a new entry
+        while *self.peek() == Tok::Bar {
+            self.next();
+            options.push(self.concat()?);
+        }
+        Ok(if options.len() == 1 { options.remove(0) } else { Regex::Union(options) })
+    }
+
+    fn starts_atom(&self) -> bool {
+        match self.peek() {
+            Tok::Str(_) | Tok::Set(_) | Tok::LParen => true,
+            Tok::Ident(s) => !KEYWORDS.contains(&s.as_str()),
+            _ => false,
+        }
+    }
+
+    fn concat(&mut self) -> Result<Regex, YalError> {
+        if !self.starts_atom() {
+            let (tok, loc) = self.next();
+            return error(loc, format!("expected a regex, found {}", tok));
+        }
+        let mut parts = Vec::new();
+        while self.starts_atom() {
+            parts.push(self.postfix()?);
+        }
+        Ok(if parts.len() == 1 { parts.remove(0) } else { Regex::Concat(parts) })
+    }
+
+    fn postfix(&mut self) -> Result<Regex, YalError> {
+        let mut atom = self.atom()?;
+        loop {
+            atom = match self.peek() {
+                Tok::Star => Regex::Kleene(Box::new(atom)),
+                Tok::Plus => Regex::Plus(Box::new(atom)),
+                Tok::Question => Regex::Optional(Box::new(atom)),
+                _ => return Ok(atom),
+            };
+            self.next();
+        }
+    }
+
+    fn atom(&mut self) -> Result<Regex, YalError> {
+        match self.next() {
+            (Tok::Str(s), loc) => {
+                if s.is_empty() {
+                    return error(loc, "empty string literal in regex".to_string());
+                }
+                Ok(Regex::Literal(s))
+            }
+            (Tok::Set(items), _) => Ok(Regex::Set(items)),
+            (Tok::Ident(name), loc) => {
+                if !self.defined.contains(&name) {
+                    return error(loc, format!("undefined variable `{}`", name));
+                }
+                Ok(Regex::Var(name, loc))
+            }
+            (Tok::LParen, loc) => {
+                let inner = self.regex()?;
+                match self.next() {
+                    (Tok::RParen, _) => Ok(inner),
+                    (tok, _) => error(loc, format!("unclosed `(`, found {}", tok)),
+                }
+            }
+            (tok, loc) => error(loc, format!("expected a regex, found {}", tok)),
+        }
+    }
+}
+
+/// Parses the contents of a .yal file
+pub fn parse_yal(src: &str) -> Result<YalFile, YalError> {
+    let source = strip_comments(src, &YAL_COMMENTS).map_err(|e| YalError {
+        msg: "unterminated comment".to_string(),
+        loc: Loc { line: e.line, col: e.col },
+    })?;
+    let lexer = Lexer {
+        chars: source.chars().collect(),
+        pos: 0,
+        loc: Loc { line: 1, col: 1 },
+    };
+    let mut parser = Parser {
+        tokens: lexer.tokens()?,
+        pos: 0,
+        defined: HashSet::new(),
+    };
+    parser.file()
+}
@@ -1,247 +1,114 @@
-use std::{collections::HashMap, hash::Hash};
-use crate::{utility::reader::read_lines};
+use std::{collections::{BTreeMap, HashMap}, fs};
 
+use super::yl_parser::{parse_yal, Regex, SetItem, YalFile};
 
+#[derive(Debug, PartialEq)]
 pub struct LexerData{
     pub merged: String,
     pub actions: HashMap<usize, String>,
     pub imports: Vec<String>,
 }
 
-#[derive(Eq, Hash, Debug, PartialEq, Clone)]
-pub struct LexemVar{
-    pub id: u8,
-    pub regex: String,
-}
-
 pub struct Lexem{
     pub id: usize,
     pub regex: String,
     pub action: String,
 }
 
-fn clean_rules(rules: &Vec<Lexem>)->Vec<Lexem>{
-    fn clean(reg: String)->String{
-        let mut reg_copy = reg.chars().peekable();
-        let mut new_reg = String::new();
-        while let Some(c) = reg_copy.next(){
-            if c == '\\'{
-                if let Some(c_next) = reg_copy.peek(){
-                    new_reg.push('\\');
-                    new_reg.push(*c_next);
-                    reg_copy.next();
-                }
-            } else {
-                if c != '"'{
-                    new_reg.push(c);
-                }
-            }
+// Characters with a meaning for the regex tokenizer
+const OPERATORS: [char; 13] = ['\\', '?', '#', '*', '|', '+', '(', ')', '[', ']', '{', '}', '"'];
+
+fn push_char(out: &mut String, c: char){
+    match c {
+        '\n' => out.push_str("\\n"),
+        '\t' => out.push_str("\\t"),
+        '\r' => out.push_str("\\r"),
+        c if OPERATORS.contains(&c) => {
+            out.push('\\');
+            out.push(c);
         }
-        
-        
-        new_reg
-    }
-    let mut new_rules: Vec<Lexem> = Vec::new();
-
-    for l in rules{
-        new_rules.push(
-            Lexem { id: l.id, regex: clean(l.regex.clone()), action: l.action.clone() }
-        );
+        c => out.push(c),
     }
-    new_rules
 }
 
-fn trim_ws(content: String)->String{
-    let mut ret = String::new();
-    let mut flag = true;
-    for ch in content.chars(){
-        if flag{
-            if !ch.is_whitespace(){
-                flag=false;
-                ret.push(ch);
-            }
-        } else{
-            ret.push(ch);
-        }
+fn is_atomic(regex: &Regex)->bool{
+    match regex {
+        Regex::Literal(s) => s.chars().count() == 1,
+        Regex::Set(_) | Regex::Var(_, _) | Regex::Union(_) => true,
+        _ => false,
     }
-    ret.trim_end().to_string()
 }
 
-fn encode_vars(vars: Vec<String>)->HashMap<String, LexemVar>{
-    let mut map: HashMap<String,LexemVar> = HashMap::new();
-    for (i,mut v) in vars.into_iter().enumerate(){
-        let mut name = String::new();
-        let mut reg = String::new();
-        let mut section: usize = 0;
-        v.drain(..4);
-        for ch in v.chars(){
-            if section==0{
-                if ch.is_whitespace(){
-                    section = 1;
-                } else{
-                    name.push(ch);
-                }
+/// Writes a parsed regex in the syntax understood by `tokenizer::inf_to_pos`.
+/// Variables are looked up in `vars`, which holds their already rendered form.
+pub fn render_regex(regex: &Regex, vars: &HashMap<String, String>)->String{
+    let mut out = String::new();
+    match regex {
+        Regex::Literal(s) => {
+            for c in s.chars(){
+                push_char(&mut out, c);
             }
-            if section == 1{
-                if ch=='='{
-                    section=2;
-                }
-            }
-            if section ==2 {
-                if ch.is_whitespace(){
-                    section = 3;
-                    continue;
+        }
+        Regex::Set(items) => {
+            let rendered: Vec<String> = items.iter().map(|item| match item {
+                SetItem::Range(from, to) => format!("[{}-{}]", from, to),
+                SetItem::Char(c) => {
+                    let mut s = String::new();
+                    push_char(&mut s, *c);
+                    s
                 }
+            }).collect();
+            if rendered.len() == 1 {
+                out += &rendered[0];
+            } else {
+                out += &format!("({})", rendered.join("|"));
             }
-            if section == 3{
-                reg.push(ch);
-            }
-        }  
-
-        map.insert(
-            name.to_string(), 
-            LexemVar { id: i as u8, regex: reg.to_string()}
-        );
-    }
-    map
-}
-
-fn contains_var(reg_og: String)->(bool,usize, usize){
-    let mut reg = reg_og.clone();
-    let operations: Vec<char> = Vec::from(['|','*','+','?','(',')']);
-    let mut is_range = false;
-    let mut is_literal = false;
-    let mut has_var = false;
-    let mut start: usize = 0;
-    let mut end: usize = 0;
-    let mut last_char: char = '0';
-    for (i,ch) in reg.chars().enumerate(){
-        if !operations.contains(&ch){
-            if ch=='['{
-                is_range = true;
-            }
-            else if ch==']'{
-                is_range = false;
-            }
-            else if ch == '"'{
-                if last_char != '\\'{
-                    is_literal = !is_literal;
-                }  
+        }
+        Regex::Var(name, loc) => {
+            match vars.get(name) {
+                Some(reg) => out += &format!("({})", reg),
+                None => panic!("~ Error Lex: {}: var not found {}", loc, name),
             }
-            else{
-                if !is_range && !is_literal{
-                    has_var = true;
-                    start = i;
-                    break;
-                }
+        }
+        Regex::Concat(parts) => {
+            for p in parts{
+                out += &render_regex(p, vars);
             }
         }
-        last_char = ch;
-    }
-    reg.drain(..start);
-    if !has_var{
-        return (false, 0,0)
-    }
-    for (i,ch) in reg.chars().enumerate(){
-        if operations.contains(&ch){
-            end=i;
-            break;
+        Regex::Union(options) => {
+            let rendered: Vec<String> = options.iter().map(|o| render_regex(o, vars)).collect();
+            out += &format!("({})", rendered.join("|"));
         }
-        if Vec::from(['[',']','\"']).contains(&ch){
-            end = i;
-            break;
+        Regex::Kleene(inner) | Regex::Plus(inner) | Regex::Optional(inner) => {
+            let body = render_regex(inner, vars);
+            let operand = if is_atomic(inner){ body } else { format!("({})", body) };
+            out += &match regex {
+                Regex::Kleene(_) => format!("{}*", operand),
+                Regex::Plus(_) => format!("{}+", operand),
+                // The tokenizer turns `?` into `|empty`, so it needs its own group
+                _ => format!("({}?)", operand),
+            };
         }
     }
-
-    (has_var, start, end+start)
+    out
 }
 
-fn replace_vars(og_reg: String, vars: &HashMap<String, LexemVar>)->String{
-    let mut new_reg = og_reg.clone();
-    loop{
-        let contains = contains_var(new_reg.clone());
-        if contains.0{
-            if contains.1!= contains.2{
-                let sus = &new_reg[contains.1..contains.2];
-                if let Some(sus_r) = vars.get(sus){
-                    let mut tem= String::new();
-                    tem+=  &new_reg[0..contains.1];
-                    tem+= &sus_r.regex;
-                    tem+= &new_reg[contains.2..];
-                    new_reg = tem.clone();
-                } else {
-                    panic!("~ Error Lex: var not found in middle {}",sus);
-                }
-            } else {
-                let sus = &new_reg[contains.1..];
-                if let Some(sus_r) = vars.get(sus){
-                    let mut tem= String::new();
-                    tem+=&new_reg[..contains.1];
-                    tem+= &sus_r.regex;
-                    new_reg = tem.clone();
-                } else {
-                    panic!("~ Error Lex: var not found at start or end {}",sus);
-                }
-            }
-        } else {
-            break
-        }
+fn encode_rules(file: &YalFile)->Vec<Lexem>{
+    // 1. Render variables in definition order, so each one can use the previous
+    let mut vars: HashMap<String, String> = HashMap::new();
+    for l in &file.lets{
+        let reg = render_regex(&l.regex, &vars);
+        vars.insert(l.name.clone(), reg);
     }
-    new_reg
-}
 
-fn encode_rule(rule: Vec<String>, vars: &HashMap<String, LexemVar>)->Vec<Lexem>{
+    // 2. Entries of every rule get consecutive ids
     let mut rule_vec: Vec<Lexem> = Vec::new();
-    for (i, r_) in rule.iter().enumerate(){
-        let mut r = r_.clone();
-        if r.starts_with("|"){
-            r.drain(..2);
-        }
-        let mut reg = String::new();
-        let mut action = String::new();
-        let mut section: usize = 0;
-        let mut is_lit = false;
-        let mut is_range = false;
-        for ch in r.chars(){
-            if section==0{
-                if ch == '\"'{
-                    is_lit = !is_lit;
-                }
-                if ch=='['{
-                    is_range = true;
-                }
-                if ch==']'{
-                    is_range = false;
-                }
-                if !is_range && !is_lit{
-                    if !ch.is_whitespace(){
-                        reg.push(ch);
-                        continue;
-                    } else {
-                        section = 1;
-                    }
-                } else {
-                    reg.push(ch);
-                }
-            }
-            if section==1{
-                if ch=='{'{
-                    section = 2;
-                    continue;
-                }
-            }
-            if section == 2{
-                action.push(ch);
-            }
-        }
-        let mut cl_action = trim_ws(action);
-        cl_action.pop();
-        cl_action = trim_ws(cl_action);
-        let cl_reg = replace_vars(reg, &vars);
-
-        rule_vec.push(
-            Lexem { id: i, regex: cl_reg, action: cl_action }
-        )
+    for entry in file.rules.iter().flat_map(|r| &r.entries){
+        rule_vec.push(Lexem {
+            id: rule_vec.len(),
+            regex: render_regex(&entry.regex, &vars),
+            action: entry.action.clone(),
+        });
     }
     rule_vec
 }
@@ -249,7 +116,7 @@ fn encode_rule(rule: Vec<String>, vars: &HashMap<String, LexemVar>)->Vec<Lexem>{
 fn genereate_action_table(rules: &Vec<Lexem>, imports: Vec<String>)->LexerData{
     let mut merged = String::new();
     let mut actions: HashMap<usize, String> = HashMap::new();
-    
+
     for r in rules{
         merged+=&format!("(({}){{{}}})|",r.regex, r.id);
         actions.insert(r.id, r.action.clone());
@@ -263,56 +130,68 @@ fn genereate_action_table(rules: &Vec<Lexem>, imports: Vec<String>)->LexerData{
     }
 }
 
-pub fn read_yalex(filename:&str)->LexerData{
-    let mut section:usize= 0;
-    // 1. Section yal content
-    let mut header: Vec<String> = Vec::new();
-    let mut vars: Vec<String> = Vec::new();
-    let mut rule: Vec<String> = Vec::new();
-    if let Ok(lines) = read_lines(filename){
-        for line in lines{
-            if let Ok(raw_content) = line{
-                // Remove inital whitespace of line
-                let content = trim_ws(raw_content);
-                if !content.clone().starts_with("(*"){ // if its not a comment
-                    if section == 0 { // Header section
-                        if !content.starts_with("{"){
-                            if content.starts_with("}"){
-                                section = 1;
-                            } else{
-                                header.push(content.clone());
-                            }
-                        }
-                    }
-                    if section == 1{ // Var section
-                        if content.starts_with("let"){
-                            vars.push(content.clone());
-                        } else {
-                            if content.starts_with("rule"){
-                                section = 2;
-                                continue;
-                            }
-                        }
-                    }
-                    if section == 2{ // Rule section
-                        rule.push(content.clone());
-                    }
+/// Token name returned by an action like `return "ID";`, if any; escapes
+/// in the string literal are undone
+pub fn action_token(action: &str)->Option<String>{
+    let after = &action[action.find("return")? + "return".len()..];
+    let mut chars = after[after.find('"')? + 1..].chars();
+    let mut token = String::new();
+    loop{
+        match chars.next()?{
+            '"' => return Some(token),
+            '\\' => match chars.next()?{
+                'n' => token.push('\n'),
+                't' => token.push('\t'),
+                'r' => token.push('\r'),
+                '0' => token.push('\0'),
+                'u' => {
+                    let hex: String = chars.by_ref().skip(1).take_while(|c| *c != '}').collect();
+                    token.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
                 }
+                c => token.push(c),
+            },
+            c => token.push(c),
+        }
+    }
+}
+
+/// Regex of every token, as the lexer gets it; tokens returned by several
+/// rules get them all, in order
+pub fn token_patterns(file: &YalFile)->BTreeMap<String, String>{
+    let mut patterns: BTreeMap<String, String> = BTreeMap::new();
+    for r in encode_rules(file){
+        if let Some(token) = action_token(&r.action){
+            let pattern = patterns.entry(token).or_default();
+            if !pattern.is_empty(){
+                *pattern += " | ";
             }
+            *pattern += &r.regex;
         }
     }
-    // 2. Encode variable
-    let var_vec = encode_vars(vars);
-    // for (k,v) in &var_vec{
-    //     println!("Name: {}, Reg: {}",k,v.regex);
-    // }
-    // 3. Encode Rules
-    let rule_vec = clean_rules(
-        &encode_rule(rule, &var_vec));
-    // for v in &rule_vec{
-    //     println!("ID: {}, Reg: {}, action: {},",v.id, v.regex, v.action);
-    // }
-    let lexdata = genereate_action_table(&rule_vec, header);
-    lexdata
+    patterns
+}
 
-}
\ No newline at end of file
+/// Builds the lexer data of an already parsed .yal file
+pub fn lexer_data(file: &YalFile)->LexerData{
+    let rule_vec = encode_rules(file);
+    // Header lines are the imports of the generated parser
+    let header: Vec<String> = file.header.iter()
+        .flat_map(|h| h.lines())
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+    genereate_action_table(&rule_vec, header)
+}
+
+pub fn read_yal_file(filename:&str)->YalFile{
+    let source = fs::read_to_string(filename)
+        .unwrap_or_else(|e| panic!("~ Error Lex: couldn't read '{}': {}", filename, e));
+    match parse_yal(&source) {
+        Ok(file) => file,
+        Err(e) => panic!("~ Error Lex: {}:{}", filename, e),
+    }
+}
+
+pub fn read_yalex(filename:&str)->LexerData{
+    lexer_data(&read_yal_file(filename))
+}
@@ -2,10 +2,18 @@ pub mod utility{
     pub mod reader;
     pub mod writer;
     pub mod read_config;
+    pub mod comments;
+    pub mod exporter;
+    pub mod cli;
+    pub mod repl;
+    pub mod watch;
+    pub mod json_output;
 }
 
 pub mod lex{
     pub mod yl_reader;
+    pub mod yl_parser;
+    pub mod flex_reader;
     pub mod direct_afd;
     pub mod grammar_tree;
     pub mod tokenizer;
@@ -15,9 +23,18 @@ pub mod lex{
 
 pub mod syn{
     pub mod yp_reader;
+    pub mod yp_parser;
+    pub mod yp_writer;
+    pub mod bison_reader;
     pub mod slr_automata;
     pub mod lalr_automata;
     pub mod first_follow;
+    pub mod validation;
+    pub mod precedence;
+    pub mod conflicts;
+    pub mod compress;
+    pub mod transform;
+    pub mod parse_tree;
     pub mod syn_analyzer;
 }
 
@@ -26,3 +43,10 @@ pub mod view{
     pub mod print_table;
     pub mod logging;
 }
+
+pub mod codegen{
+    pub mod build;
+    pub mod emit;
+    pub mod binary;
+    pub mod runtime;
+}
@@ -1,41 +1,380 @@
-use core::panic;
-use std::{env, fs};
-use std::{collections::HashMap, fs::File};
-use std::io::Write;
-use syntax_analyzer::lex::lex_analyzer::LexAnalyzer;
-use syntax_analyzer::syn::syn_analyzer::SynAnalyzer;
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::Path,
+    process,
+    time::Duration,
+};
+
+use prettytable::{row, Table};
 use ron::ser::{to_string_pretty, PrettyConfig};
-use syntax_analyzer::utility::read_config::fetch_config;
+use syntax_analyzer::{
+    codegen::{
+        binary::{encode_lexer, encode_parser},
+        emit::{emit_crate, Runtime, TableFormat},
+        runtime::{self, Lexer},
+    },
+    lex::{
+        flex_reader::{read_flex, read_flex_file},
+        lex_analyzer::LexAnalyzer,
+        yl_reader::{action_token, read_yal_file, read_yalex},
+    },
+    syn::{
+        bison_reader::read_bison,
+        compress::CompressedTables,
+        conflicts::find_conflicts,
+        first_follow::{find_first, find_follow},
+        syn_analyzer::SynAnalyzer,
+        transform::transform_grammar,
+        validation::{validate_grammar, Severity},
+        yp_reader::{read_grammar, read_yalpar},
+        yp_writer::write_yalpar,
+    },
+    utility::{
+        cli::{guarded, parse_args, readable, Cli, CliError, Command, Options, OutputFormat, USAGE},
+        exporter::{antlr4, w3c_ebnf},
+        json_output::Report,
+        read_config::{commented_default, config_candidates, grammar_name, Config},
+        repl::Repl,
+        watch::{Snapshot, Watcher},
+    },
+    view::{
+        logging::print_grammar_issues,
+        print_table::{print_parse_steps, print_symbol_table},
+    },
+};
 
 const LEX_RON_PATH: &str = "./src/bin/lex_analyzer.ron";
 const SYN_RON_PATH: &str = "./src/bin/syn_analyzer.ron";
+const LEX_BIN_PATH: &str = "./src/bin/lex_analyzer.bin";
+const SYN_BIN_PATH: &str = "./src/bin/syn_analyzer.bin";
 const PARSER_PATH: &str = "./src/bin/parser.rs";
 
 fn main(){
-    // 1. Fetch Arguments
-    let args: Vec<String> = env::args().collect();
-    if args.len()!=3{
-        panic!("Arguments must be 'cargo run --bin syntax_analyzer -- ./path/to/lex.yal ./path/to/syn.yalp'")
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let code = match parse_args(&args).and_then(run) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            e.exit_code()
+        }
+    };
+    process::exit(code);
+}
+
+fn run(cli: Cli)->Result<(), CliError>{
+    let options = &cli.options;
+    // A missing file is an I/O error, not one of the grammar; JSON parses
+    // report it in their output
+    if options.format == OutputFormat::Text {
+        cli.command.inputs().into_iter().try_for_each(readable)?;
     }
-    let lex_path = &args[1];
-    let syn_path = &args[2];
+    match cli.command {
+        Command::Help => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Command::Generate { lex, syn } => generate(&lex, &syn, options),
+        Command::Parse { lex, syn, input } if options.format != OutputFormat::Text => parse_json(&lex, &syn, &input, options),
+        Command::Parse { lex, syn, input } => parse(&lex, &syn, &input, &options.config(&[&lex, &syn])?),
+        Command::Check { lex, syn } => check(&lex, &syn, &options.config(&[&lex, &syn])?, options.quiet),
+        Command::Repl { lex, syn } => {
+            let mut repl = Repl::load(&lex, &syn, options.config(&[&lex, &syn])?)?;
+            repl.run(io::stdin().lock(), io::stdout())?;
+            Ok(())
+        }
+        Command::ExplainConflicts { syn } => explain_conflicts(&syn, &options.config(&[&syn])?),
+        Command::Render { files } => {
+            let grammars: Vec<&str> = files.iter().map(|f| f.as_str()).collect();
+            render(&files, &options.config(&grammars)?, options.out_dir.as_deref().unwrap_or("./graph"))
+        }
+        Command::FirstFollow { syn } => first_follow(&syn),
+        Command::Transform { syn, out } => transform(&syn, &out),
+        Command::Bison { input, out } => {
+            // Semantic actions have no place in .yalp and are dropped
+            let grammar = guarded(|| read_bison(&input).grammar)?;
+            if !grammar.precedence.is_empty() && !options.quiet {
+                eprintln!("~ Warning Bison: .yalp has no precedence, conflicts '{}' resolves with it stay in '{}'", input, out);
+            }
+            guarded(|| write_yalpar(&out, &grammar))
+        }
+        Command::Export { format, syn, lex, out } => export(&format, &syn, &lex, &out),
+        Command::ConfigInit { path } => config_init(&path),
+    }
+}
 
-    // 2. fetch Config
-    let config = fetch_config();
+// From the grammar files, or already generated when given the .ron
+fn analyzers(lex_path: &str, syn_path: &str, config: &Config)->Result<(LexAnalyzer, SynAnalyzer), CliError>{
+    let serialized = |path: &str, e: Box<dyn std::error::Error>| CliError::Io(format!("couldn't read '{}': {}", path, e));
+    let extension = |path: &str| Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("").to_string();
+    let lex = match extension(lex_path).as_str() {
+        "ron" => LexAnalyzer::from_ron(lex_path).map_err(|e| serialized(lex_path, e))?,
+        "bin" => LexAnalyzer::from_bin(lex_path).map_err(|e| serialized(lex_path, e))?,
+        _ => guarded(|| LexAnalyzer::generate(lex_path, &config.for_grammar(lex_path)))?,
+    };
+    let syn = match extension(syn_path).as_str() {
+        "ron" => SynAnalyzer::from_ron(syn_path).map_err(|e| serialized(syn_path, e))?,
+        "bin" => SynAnalyzer::from_bin(syn_path).map_err(|e| serialized(syn_path, e))?,
+        _ => guarded(|| SynAnalyzer::generate(syn_path, &config.for_grammar(syn_path), Some(&lex.token_names())))?,
+    };
+    Ok((lex, syn))
+}
+
+// Into src/bin as before, or into a crate of its own with --out-dir
+fn generate(lex_path: &str, syn_path: &str, options: &Options)->Result<(), CliError>{
+    if options.watch {
+        return watch(lex_path, syn_path, options);
+    }
+    let (la_raw, sa_raw) = analyzers(lex_path, syn_path, &options.config(&[lex_path, syn_path])?)?;
+    write_analyzers(la_raw, sa_raw, options)
+}
 
-    // 3. Generate
-    let la_raw = LexAnalyzer::generate(&lex_path, &config);
-    let la_serialized = to_string_pretty(&la_raw, PrettyConfig::default()).unwrap();
-    let mut l_file = File::create(LEX_RON_PATH).unwrap();
-    l_file.write_all(la_serialized.as_bytes()).unwrap();
+fn write_analyzers(la_raw: LexAnalyzer, mut sa_raw: SynAnalyzer, options: &Options)->Result<(), CliError>{
+    // The size is reported either way, to tell whether --compress pays off
+    let raw_entries = sa_raw.action.len() + sa_raw.goto.len();
+    let packed = CompressedTables::new(&sa_raw.action, &sa_raw.goto);
+    if !options.quiet {
+        println!(
+            "~ ACTION/GOTO: {} cells ({} entries), {} compressed",
+            packed.raw_cells,
+            raw_entries,
+            packed.cells()
+        );
+    }
+    if options.compress {
+        sa_raw.compress();
+    }
+    if let Some(out_dir) = &options.out_dir {
+        let format = match (options.static_tables, options.binary) {
+            (true, _) => TableFormat::Static,
+            (_, true) => TableFormat::Binary,
+            _ => TableFormat::Ron,
+        };
+        // A directory is a checkout, anything else a version
+        let runtime = match &options.runtime {
+            Some(r) if Path::new(r).is_dir() => Runtime::Path(r.into()),
+            Some(r) => Runtime::Version(r.clone()),
+            None => Runtime::local(),
+        };
+        emit_crate(Path::new(out_dir), &la_raw, &sa_raw, format, &runtime)?;
+        return Ok(());
+    }
+    let to_ron = |e: ron::Error| CliError::Io(e.to_string());
+    fs::write(LEX_RON_PATH, to_string_pretty(&la_raw, PrettyConfig::default()).map_err(to_ron)?)?;
+    fs::write(SYN_RON_PATH, to_string_pretty(&sa_raw, PrettyConfig::default()).map_err(to_ron)?)?;
+    // parser.rs reads the .ron, the .bin are for `parse` and other programs
+    if options.binary {
+        fs::write(LEX_BIN_PATH, encode_lexer(&la_raw))?;
+        fs::write(SYN_BIN_PATH, encode_parser(&sa_raw))?;
+    }
+    write_to_main(PARSER_PATH, la_raw.header, la_raw.actions)?;
+    Ok(())
+}
 
-    let sa_raw = SynAnalyzer::generate(&syn_path, &config);
-    let sa_serialized = to_string_pretty(&sa_raw, PrettyConfig::default()).unwrap();
-    let mut s_file = File::create(SYN_RON_PATH).unwrap();
-    s_file.write_all(sa_serialized.as_bytes()).unwrap();
+// Generates on every change of the files, printing what changed. Errors
+// are reported and the files watched again; changes are always shown
+// against the last generation without errors.
+fn watch(lex_path: &str, syn_path: &str, options: &Options)->Result<(), CliError>{
+    // Config files that don't exist yet are watched too
+    let configs: Vec<String> = config_candidates(&[lex_path, syn_path], options.config.as_deref())
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect();
+    let mut files = vec![lex_path, syn_path];
+    files.extend(configs.iter().map(|c| c.as_str()));
+    files.extend(options.sample.as_deref());
+    let mut watcher = Watcher::new(&files);
+    let mut last: Option<Snapshot> = None;
+    loop {
+        match options.config(&[lex_path, syn_path]) {
+            Ok(config) => {
+                let (snapshot, built) = Snapshot::take(lex_path, syn_path, &config, options.sample.as_deref());
+                if let Some((la_raw, sa_raw)) = built {
+                    if let Err(e) = write_analyzers(la_raw, sa_raw, options) {
+                        eprintln!("{}", e);
+                    }
+                }
+                match &last {
+                    Some(last) => snapshot.diff(last).iter().for_each(|l| println!("{}", l)),
+                    None => println!("{}", snapshot.summary()),
+                }
+                if snapshot.error.is_none() {
+                    last = Some(snapshot);
+                }
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+        let changed = watcher.wait(Duration::from_millis(500));
+        let names: Vec<String> = changed.iter().map(|p| p.display().to_string()).collect();
+        println!("\n~ Changed: {}", names.join(", "));
+    }
+}
 
-    // Generate Parser
-    let _ = write_to_main(PARSER_PATH, la_raw.header, la_raw.actions);
+// Parses an input right away, no parser to compile
+fn parse(lex_path: &str, syn_path: &str, input_path: &str, config: &Config)->Result<(), CliError>{
+    let (lex, syn) = analyzers(lex_path, syn_path, config)?;
+    let contents = fs::read_to_string(input_path)
+        .map_err(|e| CliError::Io(format!("couldn't read '{}': {}", input_path, e)))?;
+    let lexer = Lexer::new(&lex, |id| lex.token(id));
+    let (symbols, steps, error) = runtime::parse(&lexer, &syn, &contents).ok_or(CliError::Lexical)?;
+    // The tokens are named after the lexer, the steps after the grammar
+    if let Some(path) = &config.for_grammar(lex_path).vis.symbol_table {
+        print_symbol_table(&symbols, path)?;
+    }
+    if let Some(path) = &config.for_grammar(syn_path).vis.parse_steps {
+        print_parse_steps(&steps, path)?;
+    }
+    match error {
+        Some(error) => {
+            println!("{}", error.visual());
+            Err(CliError::Syntax(error.message))
+        }
+        None => Ok(()),
+    }
+}
+
+// `parse` for programs: every result on stdout as JSON, even the errors
+fn parse_json(lex_path: &str, syn_path: &str, input_path: &str, options: &Options)->Result<(), CliError>{
+    let report = options
+        .config(&[lex_path, syn_path])
+        .and_then(|config| {
+            readable(lex_path)?;
+            readable(syn_path)?;
+            analyzers(lex_path, syn_path, &config)
+        })
+        .and_then(|(lex, syn)| {
+            let contents = fs::read_to_string(input_path)
+                .map_err(|e| CliError::Io(format!("couldn't read '{}': {}", input_path, e)))?;
+            Ok(Report::new(&Lexer::new(&lex, |id| lex.token(id).map(String::from)), &syn, &contents))
+        })
+        .unwrap_or_else(|e| Report::failed(&e));
+    match options.format {
+        OutputFormat::JsonLines => report.to_json_lines().iter().for_each(|l| println!("{}", l)),
+        _ => println!("{}", serde_json::to_string_pretty(&report.to_json()).unwrap()),
+    }
+    report.error().map_or(Ok(()), Err)
+}
+
+// Every problem of the grammar, and the conflicts of its tables
+fn check(lex_path: &str, syn_path: &str, config: &Config, quiet: bool)->Result<(), CliError>{
+    let lexer = guarded(|| if lex_path.ends_with(".l") { read_flex(lex_path) } else { read_yalex(lex_path) })?;
+    let tokens = lexer.actions.values().filter_map(|a| action_token(a)).collect();
+    let grammar = guarded(|| read_grammar(syn_path))?;
+    let issues = validate_grammar(&grammar, Some(&tokens));
+    print_grammar_issues(&issues);
+    let errors = issues.iter().filter(|i| i.severity() == Severity::Error).count();
+    if errors > 0 {
+        return Err(CliError::Grammar(format!("'{}' has {} grammar errors", syn_path, errors)));
+    }
+    let conflicts = find_conflicts(&grammar, config.parse_method);
+    for c in &conflicts {
+        println!("{}", c);
+    }
+    if !conflicts.is_empty() {
+        return Err(CliError::Grammar(format!("'{}' has {} {:?} conflicts", syn_path, conflicts.len(), config.parse_method)));
+    }
+    if !quiet {
+        println!("~ '{}' and '{}' are fine for {:?}", lex_path, syn_path, config.parse_method);
+    }
+    Ok(())
+}
+
+fn explain_conflicts(syn_path: &str, config: &Config)->Result<(), CliError>{
+    let grammar = guarded(|| read_grammar(syn_path))?;
+    let conflicts = find_conflicts(&grammar, config.parse_method);
+    if conflicts.is_empty() {
+        println!("~ No {:?} conflicts in '{}'", config.parse_method, syn_path);
+        return Ok(());
+    }
+    for c in &conflicts {
+        println!("{}\n", c);
+    }
+    Err(CliError::Grammar(format!("{} {:?} conflicts", conflicts.len(), config.parse_method)))
+}
+
+// Generates with every drawing of the config pointed at `out_dir`, named
+// after each file so several grammars can share it
+fn render(files: &[String], config: &Config, out_dir: &str)->Result<(), CliError>{
+    let at = |name: &str| Some(format!("{}/{{grammar}}_{}", out_dir.trim_end_matches('/'), name));
+    fs::create_dir_all(out_dir)?;
+    let mut template = Config { parse_method: config.parse_method, ..Config::default() };
+    template.vis.dfa = at("dfa");
+    template.vis.grammar_tree = at("grammar_tree.txt");
+    template.vis.slr_png = at("slr");
+    template.vis.lalr_png = at("lalr");
+    template.vis.parse_table = at("parse_table.txt");
+    for file in files {
+        let mut config = template.clone();
+        config.expand_paths(&grammar_name(file));
+        let config = &config;
+        if file.ends_with(".yal") || file.ends_with(".l") {
+            guarded(|| { LexAnalyzer::generate(file, config); })?;
+        } else if file.ends_with(".yalp") || file.ends_with(".y") {
+            guarded(|| { SynAnalyzer::generate(file, config, None); })?;
+        } else {
+            return Err(CliError::Usage(format!("can't render '{}', expected a .yal, .l, .yalp or .y", file)));
+        }
+    }
+    Ok(())
+}
+
+fn first_follow(syn_path: &str)->Result<(), CliError>{
+    let grammar = guarded(|| read_grammar(syn_path))?;
+    let first = find_first(grammar.productions.clone(), grammar.terminals.clone(), grammar.non_terminals.clone());
+    let follow = find_follow(&grammar.productions, &grammar.terminals, &grammar.non_terminals, &first, &grammar.init_symbol);
+    let set = |s: Option<&std::collections::HashSet<String>>| {
+        let mut v: Vec<&String> = s.into_iter().flatten().collect();
+        v.sort();
+        v.iter().map(|t| t.as_str()).collect::<Vec<_>>().join(" ")
+    };
+    let mut heads: Vec<&String> = grammar.non_terminals.iter().collect();
+    heads.sort();
+    let mut table = Table::new();
+    table.add_row(row!["Non Terminal", "FIRST", "FOLLOW"]);
+    for nt in heads {
+        table.add_row(row![nt, set(first.get(nt)), set(follow.get(nt))]);
+    }
+    table.printstd();
+    Ok(())
+}
+
+// Writes the grammar as ANTLR4 (`antlr4`) or W3C EBNF (`ebnf`)
+fn export(format: &str, syn_path: &str, lex_path: &str, out_path: &str)->Result<(), CliError>{
+    let grammar = guarded(|| read_grammar(syn_path))?;
+    let lexer = guarded(|| if lex_path.ends_with(".l") { read_flex_file(lex_path) } else { read_yal_file(lex_path) })?;
+    let content = match format {
+        "antlr4" => {
+            // ANTLR wants the grammar named as its file
+            let name = Path::new(out_path).file_stem().and_then(|s| s.to_str()).unwrap_or("Grammar");
+            antlr4(name, &grammar, Some(&lexer))
+        }
+        "ebnf" => w3c_ebnf(&grammar, Some(&lexer)),
+        _ => return Err(CliError::Usage(format!("export format must be 'antlr4' or 'ebnf', found '{}'", format))),
+    };
+    fs::write(out_path, content).map_err(|e| CliError::Io(format!("couldn't write '{}': {}", out_path, e)))
+}
+
+// Writes the default config with every key commented, never over a file
+fn config_init(path: &str)->Result<(), CliError>{
+    let content = commented_default(Path::new(path)).map_err(CliError::Usage)?;
+    if Path::new(path).exists() {
+        return Err(CliError::Io(format!("'{}' already exists", path)));
+    }
+    fs::write(path, content).map_err(|e| CliError::Io(format!("couldn't write '{}': {}", path, e)))?;
+    println!("~ Wrote '{}'", path);
+    Ok(())
+}
+
+// Rewrites the grammar for LL-style parsers and writes it back as .yalp
+fn transform(syn_path: &str, out_path: &str)->Result<(), CliError>{
+    let grammar = guarded(|| read_yalpar(syn_path))?;
+    let issues = validate_grammar(&grammar, None);
+    print_grammar_issues(&issues);
+    if issues.iter().any(|i| i.severity() == Severity::Error) {
+        return Err(CliError::Grammar(format!("'{}' has grammar errors", syn_path)));
+    }
+    let transformed = transform_grammar(&grammar);
+    guarded(|| write_yalpar(out_path, &transformed))
 }
 
 fn write_to_main(
@@ -94,13 +433,8 @@ const SYN_RON_PATH: &str = \"./src/bin/syn_analyzer.ron\";\n\n";
             let tem = actions(s.token.parse::<i32>().unwrap());
             if !tem.is_empty() {
                 symbol_table.push(Symbol {
-                    id: s.id,
-                    token: s.token.clone(),
-                    start: s.start,
-                    end: s.end,
-                    content: s.content.clone(),
                     token_name: tem.to_string(),
-                    line: s.line,
+                    ..s.clone()
                 });
             }
         }
@@ -121,9 +455,9 @@ const SYN_RON_PATH: &str = \"./src/bin/syn_analyzer.ron\";\n\n";
             let _steps_rslt = print_table::print_parse_steps(&steps, &path);
         }
 
-        if let Some((visual_msg, detailed_msg)) = error_msg {
-            println!(\"{}\", visual_msg);
-            println!(\"{}\", detailed_msg);
+        if let Some(error) = error_msg {
+            println!(\"{}\", error.visual());
+            println!(\"{}\", error.message);
         };
     }
     Ok(())
@@ -0,0 +1,534 @@
+//! Importer for Bison/Yacc .y grammars
+//!
+//! Only what the generators can use is read: `%token`, the precedence
+//! declarations (`%left`, `%right`, `%nonassoc`, `%precedence`), `%start` and
+//! the rules. Every other directive, the `%{ ... %}` prologue and the
+//! epilogue after the second `%%` are skipped. Semantic actions are kept as
+//! opaque blocks next to the grammar. Character literals such as `'+'` become
+//! terminals named with their quotes, as in .yalp.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+};
+
+use super::{precedence::Precedence, yp_parser::YalpError, yp_reader::GrammarInfo};
+use crate::lex::yl_parser::Loc;
+
+/// Semantic action of a rule, `{ ... }` braces included
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionBlock {
+    pub head: String,
+    // Index of the alternative among the ones of `head`
+    pub alternative: usize,
+    // Symbols before the block; less than the body length for mid-rule actions
+    pub position: usize,
+    pub code: String,
+    pub loc: Loc,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BisonGrammar {
+    pub grammar: GrammarInfo,
+    pub actions: Vec<ActionBlock>,
+    // (`%left`, [symbols]) in declaration order, lowest precedence first
+    pub precedence: Vec<(String, Vec<String>)>,
+}
+
+fn error<T>(loc: Loc, msg: String) -> Result<T, YalpError> {
+    Err(YalpError { msg, loc })
+}
+
+/*──────────────────────────────────────────────────────────────*/
+/* TOKENIZER                                                    */
+/*──────────────────────────────────────────────────────────────*/
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    // 'x', quotes included
+    Char(String),
+    // "alias", quotes included
+    Str(String),
+    Directive(String),
+    Tag,
+    Int,
+    // [name] after a symbol
+    NamedRef,
+    Action(String),
+    Colon,
+    Bar,
+    Semicolon,
+    Separator,
+    Eof,
+}
+
+impl fmt::Display for Tok {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tok::Ident(s) | Tok::Char(s) | Tok::Str(s) | Tok::Directive(s) => write!(f, "`{}`", s),
+            Tok::Tag => write!(f, "a `<type>` tag"),
+            Tok::Int => write!(f, "a number"),
+            Tok::NamedRef => write!(f, "a `[name]` reference"),
+            Tok::Action(_) => write!(f, "an action block"),
+            Tok::Colon => write!(f, "`:`"),
+            Tok::Bar => write!(f, "`|`"),
+            Tok::Semicolon => write!(f, "`;`"),
+            Tok::Separator => write!(f, "`%%`"),
+            Tok::Eof => write!(f, "end of file"),
+        }
+    }
+}
+
+struct Scanner {
+    chars: Vec<char>,
+    i: usize,
+    loc: Loc,
+}
+
+impl Scanner {
+    fn peek_at(&self, n: usize) -> Option<char> {
+        self.chars.get(self.i + n).copied()
+    }
+
+    fn advance(&mut self, n: usize) {
+        for _ in 0..n {
+            if self.chars[self.i] == '\n' {
+                self.loc.line += 1;
+                self.loc.col = 1;
+            } else {
+                self.loc.col += 1;
+            }
+            self.i += 1;
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> String {
+        let start = self.i;
+        while self.peek_at(0).is_some_and(&f) {
+            self.advance(1);
+        }
+        self.chars[start..self.i].iter().collect()
+    }
+
+    // Skips `/* */` and `//` comments; false if there is none here
+    fn comment(&mut self) -> Result<bool, YalpError> {
+        let start = self.loc;
+        match (self.peek_at(0), self.peek_at(1)) {
+            (Some('/'), Some('*')) => {
+                self.advance(2);
+                while !(self.peek_at(0) == Some('*') && self.peek_at(1) == Some('/')) {
+                    if self.peek_at(0).is_none() {
+                        return error(start, "unterminated comment".to_string());
+                    }
+                    self.advance(1);
+                }
+                self.advance(2);
+                Ok(true)
+            }
+            (Some('/'), Some('/')) => {
+                self.take_while(|c| c != '\n');
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    // 'x' or "x", escapes included
+    fn quoted(&mut self) -> Result<String, YalpError> {
+        let start = (self.i, self.loc);
+        let quote = self.chars[self.i];
+        self.advance(1);
+        loop {
+            match self.peek_at(0) {
+                Some('\\') if self.peek_at(1).is_some() => self.advance(2),
+                Some(c) if c == quote => break,
+                Some('\n') | None => return error(start.1, "unterminated quoted symbol".to_string()),
+                _ => self.advance(1),
+            }
+        }
+        self.advance(1);
+        Ok(self.chars[start.0..self.i].iter().collect())
+    }
+
+    // { ... } with nested braces; strings and comments may contain braces
+    fn action(&mut self) -> Result<String, YalpError> {
+        let start = (self.i, self.loc);
+        let mut depth = 0;
+        loop {
+            if self.comment()? {
+                continue;
+            }
+            match self.peek_at(0) {
+                Some('{') => {
+                    depth += 1;
+                    self.advance(1);
+                }
+                Some('}') => {
+                    depth -= 1;
+                    self.advance(1);
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                Some('\'') | Some('"') => {
+                    self.quoted()?;
+                }
+                Some(_) => self.advance(1),
+                None => return error(start.1, "unterminated action block".to_string()),
+            }
+        }
+        Ok(self.chars[start.0..self.i].iter().collect())
+    }
+
+    fn tokens(&mut self) -> Result<Vec<(Tok, Loc)>, YalpError> {
+        let mut tokens = Vec::new();
+        let mut separators = 0;
+        while let Some(c) = self.peek_at(0) {
+            let start = self.loc;
+            if c.is_whitespace() {
+                self.advance(1);
+                continue;
+            }
+            if self.comment()? {
+                continue;
+            }
+            let tok = match c {
+                ':' => {
+                    self.advance(1);
+                    Tok::Colon
+                }
+                '|' => {
+                    self.advance(1);
+                    Tok::Bar
+                }
+                ';' => {
+                    self.advance(1);
+                    Tok::Semicolon
+                }
+                '%' if self.peek_at(1) == Some('%') => {
+                    // Everything after the second %% is C code
+                    separators += 1;
+                    if separators == 2 {
+                        break;
+                    }
+                    self.advance(2);
+                    Tok::Separator
+                }
+                '%' if self.peek_at(1) == Some('{') => {
+                    // Prologue, copied verbatim by Bison and skipped here
+                    while !(self.peek_at(0) == Some('%') && self.peek_at(1) == Some('}')) {
+                        if self.peek_at(0).is_none() {
+                            return error(start, "unterminated `%{` prologue".to_string());
+                        }
+                        self.advance(1);
+                    }
+                    self.advance(2);
+                    continue;
+                }
+                '%' => {
+                    self.advance(1);
+                    let name = self.take_while(|c| c.is_alphanumeric() || c == '_' || c == '-');
+                    if name.is_empty() {
+                        return error(start, "expected a directive name after `%`".to_string());
+                    }
+                    Tok::Directive(format!("%{}", name))
+                }
+                '<' => {
+                    self.take_while(|c| c != '>' && c != '\n');
+                    if self.peek_at(0) != Some('>') {
+                        return error(start, "unterminated `<type>` tag".to_string());
+                    }
+                    self.advance(1);
+                    Tok::Tag
+                }
+                '[' => {
+                    self.take_while(|c| c != ']' && c != '\n');
+                    if self.peek_at(0) != Some(']') {
+                        return error(start, "unterminated `[name]` reference".to_string());
+                    }
+                    self.advance(1);
+                    Tok::NamedRef
+                }
+                '{' => Tok::Action(self.action()?),
+                '\'' => Tok::Char(self.quoted()?),
+                '"' => Tok::Str(self.quoted()?),
+                c if c.is_ascii_digit() => {
+                    self.take_while(|c| c.is_ascii_alphanumeric());
+                    Tok::Int
+                }
+                c if c.is_alphabetic() || c == '_' || c == '.' => {
+                    Tok::Ident(self.take_while(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '-'))
+                }
+                c => return error(start, format!("unexpected character '{}'", c)),
+            };
+            tokens.push((tok, start));
+        }
+        tokens.push((Tok::Eof, self.loc));
+        Ok(tokens)
+    }
+}
+
+/*──────────────────────────────────────────────────────────────*/
+/* PARSER                                                       */
+/*──────────────────────────────────────────────────────────────*/
+
+#[derive(Default)]
+struct Declarations {
+    tokens: Vec<String>,
+    aliases: HashMap<String, String>,
+    precedence: Vec<(String, Vec<String>)>,
+    start: Option<(String, Loc)>,
+}
+
+struct Parser {
+    tokens: Vec<(Tok, Loc)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Tok {
+        &self.tokens[self.pos].0
+    }
+
+    fn next(&mut self) -> (Tok, Loc) {
+        let tok = self.tokens[self.pos].clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn declarations(&mut self) -> Result<Declarations, YalpError> {
+        let mut decls = Declarations::default();
+        loop {
+            match self.next() {
+                (Tok::Separator, _) => return Ok(decls),
+                (Tok::Eof, loc) => return error(loc, "expected `%%` before the rules".to_string()),
+                (Tok::Directive(d), _) => match d.as_str() {
+                    "%token" => {
+                        // %token <type> NAME [number] ["alias"] ...
+                        let mut last: Option<String> = None;
+                        loop {
+                            match self.peek().clone() {
+                                Tok::Tag | Tok::Int => {}
+                                Tok::Ident(name) | Tok::Char(name) => {
+                                    decls.tokens.push(name.clone());
+                                    last = Some(name);
+                                }
+                                Tok::Str(alias) => match &last {
+                                    Some(name) => {
+                                        decls.aliases.insert(alias, name.clone());
+                                    }
+                                    None => return error(self.tokens[self.pos].1, format!("alias {} doesn't follow a token name", alias)),
+                                },
+                                _ => break,
+                            }
+                            self.next();
+                        }
+                    }
+                    "%left" | "%right" | "%nonassoc" | "%precedence" => {
+                        let mut symbols = Vec::new();
+                        loop {
+                            match self.peek().clone() {
+                                Tok::Tag | Tok::Int => {}
+                                Tok::Ident(s) | Tok::Char(s) => symbols.push(s),
+                                Tok::Str(alias) => match decls.aliases.get(&alias) {
+                                    Some(name) => symbols.push(name.clone()),
+                                    None => return error(self.tokens[self.pos].1, format!("{} isn't the alias of any %token", alias)),
+                                },
+                                _ => break,
+                            }
+                            self.next();
+                        }
+                        decls.precedence.push((d, symbols));
+                    }
+                    "%start" => match self.next() {
+                        (Tok::Ident(name), at) => decls.start = Some((name, at)),
+                        (_, at) => return error(at, "`%start` needs a symbol".to_string()),
+                    },
+                    _ => {
+                        // %define, %union, %type, %code, %expect, ... mean nothing here
+                        while !matches!(self.peek(), Tok::Directive(_) | Tok::Separator | Tok::Eof) {
+                            self.next();
+                        }
+                    }
+                },
+                (Tok::Semicolon, _) => {}
+                (tok, loc) => return error(loc, format!("expected a declaration or `%%`, found {}", tok)),
+            }
+        }
+    }
+
+    // A head is a name followed by `:`, which is how rules without `;` end
+    fn at_head(&self) -> bool {
+        matches!(self.peek(), Tok::Ident(_))
+            && matches!(self.tokens.get(self.pos + 1), Some((Tok::Colon, _)) | Some((Tok::NamedRef, _)))
+    }
+}
+
+enum BodySym {
+    Name(String, Loc),
+    Literal(String),
+}
+
+/// Parses the contents of a Bison .y file
+pub fn parse_bison(src: &str) -> Result<BisonGrammar, YalpError> {
+    let mut scanner = Scanner {
+        chars: src.chars().collect(),
+        i: 0,
+        loc: Loc { line: 1, col: 1 },
+    };
+    let mut parser = Parser { tokens: scanner.tokens()?, pos: 0 };
+    let decls = parser.declarations()?;
+
+    // 1. Rules, in order of appearance
+    // Head, body and %prec token of every rule
+    let mut rules: Vec<(String, Vec<BodySym>, Option<String>)> = Vec::new();
+    let mut actions = Vec::new();
+    let mut heads: Vec<String> = Vec::new();
+    let mut alternatives: HashMap<String, usize> = HashMap::new();
+    loop {
+        let head = match parser.next() {
+            (Tok::Ident(name), loc) => {
+                if decls.tokens.contains(&name) {
+                    return error(loc, format!("token `{}` can't be the head of a rule", name));
+                }
+                name
+            }
+            (Tok::Eof, _) | (Tok::Separator, _) => break,
+            (Tok::Semicolon, _) => continue,
+            (tok, loc) => return error(loc, format!("expected a rule name, found {}", tok)),
+        };
+        if *parser.peek() == Tok::NamedRef {
+            parser.next();
+        }
+        match parser.next() {
+            (Tok::Colon, _) => {}
+            (tok, loc) => return error(loc, format!("expected `:` after `{}`, found {}", head, tok)),
+        }
+        if !heads.contains(&head) {
+            heads.push(head.clone());
+        }
+        loop {
+            let alternative = *alternatives.entry(head.clone()).and_modify(|n| *n += 1).or_insert(0);
+            let mut body = Vec::new();
+            let mut prec = None;
+            loop {
+                if parser.at_head() {
+                    break;
+                }
+                match parser.peek().clone() {
+                    Tok::Ident(name) => body.push(BodySym::Name(name, parser.tokens[parser.pos].1)),
+                    Tok::Char(c) => body.push(BodySym::Literal(c)),
+                    Tok::Str(alias) => match decls.aliases.get(&alias) {
+                        Some(name) => body.push(BodySym::Name(name.clone(), parser.tokens[parser.pos].1)),
+                        None => return error(parser.tokens[parser.pos].1, format!("{} isn't the alias of any %token", alias)),
+                    },
+                    Tok::Action(code) => actions.push(ActionBlock {
+                        head: head.clone(),
+                        alternative,
+                        position: body.len(),
+                        code,
+                        loc: parser.tokens[parser.pos].1,
+                    }),
+                    Tok::Directive(d) if d == "%prec" => {
+                        parser.next();
+                        prec = match parser.peek().clone() {
+                            Tok::Ident(s) | Tok::Char(s) => Some(s),
+                            Tok::Str(alias) => decls.aliases.get(&alias).cloned(),
+                            tok => return error(parser.tokens[parser.pos].1, format!("`%prec` needs a token, found {}", tok)),
+                        };
+                    }
+                    Tok::Directive(d) if d == "%dprec" || d == "%merge" => {
+                        parser.next();
+                    }
+                    Tok::Directive(d) if d == "%empty" => {}
+                    Tok::NamedRef => {}
+                    _ => break,
+                }
+                parser.next();
+            }
+            rules.push((head.clone(), body, prec));
+            match parser.peek() {
+                Tok::Bar => {
+                    parser.next();
+                }
+                Tok::Semicolon => {
+                    parser.next();
+                    break;
+                }
+                _ if parser.at_head() => break,
+                Tok::Eof | Tok::Separator => break,
+                tok => return error(parser.tokens[parser.pos].1, format!("unexpected {} in the rule of `{}`", tok, head)),
+            }
+        }
+    }
+
+    // 2. Symbols: heads are non terminals, everything else must be a token
+    let non_terminals: HashSet<String> = heads.iter().cloned().collect();
+    let declared: HashSet<&String> = decls.tokens.iter().chain(decls.precedence.iter().flat_map(|(_, s)| s)).collect();
+    let mut terminals: HashSet<String> = decls.tokens.iter().cloned().collect();
+    let mut productions: HashMap<String, Vec<Vec<String>>> = HashMap::new();
+    let mut precedence = Precedence::from_declarations(&decls.precedence);
+    for (head, body, prec) in rules {
+        let mut names = Vec::new();
+        for sym in body {
+            match sym {
+                BodySym::Literal(c) => {
+                    terminals.insert(c.clone());
+                    names.push(c);
+                }
+                BodySym::Name(name, loc) => {
+                    if !non_terminals.contains(&name) {
+                        // `error` is Bison's predefined token
+                        if !declared.contains(&name) && name != "error" {
+                            return error(loc, format!("`{}` is used, but is not defined as a token and has no rules", name));
+                        }
+                        terminals.insert(name.clone());
+                    }
+                    names.push(name);
+                }
+            }
+        }
+        if let Some(token) = prec {
+            precedence.rules.insert((head.clone(), names.clone()), token);
+        }
+        productions.entry(head).or_default().push(names);
+    }
+
+    // 3. Start symbol
+    let init_symbol = match (decls.start, heads.first()) {
+        (Some((start, loc)), _) => {
+            if !non_terminals.contains(&start) {
+                return error(loc, format!("start symbol `{}` has no rules", start));
+            }
+            start
+        }
+        (None, Some(first)) => first.clone(),
+        (None, None) => return error(Loc::default(), "the grammar has no rules".to_string()),
+    };
+
+    Ok(BisonGrammar {
+        grammar: GrammarInfo {
+            productions,
+            terminals,
+            non_terminals,
+            ignore: HashSet::new(),
+            init_symbol,
+            flatten: HashSet::new(),
+            precedence,
+        },
+        actions,
+        precedence: decls.precedence,
+    })
+}
+
+pub fn read_bison(filename: &str) -> BisonGrammar {
+    let source = fs::read_to_string(filename)
+        .unwrap_or_else(|e| panic!("~ Error Syn: couldn't read '{}': {}", filename, e));
+    match parse_bison(&source) {
+        Ok(grammar) => grammar,
+        Err(e) => panic!("~ Error Syn: {}:{}", filename, e),
+    }
+}
@@ -0,0 +1,164 @@
+//! Compressed ACTION and GOTO tables, the way yacc packs them
+//!
+//! 1. Default reductions: the most common reduction of every state is taken
+//!    out of its row and used for any terminal the row doesn't have. The
+//!    error is then found a few reductions later, but before shifting the
+//!    wrong token, so the same inputs are accepted.
+//! 2. Error entries are never stored: a row only keeps its actions.
+//! 3. Row displacement: every row is laid over a single vector at the
+//!    first offset where its entries fall on free slots. Each slot keeps the
+//!    state owning it, so a lookup landing on another row's slot misses.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    Shift(u8),
+    Reduce(u8),
+    Accept,
+}
+
+impl Action {
+    /// From the `s5`, `r3` and `acc` of `SynAnalyzer::action`
+    pub fn parse(action: &str) -> Option<Action> {
+        match action {
+            "acc" => Some(Action::Accept),
+            a if a.starts_with('s') => a[1..].parse().ok().map(Action::Shift),
+            a if a.starts_with('r') => a[1..].parse().ok().map(Action::Reduce),
+            _ => None,
+        }
+    }
+
+    /// Back to the notation of `SynAnalyzer::action`
+    pub fn code(&self) -> String {
+        match self {
+            Action::Shift(st) => format!("s{}", st),
+            Action::Reduce(p) => format!("r{}", p),
+            Action::Accept => "acc".to_string(),
+        }
+    }
+}
+
+/// Rows of a table laid over one vector, see the module docs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Comb<T> {
+    // Where the row of each state starts
+    pub base: Vec<usize>,
+    // Owner state and value of every slot
+    pub slots: Vec<Option<(u8, T)>>,
+}
+
+impl<T: Copy> Comb<T> {
+    /// `rows[state]` holds the (column, value) entries of that state
+    fn pack(rows: &[Vec<(usize, T)>]) -> Comb<T> {
+        let mut base = vec![0; rows.len()];
+        let mut slots: Vec<Option<(u8, T)>> = Vec::new();
+        // Fullest rows first, while there is room left
+        let mut order: Vec<usize> = (0..rows.len()).collect();
+        order.sort_by_key(|st| std::cmp::Reverse(rows[*st].len()));
+        for st in order {
+            let row = &rows[st];
+            if row.is_empty() {
+                continue;
+            }
+            let fits = |b: usize| row.iter().all(|(col, _)| slots.get(b + col).is_none_or(|s| s.is_none()));
+            let b = (0..).find(|b| fits(*b)).unwrap();
+            for (col, value) in row {
+                if slots.len() <= b + col {
+                    slots.resize(b + col + 1, None);
+                }
+                slots[b + col] = Some((st as u8, *value));
+            }
+            base[st] = b;
+        }
+        Comb { base, slots }
+    }
+
+    fn get(&self, state: u8, col: usize) -> Option<T> {
+        let b = *self.base.get(state as usize)?;
+        match self.slots.get(b + col) {
+            Some(Some((owner, value))) if *owner == state => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressedTables {
+    // Columns, sorted to be binary searched
+    pub terminals: Vec<String>,
+    pub non_terminals: Vec<String>,
+    // Reduction of every state for the terminals its row lacks
+    pub default_reductions: Vec<Option<u8>>,
+    pub action: Comb<Action>,
+    pub goto: Comb<u8>,
+    // Cells of the tables before compressing, error entries included
+    pub raw_cells: usize,
+}
+
+impl CompressedTables {
+    pub fn new(action: &HashMap<(u8, String), String>, goto: &HashMap<(u8, String), u8>) -> CompressedTables {
+        let terminals: Vec<String> = action.keys().map(|(_, t)| t.clone()).collect::<BTreeSet<_>>().into_iter().collect();
+        let non_terminals: Vec<String> = goto.keys().map(|(_, nt)| nt.clone()).collect::<BTreeSet<_>>().into_iter().collect();
+        let states = action.keys().chain(goto.keys()).map(|(st, _)| *st as usize + 1).max().unwrap_or(0);
+        let column = |columns: &[String], name: &str| columns.binary_search_by(|c| c.as_str().cmp(name)).unwrap();
+
+        let mut action_rows: Vec<Vec<(usize, Action)>> = vec![Vec::new(); states];
+        for ((st, t), a) in action {
+            let a = Action::parse(a).unwrap_or_else(|| panic!("~ Error Syn: unknown action '{}' in state {}", a, st));
+            action_rows[*st as usize].push((column(&terminals, t), a));
+        }
+        let mut default_reductions = Vec::with_capacity(states);
+        for row in &mut action_rows {
+            row.sort_by_key(|(col, _)| *col);
+            // The most common reduction, the lowest production on ties
+            let mut counts: BTreeMap<u8, usize> = BTreeMap::new();
+            for (_, a) in row.iter() {
+                if let Action::Reduce(p) = a {
+                    *counts.entry(*p).or_insert(0) += 1;
+                }
+            }
+            let default = counts.iter().max_by_key(|(p, n)| (**n, std::cmp::Reverse(**p))).map(|(p, _)| *p);
+            if let Some(p) = default {
+                row.retain(|(_, a)| *a != Action::Reduce(p));
+            }
+            default_reductions.push(default);
+        }
+
+        let mut goto_rows: Vec<Vec<(usize, u8)>> = vec![Vec::new(); states];
+        for ((st, nt), to) in goto {
+            goto_rows[*st as usize].push((column(&non_terminals, nt), *to));
+        }
+        goto_rows.iter_mut().for_each(|row| row.sort_by_key(|(col, _)| *col));
+
+        CompressedTables {
+            raw_cells: states * (terminals.len() + non_terminals.len()),
+            terminals,
+            non_terminals,
+            default_reductions,
+            action: Comb::pack(&action_rows),
+            goto: Comb::pack(&goto_rows),
+        }
+    }
+
+    pub fn action(&self, state: u8, terminal: &str) -> Option<Action> {
+        let entry = self
+            .terminals
+            .binary_search_by(|t| t.as_str().cmp(terminal))
+            .ok()
+            .and_then(|col| self.action.get(state, col));
+        entry.or_else(|| self.default_reductions.get(state as usize).copied().flatten().map(Action::Reduce))
+    }
+
+    pub fn goto(&self, state: u8, non_terminal: &str) -> Option<u8> {
+        let col = self.non_terminals.binary_search_by(|nt| nt.as_str().cmp(non_terminal)).ok()?;
+        self.goto.get(state, col)
+    }
+
+    /// Bases, defaults and slots of both combs
+    pub fn cells(&self) -> usize {
+        self.action.base.len() + self.action.slots.len() + self.default_reductions.len() + self.goto.base.len() + self.goto.slots.len()
+    }
+}
@@ -0,0 +1,155 @@
+//! Conflicts of the LR tables, found before the tables are built
+//!
+//! The table builders keep a single action per `(state, token)`: SLR lets the
+//! last one win and LALR panics. Here every candidate action is collected,
+//! so all the conflicts of a grammar can be reported at once. Shift/reduce
+//! pairs the Bison precedence settles are not conflicts.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+};
+
+use super::{
+    first_follow, lalr_automata::LALR,
+    slr_automata::{Element, SLR},
+    yp_reader::GrammarInfo,
+};
+use crate::utility::read_config::ParseMethod;
+
+/// More than one action for `token` in `state`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub state: u8,
+    pub token: String,
+    // `sN`, `rN` or `acc`, sorted
+    pub actions: Vec<String>,
+    // Every action, spelled out: "shift to state 4", "reduce by E -> E + T"
+    pub choices: Vec<String>,
+}
+
+impl Conflict {
+    pub fn kind(&self) -> &'static str {
+        match self.actions.iter().any(|a| a.starts_with('s')) {
+            true => "shift/reduce",
+            false => "reduce/reduce",
+        }
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} conflict in state {} on `{}`:", self.kind(), self.state, self.token)?;
+        for c in &self.choices {
+            write!(f, "\n    - {}", c)?;
+        }
+        Ok(())
+    }
+}
+
+/// `E -> E + T`, as the productions of the automata are stored
+pub fn production_text(production: &[Element]) -> String {
+    let names: Vec<&str> = production
+        .iter()
+        .map(|e| match e {
+            Element::Terminal(s) | Element::NonTerminal(s) => s.as_str(),
+        })
+        .collect();
+    match names.len() {
+        1 => format!("{} -> %empty", names[0]),
+        _ => format!("{} -> {}", names[0], names[1..].join(" ")),
+    }
+}
+
+fn describe(action: &str, productions: &HashMap<u8, Vec<Element>>) -> String {
+    match action.split_at(1) {
+        ("s", to) => format!("shift to state {}", to),
+        ("r", id) => format!("reduce by {}", production_text(&productions[&id.parse::<u8>().unwrap()])),
+        _ => "accept".to_string(),
+    }
+}
+
+/// Every `(state, token)` of the automaton of `method` with several actions
+pub fn find_conflicts(grammar: &GrammarInfo, method: ParseMethod) -> Vec<Conflict> {
+    let first = first_follow::find_first(
+        grammar.productions.clone(),
+        grammar.terminals.clone(),
+        grammar.non_terminals.clone(),
+    );
+    let mut slr = SLR::new(&grammar.productions, &grammar.terminals, &grammar.init_symbol);
+    let mut actions: BTreeMap<(u8, String), BTreeSet<String>> = BTreeMap::new();
+    let mut add = |state: u8, token: &str, action: String| {
+        actions.entry((state, token.to_string())).or_default().insert(action);
+    };
+
+    let productions = match method {
+        ParseMethod::SLR => {
+            // 1. Shifts, reduces on FOLLOW(head) and accept, as build_parsing_table
+            let follows = first_follow::find_follow(
+                &grammar.productions,
+                &grammar.terminals,
+                &grammar.non_terminals,
+                &first,
+                &grammar.init_symbol,
+            );
+            slr.generate();
+            for (&state, edges) in &slr.edges {
+                for (sym, &dest) in edges {
+                    if let Element::Terminal(t) = sym {
+                        add(state, t, format!("s{}", dest));
+                    }
+                }
+            }
+            for &(state, prod_id) in &slr.finish_states {
+                if let Element::NonTerminal(head) = &slr.productions[&prod_id][0] {
+                    for t in follows.get(head).into_iter().flatten() {
+                        add(state, t, format!("r{}", prod_id));
+                    }
+                }
+            }
+            for &state in &slr.acceptance_states {
+                add(state, "$", "acc".to_string());
+            }
+            slr.productions
+        }
+        ParseMethod::LALR | ParseMethod::LR1 => {
+            // 2. Shifts and reduces on the lookaheads of every item
+            let mut lalr = LALR::new(&slr.productions, &grammar.terminals, &grammar.init_symbol);
+            lalr.canonical = method == ParseMethod::LR1;
+            lalr.generate(&first);
+            for st in &lalr.states {
+                for it in &st.items {
+                    let rhs = &lalr.productions[&it.prod_id];
+                    if it.dot < rhs.len() - 1 {
+                        if let Element::Terminal(t) = &rhs[it.dot + 1] {
+                            if let Some(&tgt) = st.transitions.get(t) {
+                                add(st.id, t, format!("s{}", tgt));
+                            }
+                        }
+                    } else if it.prod_id == 0 && it.lookahead.contains("$") {
+                        add(st.id, "$", "acc".to_string());
+                    } else {
+                        for la in &it.lookahead {
+                            add(st.id, la, format!("r{}", it.prod_id));
+                        }
+                    }
+                }
+            }
+            lalr.productions
+        }
+    };
+
+    actions
+        .into_iter()
+        .filter(|(_, a)| a.len() > 1)
+        .filter(|((_, token), a)| {
+            let pair: Vec<&String> = a.iter().collect();
+            pair.len() != 2 || grammar.precedence.settle(token, pair[0], pair[1], &productions).is_none()
+        })
+        .map(|((state, token), a)| {
+            let actions: Vec<String> = a.into_iter().collect();
+            let choices = actions.iter().map(|a| describe(a, &productions)).collect();
+            Conflict { state, token, actions, choices }
+        })
+        .collect()
+}
@@ -4,6 +4,7 @@
 use std::collections::{hash_map::Entry, HashMap, HashSet};
 
 use super::slr_automata::Element;
+use super::precedence::{Actions, Precedence};
 
 /*──────────────────────────────────────────────────────────────*/
 /* LR(1) ITEM                                                   */
@@ -46,6 +47,8 @@ pub struct LALR {
     pub init_symbol: String,
 
     pub states: Vec<State>,
+    // Canonical LR(1): states are told apart by their lookaheads too
+    pub canonical: bool,
 }
 
 impl LALR {
@@ -60,6 +63,7 @@ impl LALR {
             terminals: terminals.clone(),
             init_symbol: init_symbol.clone(),
             states: Vec::new(),
+            canonical: false,
         }
     }
 
@@ -71,19 +75,49 @@ impl LALR {
         first: &HashMap<String, HashSet<String>>,
         mut lks: HashSet<String>,
     ) -> HashSet<String> {
-        if seq.is_empty() {
-            return lks;
-        }
-        match &seq[0] {
-            Element::Terminal(t) => {
-                lks.insert(t.clone());
-                lks
+        // `a` only counts when all of β can be empty
+        let mut out = HashSet::new();
+        for sym in seq {
+            match sym {
+                Element::Terminal(t) => {
+                    out.insert(t.clone());
+                    return out;
+                }
+                Element::NonTerminal(nt) => {
+                    let f = first.get(nt).cloned().unwrap_or_default();
+                    let nullable = f.contains("ε");
+                    out.extend(f.into_iter().filter(|s| s != "ε"));
+                    if !nullable {
+                        return out;
+                    }
+                }
             }
-            Element::NonTerminal(nt) => {
-                lks.extend(first.get(nt).cloned().unwrap_or_default());
-                lks
+        }
+        lks.extend(out);
+        lks
+    }
+
+    /*--------------------------------------------------------*/
+    /* helper: FIRST with ε                                    */
+    /*--------------------------------------------------------*/
+    // find_first only looks at the first symbol of every body: here the
+    // symbols past nullable ones are added, and "ε" to the nullable heads
+    fn nullable_first(&self, first: &HashMap<String, HashSet<String>>) -> HashMap<String, HashSet<String>> {
+        let mut first = first.clone();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for prod in self.productions.values() {
+                let (Element::NonTerminal(head) | Element::Terminal(head)) = &prod[0];
+                let body = Self::first_sequence(&prod[1..], &first, HashSet::from(["ε".to_string()]));
+                let set = first.entry(head.clone()).or_default();
+                if !body.is_subset(set) {
+                    set.extend(body);
+                    changed = true;
+                }
             }
         }
+        first
     }
 
     /*--------------------------------------------------------*/
@@ -185,6 +219,7 @@ impl LALR {
     /* GENERATE LR(1) CANONICAL COLLECTION + MERGE → LALR      */
     /*--------------------------------------------------------*/
     pub fn generate(&mut self, first: &HashMap<String, HashSet<String>>) {
+        let first = &self.nullable_first(first);
         // ---------- I0 ----------
         let mut i0_la = HashSet::new();
         i0_la.insert("$".to_string());
@@ -196,15 +231,15 @@ impl LALR {
         });
         let i0 = Self::closure_lr1(items0, &self.productions, first);
 
-        // maps LR(0) kernel → state_id
-        let mut canonical: HashMap<Vec<(u8, usize)>, u8> = HashMap::new();
+        // maps kernel → state_id
+        let mut canonical: HashMap<Vec<(u8, usize, Vec<String>)>, u8> = HashMap::new();
 
         self.states.push(State {
             id: 0,
             items: i0.clone(),
             transitions: HashMap::new(),
         });
-        canonical.insert(Self::kernel_key(&i0), 0);
+        canonical.insert(self.state_key(&i0), 0);
 
         // BFS over states
         let mut queue: Vec<u8> = vec![0];
@@ -224,6 +259,9 @@ impl LALR {
                     }
                 })
                 .collect();
+            // Sorted, so states are numbered the same on every run
+            let mut symbols: Vec<String> = symbols.into_iter().collect();
+            symbols.sort();
 
             for sym in symbols {
                 let goto_set = Self::goto_lr1(
@@ -235,11 +273,22 @@ impl LALR {
                 if goto_set.is_empty() {
                     continue;
                 }
-                let kkey = Self::kernel_key(&goto_set);
+                let kkey = self.state_key(&goto_set);
 
                 let tid = match canonical.entry(kkey) {
-                    Entry::Occupied(o) => *o.get(),
+                    Entry::Occupied(o) => {
+                        let tid = *o.get();
+                        // Same kernel reached again: its lookaheads are added
+                        // and the state explored again if they grew
+                        if !self.canonical && self.add_lookaheads(tid, goto_set) {
+                            queue.push(tid);
+                        }
+                        tid
+                    }
                     Entry::Vacant(v) => {
+                        if self.states.len() > u8::MAX as usize {
+                            panic!("~ Error Syn: the automaton needs more than {} states", u8::MAX as usize + 1);
+                        }
                         let new_id = self.states.len() as u8;
                         self.states.push(State {
                             id: new_id,
@@ -258,6 +307,9 @@ impl LALR {
         }
 
         // ------------ MERGE lookaheads of identical kernels -------------
+        if self.canonical {
+            return;
+        }
         let mut core_map: HashMap<Vec<(u8, usize)>, usize> = HashMap::new();
         for (idx, st) in self.states.clone().into_iter().enumerate() {
             let key = Self::kernel_key(&st.items);
@@ -287,6 +339,74 @@ impl LALR {
         }
     }
 
+    // Items of a state as SLR::print_state writes them, with their lookaheads
+    pub fn print_state(&self, idx: usize) -> String {
+        let mut items: Vec<&ItemLR1> = self.states[idx].items.iter().collect();
+        items.sort_by_key(|it| (it.prod_id, it.dot));
+        let mut state_content = format!("I{}\n", idx);
+        for it in items {
+            let mut line = String::new();
+            for (i, e) in self.productions[&it.prod_id].iter().enumerate() {
+                match e {
+                    Element::Terminal(t) => line += &format!("\"{}\" ", t),
+                    Element::NonTerminal(nt) => line += &format!("{} ", nt),
+                }
+                if i == 0 {
+                    line += "-> ";
+                }
+                if i == it.dot {
+                    line += ". ";
+                }
+            }
+            let mut la: Vec<&String> = it.lookahead.iter().collect();
+            la.sort();
+            let la: Vec<&str> = la.iter().map(|s| s.as_str()).collect();
+            state_content += &format!("~ {}, {}\n", line, la.join(" / "));
+        }
+        state_content
+    }
+
+    // Merges the lookaheads of `items` into the state, true if any was new
+    fn add_lookaheads(&mut self, state: u8, items: HashSet<ItemLR1>) -> bool {
+        let st_items = &mut self.states[state as usize].items;
+        let mut grew = false;
+        for it in items {
+            match st_items.take(&it) {
+                Some(orig) if orig.lookahead.is_superset(&it.lookahead) => {
+                    st_items.insert(orig);
+                }
+                Some(orig) => {
+                    let mut merged = orig.lookahead.clone();
+                    merged.extend(it.lookahead);
+                    st_items.insert(ItemLR1 { prod_id: orig.prod_id, dot: orig.dot, lookahead: merged });
+                    grew = true;
+                }
+                None => {
+                    st_items.insert(it);
+                    grew = true;
+                }
+            }
+        }
+        grew
+    }
+
+    // LR(0) kernel, with the lookaheads of every item when canonical
+    fn state_key(&self, items: &HashSet<ItemLR1>) -> Vec<(u8, usize, Vec<String>)> {
+        let mut v: Vec<(u8, usize, Vec<String>)> = items
+            .iter()
+            .map(|it| {
+                let mut la: Vec<String> = match self.canonical {
+                    true => it.lookahead.iter().cloned().collect(),
+                    false => Vec::new(),
+                };
+                la.sort();
+                (it.prod_id, it.dot, la)
+            })
+            .collect();
+        v.sort();
+        v
+    }
+
     // helper to get LR(0) core key
     fn kernel_key(items: &HashSet<ItemLR1>) -> Vec<(u8, usize)> {
         let mut v: Vec<(u8, usize)> = items.iter().map(|it| (it.prod_id, it.dot)).collect();
@@ -298,9 +418,10 @@ impl LALR {
     /* BUILD PARSING TABLE                                     */
     /*--------------------------------------------------------*/
     pub fn build_parsing_table(
-        &self
+        &self,
+        precedence: &Precedence,
     ) -> (HashMap<(u8, String), String>, HashMap<(u8, String), u8>) {
-        let mut action = HashMap::new();
+        let mut action = Actions::new(precedence, &self.productions);
         let mut goto = HashMap::new();
 
         for st in &self.states {
@@ -314,20 +435,14 @@ impl LALR {
                     let sym = &rhs[it.dot + 1];
                     if let Element::Terminal(t) = sym {
                         if let Some(&tgt) = st.transitions.get(t) {
-                            let ac: Option<&String> =  action.get(&(st.id, t.clone()));
-                            match ac {
-                                Some(s)=>{
-                                    panic!("LALR Error: Ambiguity in shifts\n state:{} token:{} already present with {}",st.id, t, s)
-                                }
-                                None=>{
-                                    action.insert((st.id, t.clone()), format!("s{}", tgt));
-                                }
+                            // Several items shifting the same token agree
+                            if let Err(s) = action.add(st.id, t, format!("s{}", tgt)) {
+                                panic!("LALR Error: Ambiguity in shifts\n state:{} token:{} already present with {}",st.id, t, s)
                             }
-                            
                         }
                     } else if let Element::NonTerminal(nt) = sym {
                         if let Some(&tgt) = st.transitions.get(nt) {
-                            let gt=  action.get(&(st.id, nt.clone()));
+                            let gt=  action.table.get(&(st.id, nt.clone()));
                             match gt {
                                 Some(s)=>{
                                     panic!("LALR Error: Ambiguity in GOTO\n state:{} token:{} already present with {}",st.id, nt, s)
@@ -350,19 +465,12 @@ impl LALR {
                         continue;
                     };
                     if it.prod_id == 0 && it.lookahead.contains("$") {
-                        action.insert((st.id, "$".to_string()), "acc".to_string());
+                        action.table.insert((st.id, "$".to_string()), "acc".to_string());
                     } else {
                         for la in &it.lookahead {
-                            let rd=  action.get(&(st.id, la.clone()));
-                            match rd {
-                                Some(s)=>{
-                                    panic!("LALR Error: Ambiguity in GOTO\n state:{} token:{} already present with {}",st.id, la, s)
-                                }
-                                None=>{
-                                    action.insert((st.id, la.clone()), format!("r{}", it.prod_id));
-                                }
+                            if let Err(s) = action.add(st.id, la, format!("r{}", it.prod_id)) {
+                                panic!("LALR Error: Ambiguity in GOTO\n state:{} token:{} already present with {}",st.id, la, s)
                             }
-                            
                         }
                         // GOTO entries for <lhs> handled during Shift part
                     }
@@ -377,6 +485,6 @@ impl LALR {
                 }
             }
         }
-        (action, goto)
+        (action.table, goto)
     }
 }
@@ -0,0 +1,58 @@
+use std::{collections::HashSet, fmt};
+
+use serde::{Deserialize, Serialize};
+
+/// Node of the tree built while parsing. Leaves are the tokens, with the
+/// text they matched; inner nodes are the non terminals that were reduced.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseNode {
+    pub symbol: String,
+    pub content: Option<String>,
+    pub children: Vec<ParseNode>,
+}
+
+impl ParseNode {
+    pub fn leaf(symbol: &str, content: &str) -> ParseNode {
+        ParseNode {
+            symbol: symbol.to_string(),
+            content: Some(content.to_string()),
+            children: Vec::new(),
+        }
+    }
+
+    /// Inner node; children whose symbol is in `flatten` are replaced by
+    /// their own children, so `{ x }` lists come out as siblings instead of a
+    /// nested chain.
+    pub fn reduce(symbol: &str, children: Vec<ParseNode>, flatten: &HashSet<String>) -> ParseNode {
+        let mut flat = Vec::new();
+        for child in children {
+            if flatten.contains(&child.symbol) {
+                flat.extend(child.children);
+            } else {
+                flat.push(child);
+            }
+        }
+        ParseNode {
+            symbol: symbol.to_string(),
+            content: None,
+            children: flat,
+        }
+    }
+
+    fn write_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        match &self.content {
+            Some(content) => writeln!(f, "{}{} '{}'", "  ".repeat(depth), self.symbol, content)?,
+            None => writeln!(f, "{}{}", "  ".repeat(depth), self.symbol)?,
+        }
+        for child in &self.children {
+            child.write_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ParseNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_indented(f, 0)
+    }
+}
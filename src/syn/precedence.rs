@@ -0,0 +1,131 @@
+//! Bison precedence: `%left`, `%right`, `%nonassoc` and `%precedence`
+//!
+//! A shift/reduce conflict is settled by comparing the precedence of the
+//! token with the one of the rule, which is its `%prec` token or else its
+//! last terminal with a precedence. The higher one wins; on a tie `%left`
+//! reduces, `%right` shifts and `%nonassoc` makes the token an error. Rules
+//! or tokens without precedence, and `%precedence` ties, stay conflicts.
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+use super::slr_automata::{ActionTable, Element};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    Left,
+    Right,
+    NonAssoc,
+    // `%precedence`: only the level counts
+    None,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Precedence {
+    // Level of every token, higher binds tighter, and its associativity
+    pub tokens: HashMap<String, (usize, Assoc)>,
+    // Rules with a `%prec`, as (head, body), and the token they take it from
+    pub rules: HashMap<(String, Vec<String>), String>,
+}
+
+impl Precedence {
+    /// From the declarations in order, lowest precedence first
+    pub fn from_declarations(declarations: &[(String, Vec<String>)]) -> Precedence {
+        let mut tokens = HashMap::new();
+        for (level, (directive, symbols)) in declarations.iter().enumerate() {
+            let assoc = match directive.as_str() {
+                "%left" => Assoc::Left,
+                "%right" => Assoc::Right,
+                "%nonassoc" => Assoc::NonAssoc,
+                _ => Assoc::None,
+            };
+            for s in symbols {
+                tokens.insert(s.clone(), (level + 1, assoc));
+            }
+        }
+        Precedence { tokens, rules: HashMap::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    fn of_rule(&self, production: &[Element]) -> Option<(usize, Assoc)> {
+        let names: Vec<String> = production
+            .iter()
+            .map(|e| match e {
+                Element::Terminal(s) | Element::NonTerminal(s) => s.clone(),
+            })
+            .collect();
+        let key = (names[0].clone(), names[1..].to_vec());
+        if let Some(token) = self.rules.get(&key) {
+            return self.tokens.get(token).copied();
+        }
+        production[1..].iter().rev().find_map(|e| match e {
+            Element::Terminal(t) => self.tokens.get(t).copied(),
+            Element::NonTerminal(_) => None,
+        })
+    }
+
+    /// Which of `a` and `b`, a shift and a reduction on `token`, stays:
+    /// `Some(None)` when neither does, `None` when the precedence can't tell
+    pub fn settle(&self, token: &str, a: &str, b: &str, productions: &HashMap<u8, Vec<Element>>) -> Option<Option<String>> {
+        let (shift, reduce) = match (a.starts_with('s'), b.starts_with('r')) {
+            (true, true) => (a, b),
+            _ if b.starts_with('s') && a.starts_with('r') => (b, a),
+            _ => return None,
+        };
+        let (token_level, assoc) = *self.tokens.get(token)?;
+        let (rule_level, _) = self.of_rule(productions.get(&reduce[1..].parse::<u8>().ok()?)?)?;
+        match (rule_level.cmp(&token_level), assoc) {
+            (Ordering::Greater, _) | (Ordering::Equal, Assoc::Left) => Some(Some(reduce.to_string())),
+            (Ordering::Less, _) | (Ordering::Equal, Assoc::Right) => Some(Some(shift.to_string())),
+            (Ordering::Equal, Assoc::NonAssoc) => Some(None),
+            (Ordering::Equal, Assoc::None) => None,
+        }
+    }
+}
+
+/// ACTION as the builders fill it, settling conflicts with the precedence
+pub struct Actions<'a> {
+    pub table: ActionTable,
+    // Entries a %nonassoc turned into errors
+    errors: HashSet<(u8, String)>,
+    precedence: &'a Precedence,
+    productions: &'a HashMap<u8, Vec<Element>>,
+}
+
+impl<'a> Actions<'a> {
+    pub fn new(precedence: &'a Precedence, productions: &'a HashMap<u8, Vec<Element>>) -> Actions<'a> {
+        Actions { table: HashMap::new(), errors: HashSet::new(), precedence, productions }
+    }
+
+    /// Adds `action` for `token` in `state`. Fails with the action already
+    /// there when both differ and the precedence doesn't settle it.
+    pub fn add(&mut self, state: u8, token: &str, action: String) -> Result<(), String> {
+        let key = (state, token.to_string());
+        if self.errors.contains(&key) {
+            return Ok(());
+        }
+        let Some(old) = self.table.get(&key) else {
+            self.table.insert(key, action);
+            return Ok(());
+        };
+        if *old == action {
+            return Ok(());
+        }
+        match self.precedence.settle(token, old, &action, self.productions) {
+            Some(Some(winner)) => {
+                self.table.insert(key, winner);
+            }
+            Some(None) => {
+                self.table.remove(&key);
+                self.errors.insert(key);
+            }
+            None => return Err(old.clone()),
+        }
+        Ok(())
+    }
+}
@@ -9,6 +9,8 @@ pub type GotoTable = HashMap<(u8, String), u8>;
 
 use serde::{Deserialize, Serialize};
 
+use super::precedence::{Actions, Precedence};
+
 #[derive(Eq, Hash, Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Element {
     Terminal(String),
@@ -347,12 +349,14 @@ impl SLR {
         }
     }
 
-    /// Construye las tablas ACTION y GOTO usando los FOLLOW sets
+    /// Construye las tablas ACTION y GOTO usando los FOLLOW sets; la
+    /// precedencia resuelve los conflictos shift/reduce que pueda
     pub fn build_parsing_table(
         &self,
         follows: &HashMap<String, HashSet<String>>,
+        precedence: &Precedence,
     ) -> (ActionTable, GotoTable) {
-        let mut action: ActionTable = HashMap::new();
+        let mut action = Actions::new(precedence, &self.productions);
         let mut goto: GotoTable = HashMap::new();
 
         // 1) Shift y Goto desde las transiciones
@@ -360,7 +364,7 @@ impl SLR {
             for (sym, &dest) in trans_map {
                 match sym {
                     Element::Terminal(t) => {
-                        action.insert((state, t.clone()), format!("s{}", dest));
+                        action.table.insert((state, t.clone()), format!("s{}", dest));
                     }
                     Element::NonTerminal(nt) => {
                         goto.insert((state, nt.clone()), dest);
@@ -380,17 +384,21 @@ impl SLR {
 
             if let Some(fset) = follows.get(&head) {
                 for term in fset {
-                    action.insert((state, term.clone()), format!("r{}", prod_id));
+                    // Sin precedencia que lo resuelva, gana la última
+                    let reduce = format!("r{}", prod_id);
+                    if action.add(state, term, reduce.clone()).is_err() {
+                        action.table.insert((state, term.clone()), reduce);
+                    }
                 }
             }
         }
 
         // 3) Accept
         for &state in &self.acceptance_states {
-            action.insert((state, "$".to_string()), "acc".to_string());
+            action.table.insert((state, "$".to_string()), "acc".to_string());
         }
 
-        (action, goto)
+        (action.table, goto)
     }
 
 }
@@ -1,18 +1,26 @@
 use crate::utility::read_config::ParseMethod;
 use std::{
     collections::{HashMap, HashSet},
+    fmt,
     iter::Peekable,
 };
 
 use super::{
+    compress::CompressedTables,
     first_follow, lalr_automata,
+    parse_tree::ParseNode,
     slr_automata::{self, Element},
-    yp_reader::{read_yalpar, GrammarInfo},
+    validation::{validate_grammar, Severity},
+    yp_reader::{read_grammar, GrammarInfo},
 };
 use crate::{
-    lex::lex_analyzer::Symbol,
+    codegen::binary::decode_parser,
+    lex::lex_analyzer::{Kind, Symbol},
     utility::read_config::Config,
-    view::{logging::print_log, print_table, render},
+    view::{
+        logging::{print_grammar_issues, print_log},
+        print_table, render,
+    },
 };
 
 use console::Style;
@@ -25,25 +33,115 @@ pub struct SynAnalyzer {
     pub action: HashMap<(u8, String), String>,
     pub goto: HashMap<(u8, String), u8>,
     pub ignore: HashSet<String>,
+    // Non terminals generated from EBNF groups, see ParseNode::reduce
+    #[serde(default)]
+    pub flatten: HashSet<String>,
+    // Items of every state of the automaton, only kept in memory
+    #[serde(skip)]
+    pub states: Vec<String>,
+    // ACTION and GOTO packed by `compress`, which empties the maps above
+    #[serde(default)]
+    pub compressed: Option<CompressedTables>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ParsingStep {
     pub stack: String,
     pub input: String,
     pub action: String,
 }
 
-impl SynAnalyzer {
-    pub fn generate(filename: &str, config: &Config) -> SynAnalyzer {
-        let blue = Style::new().blue().bold();
-        let green = Style::new().green().bold();
+/// Why an input was rejected
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseError {
+    // Token where the parser stopped, the number of tokens at the end of input
+    pub index: usize,
+    // Contents of the tokens of the input, the one at `index` is pointed at
+    pub input: Vec<String>,
+    pub message: String,
+}
+
+impl ParseError {
+    /// The input with the token at `index` in red and an arrow under it
+    pub fn visual(&self) -> String {
+        error_msg_with_arrow(highlight_error_token(&self.input, self.index), self.index, &self.input)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
 
+impl SynAnalyzer {
+    /// `filename` is a .yalp grammar, or a Bison .y one. `lexer_tokens` are
+    /// the names returned by the .yal actions, used to check that every
+    /// declared token can actually be produced.
+    pub fn generate(
+        filename: &str,
+        config: &Config,
+        lexer_tokens: Option<&HashSet<String>>,
+    ) -> SynAnalyzer {
         // 1. Obtener gramatica
         if config.debug.generation {
             print!("\n");
-            print_log("~ S: Reading Grammar", 1, 7, &blue);
+            print_log("~ S: Reading Grammar", 1, 7, &Style::new().blue().bold());
+        }
+        let grammar = read_grammar(filename);
+        Self::from_grammar(grammar, filename, config, lexer_tokens)
+    }
+
+    /// Reads a parser serialized by `generate`
+    pub fn from_ron<P: AsRef<std::path::Path>>(path: P) -> Result<SynAnalyzer, Box<dyn std::error::Error>> {
+        Ok(ron::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Packs ACTION and GOTO, see `compress::CompressedTables`. The maps
+    /// are emptied, and every parse reads the packed tables from then on.
+    pub fn compress(&mut self) {
+        if self.compressed.is_none() {
+            self.compressed = Some(CompressedTables::new(&self.action, &self.goto));
+            self.action.clear();
+            self.goto.clear();
+        }
+    }
+
+    /// ACTION entry of `state` on `terminal`, as in `action`
+    pub fn action_at(&self, state: u8, terminal: &str) -> Option<String> {
+        match &self.compressed {
+            Some(tables) => tables.action(state, terminal).map(|a| a.code()),
+            None => self.action.get(&(state, terminal.to_string())).cloned(),
+        }
+    }
+
+    pub fn goto_at(&self, state: u8, non_terminal: &str) -> Option<u8> {
+        match &self.compressed {
+            Some(tables) => tables.goto(state, non_terminal),
+            None => self.goto.get(&(state, non_terminal.to_string())).copied(),
+        }
+    }
+
+    /// Reads a parser written by `generate --binary`, see `codegen::binary`
+    pub fn from_bin<P: AsRef<std::path::Path>>(path: P) -> Result<SynAnalyzer, Box<dyn std::error::Error>> {
+        Ok(decode_parser(&std::fs::read(path)?)?)
+    }
+
+    /// `generate` for a grammar already read; `name` is only used in errors
+    pub fn from_grammar(
+        grammar: GrammarInfo,
+        name: &str,
+        config: &Config,
+        lexer_tokens: Option<&HashSet<String>>,
+    ) -> SynAnalyzer {
+        let blue = Style::new().blue().bold();
+        let green = Style::new().green().bold();
+
+        let issues = validate_grammar(&grammar, lexer_tokens);
+        print_grammar_issues(&issues);
+        if issues.iter().any(|i| i.severity() == Severity::Error) {
+            panic!("~ Error Syn: '{}' has grammar errors", name);
         }
-        let grammar = read_yalpar(filename);
 
         // 2. Obtener firsts
         if config.debug.generation {
@@ -60,7 +158,7 @@ impl SynAnalyzer {
 
         match config.parse_method {
             ParseMethod::SLR => {
-                let (action, goto, prods) = Self::slr_flow(&grammar, &first, config);
+                let (action, goto, prods, states) = Self::slr_flow(&grammar, &first, config);
                 if config.debug.generation {
                     print_log(
                         "~ S: SLR Syntax Analyzer – Successful Generation",
@@ -75,10 +173,13 @@ impl SynAnalyzer {
                     action,
                     goto,
                     ignore: grammar.ignore,
+                    flatten: grammar.flatten,
+                    states,
+                    compressed: None,
                 }
             }
-            ParseMethod::LALR => {
-                let (action, goto, prods) = Self::lalr_flow(&grammar, &first, config);
+            ParseMethod::LALR | ParseMethod::LR1 => {
+                let (action, goto, prods, states) = Self::lalr_flow(&grammar, &first, config);
                 // por ahora es sólo un stub:
                     if config.debug.generation {
                     print_log(
@@ -95,6 +196,9 @@ impl SynAnalyzer {
                     action,
                     goto,
                     ignore: grammar.ignore,
+                    flatten: grammar.flatten,
+                    states,
+                    compressed: None,
                 }
             }
         }
@@ -108,6 +212,7 @@ impl SynAnalyzer {
         HashMap<(u8, String), String>, // Action
         HashMap<(u8, String), u8>,     // GOTO
         HashMap<u8, Vec<Element>>,     // Productions
+        Vec<String>,                   // States
     ) {
         let blue = Style::new().blue().bold();
         if config.debug.generation {
@@ -141,7 +246,7 @@ impl SynAnalyzer {
         if config.debug.generation {
             print_log("~ S: Calculating Action Table", 6, 7, &blue);
         }
-        let (action, goto) = slr.build_parsing_table(&follows);
+        let (action, goto) = slr.build_parsing_table(&follows, &grammar.precedence);
         if let Some(path) = &config.vis.parse_table {
             let _rslt = print_table::print_parse_table(
                 slr.icount,
@@ -153,7 +258,8 @@ impl SynAnalyzer {
             );
         }
         // 4. Regresar informacion relevante
-        (action, goto, slr.productions)
+        let states = (0..=slr.icount).map(|i| slr.print_state(i)).collect();
+        (action, goto, slr.productions, states)
     }
 
     // PARA IRVING
@@ -165,6 +271,7 @@ impl SynAnalyzer {
         HashMap<(u8, String), String>, // Action table
         HashMap<(u8, String), u8>,     // GOTO table
         HashMap<u8, Vec<Element>>,     // Productions
+        Vec<String>,                   // States
     ) {
         let blue = Style::new().blue().bold();
 
@@ -191,6 +298,8 @@ impl SynAnalyzer {
             &grammar.terminals,
             &grammar.init_symbol,
         );
+        // Sin fusionar estados por núcleo se obtiene el LR(1) canónico
+        lalr.canonical = config.parse_method == ParseMethod::LR1;
         lalr.generate(&first);
         if let Some(render_path) = &config.vis.lalr_png {
             render::render_lalr(&lalr, render_path);
@@ -200,7 +309,7 @@ impl SynAnalyzer {
         let state_count = lalr.states.len() as u8;
 
         // 4) Construimos las tablas ACTION/GOTO
-        let (action, goto) = lalr.build_parsing_table();
+        let (action, goto) = lalr.build_parsing_table(&grammar.precedence);
 
         // 5) (Opcional) imprimir la tabla de parseo
         if let Some(path) = &config.vis.parse_table {
@@ -214,27 +323,45 @@ impl SynAnalyzer {
             );
         }
 
-        // Devolvemos action, goto y el map de productions del LALR
-        (action, goto, lalr.productions)
+        // Devolvemos action, goto, el map de productions y los estados del LALR
+        let states = (0..lalr.states.len()).map(|i| lalr.print_state(i)).collect();
+        (action, goto, lalr.productions, states)
     }
 
-    pub fn parse(&self, tokens: &[Symbol]) -> (Vec<ParsingStep>, Option<(String, String)>) {
+    pub fn parse<K: Kind>(&self, tokens: &[Symbol<K>]) -> (Vec<ParsingStep>, Option<ParseError>) {
+        let (steps, tree) = self.parse_tree(tokens);
+        (steps, tree.err())
+    }
+
+    /// Same as `parse`, also building the parse tree of the input
+    pub fn parse_tree<K: Kind>(&self, tokens: &[Symbol<K>]) -> (Vec<ParsingStep>, Result<ParseNode, ParseError>) {
         let start = Instant::now();
+        let (steps, tree) = self.parse_tree_silent(tokens);
+        if tree.is_ok() {
+            let duration = start.elapsed();
+            let success_msg = format!("Parsing Completed in {:.2?}", duration);
+            let message = format!("\n\x1b[1;32m{}:\x1b[0m\n", success_msg,);
+            println!("{}", message);
+        }
+        (steps, tree)
+    }
+
+    /// `parse_tree` without printing anything, for the REPL and machine-readable output
+    pub fn parse_tree_silent<K: Kind>(&self, tokens: &[Symbol<K>]) -> (Vec<ParsingStep>, Result<ParseNode, ParseError>) {
         let mut steps = Vec::new();
         let mut stack: Vec<u8> = vec![0];
         let mut symbols: Vec<String> = vec![];
+        let mut nodes: Vec<ParseNode> = vec![];
         let mut input: Peekable<_> = tokens
             .iter()
-            .map(|p| p.token_name.clone())
+            .map(|p| p.token_name.name().to_string())
             .chain(std::iter::once("$".to_string()))
             .peekable();
 
         // Verificación inicial para tokens inválidos en estado 0
         if let Some(first_token) = input.peek().cloned() {
-            let initial_key = (0, first_token.clone());
-            if !self.action.contains_key(&initial_key) && !self.goto.contains_key(&initial_key) {
+            if self.action_at(0, &first_token).is_none() && self.goto_at(0, &first_token).is_none() {
                 let error_index = 0;
-                let error_msg = highlight_error_token(tokens, error_index);
                 let detailed_msg = format!("Invalid initial token '{}'", first_token);
 
                 steps.push(ParsingStep {
@@ -245,10 +372,11 @@ impl SynAnalyzer {
 
                 return (
                     steps,
-                    Some((
-                        error_msg_with_arrow(error_msg, error_index, tokens),
-                        detailed_msg,
-                    )),
+                    Err(ParseError {
+                        index: error_index,
+                        input: tokens.iter().map(|t| t.content.clone()).collect(),
+                        message: detailed_msg,
+                    }),
                 );
             }
         }
@@ -257,12 +385,11 @@ impl SynAnalyzer {
         loop {
             let state = *stack.last().unwrap();
             let lookahead = input.peek().unwrap().clone();
-            let key = (state, lookahead.clone());
 
             let stack_str = format!("{:?} {:?}", stack, symbols);
             let input_str = input.clone().collect::<Vec<_>>().join(" ");
 
-            match self.action.get(&key).map(String::as_str) {
+            match self.action_at(state, &lookahead).as_deref() {
                 Some("acc") => {
                     steps.push(ParsingStep {
                         stack: stack_str,
@@ -272,10 +399,10 @@ impl SynAnalyzer {
                     break;
                 }
                 Some(s) if s.starts_with('s') => {
-                    println!("{}[{}]= {}",state,lookahead,s);
                     let next_st: u8 = s[1..].parse().unwrap();
                     stack.push(next_st);
                     symbols.push(lookahead.clone());
+                    nodes.push(ParseNode::leaf(&lookahead, &tokens[tokens_consumed].content));
                     input.next();
                     tokens_consumed += 1;
                     steps.push(ParsingStep {
@@ -285,26 +412,24 @@ impl SynAnalyzer {
                     });
                 }
                 Some(r) if r.starts_with('r') => {
-                    println!("{}[{}]= {}",state,lookahead,r);
                     let prod_id: u8 = r[1..].parse().unwrap();
                     let rhs_len = self.productions[&(prod_id)].len() - 1;
                     for _ in 0..rhs_len {
                         stack.pop();
                         symbols.pop();
                     }
+                    let children = nodes.split_off(nodes.len() - rhs_len);
                     let top = *stack.last().unwrap();
                     let lhs = if let Element::NonTerminal(nt) = &self.productions[&(prod_id)][0] {
                         nt.clone()
                     } else {
                         unreachable!()
                     };
-                    let goto_key = (top, lhs.clone());
-                    let goto_st = match self.goto.get(&goto_key) {
-                        Some(&st) => st,
+                    let goto_st = match self.goto_at(top, &lhs) {
+                        Some(st) => st,
                         None => {
                             let detailed_msg = format!("Error: no GOTO for ({}, {})", top, lhs);
                             let error_index = tokens_consumed;
-                            let error_msg = highlight_error_token(tokens, error_index);
 
                             steps.push(ParsingStep {
                                 stack: stack_str,
@@ -314,15 +439,17 @@ impl SynAnalyzer {
 
                             return (
                                 steps,
-                                Some((
-                                    error_msg_with_arrow(error_msg, error_index, tokens),
-                                    detailed_msg,
-                                )),
+                                Err(ParseError {
+                                    index: error_index,
+                                    input: tokens.iter().map(|t| t.content.clone()).collect(),
+                                    message: detailed_msg,
+                                }),
                             );
                         }
                     };
                     stack.push(goto_st);
                     symbols.push(lhs.clone());
+                    nodes.push(ParseNode::reduce(&lhs, children, &self.flatten));
 
                     steps.push(ParsingStep {
                         stack: stack_str,
@@ -338,7 +465,6 @@ impl SynAnalyzer {
                 _ => {
                     let detailed_msg = format!("Syntax error at ({}, '{}')", state, lookahead);
                     let error_index = tokens_consumed;
-                    let error_msg = highlight_error_token(tokens, error_index);
 
                     steps.push(ParsingStep {
                         stack: stack_str.clone(),
@@ -348,36 +474,33 @@ impl SynAnalyzer {
 
                     return (
                         steps,
-                        Some((
-                            error_msg_with_arrow(error_msg, error_index, tokens),
-                            detailed_msg,
-                        )),
+                        Err(ParseError {
+                            index: error_index,
+                            input: tokens.iter().map(|t| t.content.clone()).collect(),
+                            message: detailed_msg,
+                        }),
                     );
                 }
             }
         }
 
-        let duration = start.elapsed();
-        let success_msg = format!("Parsing Completed in {:.2?}", duration);
-        let message = format!("\n\x1b[1;32m{}:\x1b[0m\n", success_msg,);
-        println!("{}", message);
-        (steps, None)
+        (steps, Ok(nodes.pop().expect("accepted input has a tree")))
     }
 }
 
-fn highlight_error_token(tokens: &[Symbol], error_index: usize) -> String {
+fn highlight_error_token(tokens: &[String], error_index: usize) -> String {
     let mut result = String::new();
     for (i, token) in tokens.iter().enumerate() {
         if i == error_index {
             // ANSI rojo brillante
-            result.push_str(&format!("\x1b[31m{}\x1b[0m ", token.content));
+            result.push_str(&format!("\x1b[31m{}\x1b[0m ", token));
         } else {
-            result.push_str(&format!("{} ", token.content));
+            result.push_str(&format!("{} ", token));
         }
     }
     result.trim_end().to_string()
 }
-fn error_msg_with_arrow(error_msg: String, error_index: usize, tokens: &[Symbol]) -> String {
+fn error_msg_with_arrow(error_msg: String, error_index: usize, tokens: &[String]) -> String {
     format!(
         "\n\x1b[1;31mParsing Error:\x1b[0m\n{}\n{:>width$}↑ here",
         error_msg,
@@ -385,13 +508,13 @@ fn error_msg_with_arrow(error_msg: String, error_index: usize, tokens: &[Symbol]
         width = compute_token_offset(tokens, error_index)
     )
 }
-fn compute_token_offset(tokens: &[Symbol], error_index: usize) -> usize {
+fn compute_token_offset(tokens: &[String], error_index: usize) -> usize {
     let mut offset = 0;
     for (i, token) in tokens.iter().enumerate() {
         if i == error_index {
             break;
         }
-        offset += token.content.len() + 1;
+        offset += token.len() + 1;
     }
     offset
 }
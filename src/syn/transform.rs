@@ -0,0 +1,241 @@
+//! Grammar rewrites for LL-style consumers
+//!
+//! The SLR/LALR builders take the grammar as it is, but a recursive-descent
+//! parser needs it free of left recursion and common prefixes. Every step
+//! takes a `GrammarInfo` and returns a new one; new non terminals are named
+//! after the head they come from (`Expr_tail`, `Stmt_factor`, ...).
+
+use std::collections::HashSet;
+
+use super::yp_reader::GrammarInfo;
+
+type Body = Vec<String>;
+
+fn push_unique(bodies: &mut Vec<Body>, body: Body) {
+    if !bodies.contains(&body) {
+        bodies.push(body);
+    }
+}
+
+/// Start symbol first, then the rest of the heads in name order
+fn ordered_heads(grammar: &GrammarInfo) -> Vec<String> {
+    let mut heads: Vec<String> = grammar
+        .productions
+        .keys()
+        .filter(|h| **h != grammar.init_symbol)
+        .cloned()
+        .collect();
+    heads.sort();
+    if grammar.productions.contains_key(&grammar.init_symbol) {
+        heads.insert(0, grammar.init_symbol.clone());
+    }
+    heads
+}
+
+/// `head_suffix`, or `head_suffixN` if that name is already taken
+fn fresh_name(grammar: &GrammarInfo, head: &str, suffix: &str) -> String {
+    let taken = |n: &String| {
+        grammar.non_terminals.contains(n) || grammar.terminals.contains(n) || grammar.ignore.contains(n)
+    };
+    let base = format!("{}_{}", head, suffix);
+    let mut name = base.clone();
+    let mut n = 2;
+    while taken(&name) {
+        name = format!("{}{}", base, n);
+        n += 1;
+    }
+    name
+}
+
+fn add_non_terminal(grammar: &mut GrammarInfo, name: String, bodies: Vec<Body>) {
+    grammar.non_terminals.insert(name.clone());
+    grammar.productions.insert(name, bodies);
+}
+
+/// Removes non productive symbols and then the ones the start symbol can't
+/// reach. Terminals are kept declared, as the lexer still returns them.
+pub fn remove_useless(grammar: &GrammarInfo) -> GrammarInfo {
+    let mut result = grammar.clone();
+
+    // 1. Productive non terminals
+    let mut productive: HashSet<String> = HashSet::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (head, bodies) in &grammar.productions {
+            if productive.contains(head) {
+                continue;
+            }
+            if bodies.iter().any(|b| b.iter().all(|s| grammar.terminals.contains(s) || productive.contains(s))) {
+                productive.insert(head.clone());
+                changed = true;
+            }
+        }
+    }
+    // The start symbol stays even if it derives nothing, validation reports it
+    productive.insert(grammar.init_symbol.clone());
+    result.productions.retain(|head, _| productive.contains(head));
+    for bodies in result.productions.values_mut() {
+        bodies.retain(|b| b.iter().all(|s| grammar.terminals.contains(s) || productive.contains(s)));
+    }
+
+    // 2. Reachable from the start symbol
+    let mut reachable: HashSet<String> = HashSet::from([grammar.init_symbol.clone()]);
+    let mut pending = vec![grammar.init_symbol.clone()];
+    while let Some(nt) = pending.pop() {
+        for body in result.productions.get(&nt).into_iter().flatten() {
+            for s in body {
+                if result.productions.contains_key(s) && reachable.insert(s.clone()) {
+                    pending.push(s.clone());
+                }
+            }
+        }
+    }
+    result.productions.retain(|head, _| reachable.contains(head));
+    result.non_terminals = result.productions.keys().cloned().collect();
+    result
+}
+
+/// Replaces every `A: B` with the bodies of `B`, following chains of them
+pub fn remove_unit_productions(grammar: &GrammarInfo) -> GrammarInfo {
+    let mut result = grammar.clone();
+    let is_unit = |b: &Body| b.len() == 1 && grammar.productions.contains_key(&b[0]);
+
+    for head in ordered_heads(grammar) {
+        // 1. Non terminals reachable through unit productions
+        let mut closure = vec![head.clone()];
+        let mut i = 0;
+        while i < closure.len() {
+            for body in &grammar.productions[&closure[i]] {
+                if is_unit(body) && !closure.contains(&body[0]) {
+                    closure.push(body[0].clone());
+                }
+            }
+            i += 1;
+        }
+
+        // 2. Their non unit bodies
+        let mut bodies = Vec::new();
+        for nt in &closure {
+            for body in grammar.productions[nt].iter().filter(|b| !is_unit(b)) {
+                push_unique(&mut bodies, body.clone());
+            }
+        }
+        result.productions.insert(head, bodies);
+    }
+    result
+}
+
+/// `A: A a | b` becomes `A: b A_tail` and `A_tail: a A_tail | %empty`
+fn remove_direct_left_recursion(grammar: &mut GrammarInfo, head: &str) {
+    let bodies = &grammar.productions[head];
+    let (recursive, rest): (Vec<&Body>, Vec<&Body>) = bodies
+        .iter()
+        .partition(|b| b.first().map(|s| s.as_str()) == Some(head));
+    if recursive.is_empty() || rest.is_empty() {
+        return;
+    }
+    let tail = fresh_name(grammar, head, "tail");
+
+    let mut head_bodies = Vec::new();
+    for beta in rest {
+        let mut body = beta.clone();
+        body.push(tail.clone());
+        push_unique(&mut head_bodies, body);
+    }
+    let mut tail_bodies = Vec::new();
+    for alpha in recursive {
+        let mut body = alpha[1..].to_vec();
+        body.push(tail.clone());
+        push_unique(&mut tail_bodies, body);
+    }
+    tail_bodies.push(Vec::new());
+
+    grammar.productions.insert(head.to_string(), head_bodies);
+    add_non_terminal(grammar, tail, tail_bodies);
+}
+
+/// Removes direct and indirect left recursion. As in the textbook
+/// algorithm, recursion hidden behind an `%empty` prefix (`A: B A ;` with a
+/// nullable `B`) isn't detected; unit cycles must be collapsed beforehand.
+pub fn remove_left_recursion(grammar: &GrammarInfo) -> GrammarInfo {
+    let mut result = grammar.clone();
+    let order = ordered_heads(grammar);
+    for (i, ai) in order.iter().enumerate() {
+        // 1. Substitute the previous heads when they lead a body of Ai
+        for aj in &order[..i] {
+            let mut bodies = Vec::new();
+            for body in &result.productions[ai] {
+                if body.first() == Some(aj) {
+                    for delta in &result.productions[aj] {
+                        let mut expanded = delta.clone();
+                        expanded.extend_from_slice(&body[1..]);
+                        push_unique(&mut bodies, expanded);
+                    }
+                } else {
+                    push_unique(&mut bodies, body.clone());
+                }
+            }
+            result.productions.insert(ai.clone(), bodies);
+        }
+        // 2. Then only direct recursion is left
+        remove_direct_left_recursion(&mut result, ai);
+    }
+    result
+}
+
+/// Groups the bodies of every head that share a prefix:
+/// `A: x y | x z` becomes `A: x A_factor` and `A_factor: y | z`
+pub fn left_factor(grammar: &GrammarInfo) -> GrammarInfo {
+    let mut result = grammar.clone();
+    let mut pending = ordered_heads(grammar);
+    while let Some(head) = pending.pop() {
+        let bodies = result.productions[&head].clone();
+        let group: Vec<&Body> = match bodies.iter().find(|b| {
+            !b.is_empty() && bodies.iter().filter(|o| o.first() == b.first()).count() > 1
+        }) {
+            Some(first) => bodies.iter().filter(|b| b.first() == first.first()).collect(),
+            None => continue,
+        };
+
+        // 1. Longest common prefix of the group
+        let mut len = 1;
+        while group.iter().all(|b| b.len() > len && b[len] == group[0][len]) {
+            len += 1;
+        }
+        let factor = fresh_name(&result, &head, "factor");
+
+        // 2. The group is replaced, in place, by `prefix A_factor`
+        let mut head_bodies = Vec::new();
+        let mut factor_bodies = Vec::new();
+        for body in &bodies {
+            if group.contains(&body) {
+                if factor_bodies.is_empty() {
+                    let mut prefix = body[..len].to_vec();
+                    prefix.push(factor.clone());
+                    head_bodies.push(prefix);
+                }
+                push_unique(&mut factor_bodies, body[len..].to_vec());
+            } else {
+                head_bodies.push(body.clone());
+            }
+        }
+        result.productions.insert(head.clone(), head_bodies);
+        add_non_terminal(&mut result, factor.clone(), factor_bodies);
+
+        // 3. Both may still have prefixes in common
+        pending.push(factor);
+        pending.push(head);
+    }
+    result
+}
+
+/// Every step in order: useless symbols, unit productions, left recursion,
+/// left factoring, and a last cleanup of whatever became unreachable.
+pub fn transform_grammar(grammar: &GrammarInfo) -> GrammarInfo {
+    let grammar = remove_useless(grammar);
+    let grammar = remove_useless(&remove_unit_productions(&grammar));
+    let grammar = remove_left_recursion(&grammar);
+    let grammar = left_factor(&grammar);
+    remove_useless(&grammar)
+}
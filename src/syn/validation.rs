@@ -0,0 +1,268 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use super::yp_reader::GrammarInfo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Problem found in a grammar before building its automaton
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrammarIssue {
+    // Used in a body (or as start symbol) but has no productions
+    UndefinedNonTerminal { symbol: String, suggestion: Option<String> },
+    UnusedTerminal(String),
+    UnusedNonTerminal(String),
+    // Used somewhere, but not derivable from the start symbol
+    Unreachable(String),
+    // Can't derive any string of terminals
+    NonProductive(String),
+    // The start symbol itself is non productive: no input is ever accepted
+    EmptyLanguage(String),
+    DuplicateProduction { head: String, body: Vec<String> },
+    // Declared as %token or IGNORE, but no .yal action returns it
+    TokenNeverProduced(String),
+    // Returned by a .yal action, but not declared in the .yalp
+    UnknownLexerToken { token: String, suggestion: Option<String> },
+    // A declared token and a returned one that are a few edits apart, a warning
+    MisspelledToken { declared: String, returned: String },
+}
+
+impl GrammarIssue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            GrammarIssue::UndefinedNonTerminal { .. }
+            | GrammarIssue::EmptyLanguage(_)
+            | GrammarIssue::DuplicateProduction { .. } => Severity::Error,
+            // Only a guess: short names like IF and ID are close too
+            _ => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for GrammarIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GrammarIssue::UndefinedNonTerminal { symbol, suggestion } => {
+                write!(f, "`{}` is used but has no productions", symbol)?;
+                match suggestion {
+                    Some(s) => write!(f, " (did you mean `{}`?)", s),
+                    None => Ok(()),
+                }
+            }
+            GrammarIssue::UnusedTerminal(s) => write!(f, "token `{}` is never used", s),
+            GrammarIssue::UnusedNonTerminal(s) => write!(f, "non terminal `{}` is never used", s),
+            GrammarIssue::Unreachable(s) => {
+                write!(f, "`{}` can't be reached from the start symbol", s)
+            }
+            GrammarIssue::NonProductive(s) => {
+                write!(f, "`{}` never derives a string of tokens", s)
+            }
+            GrammarIssue::EmptyLanguage(s) => {
+                write!(f, "start symbol `{}` never derives a string of tokens", s)
+            }
+            GrammarIssue::DuplicateProduction { head, body } => {
+                write!(f, "production `{} -> {}` is repeated", head, body.join(" "))
+            }
+            GrammarIssue::TokenNeverProduced(s) => {
+                write!(f, "token `{}` is never returned by the lexer", s)
+            }
+            GrammarIssue::UnknownLexerToken { token, suggestion } => {
+                write!(f, "the lexer returns `{}`, which is not a %token nor IGNORE", token)?;
+                match suggestion {
+                    Some(s) => write!(f, " (did you mean `{}`?)", s),
+                    None => Ok(()),
+                }
+            }
+            GrammarIssue::MisspelledToken { declared, returned } => write!(
+                f,
+                "the grammar declares `{}` but the lexer returns `{}`; is one of them misspelled?",
+                declared, returned
+            ),
+        }
+    }
+}
+
+fn sorted(set: HashSet<String>) -> Vec<String> {
+    let mut v: Vec<String> = set.into_iter().collect();
+    v.sort();
+    v
+}
+
+/// Runs every check over the grammar. `lexer_tokens`, when given, are the
+/// token names returned by the .yal actions.
+pub fn validate_grammar(
+    grammar: &GrammarInfo,
+    lexer_tokens: Option<&HashSet<String>>,
+) -> Vec<GrammarIssue> {
+    let mut issues = Vec::new();
+    let prods = &grammar.productions;
+
+    let mut heads: Vec<&String> = prods.keys().collect();
+    heads.sort();
+
+    // 1. Symbols referenced from the bodies
+    let mut used: HashSet<String> = HashSet::new();
+    for bodies in prods.values() {
+        for body in bodies {
+            used.extend(body.iter().cloned());
+        }
+    }
+
+    // 2. Undefined non terminals
+    let mut undefined: HashSet<String> = used
+        .iter()
+        .filter(|s| !grammar.terminals.contains(*s) && !prods.contains_key(*s))
+        .cloned()
+        .collect();
+    if !prods.contains_key(&grammar.init_symbol) {
+        undefined.insert(grammar.init_symbol.clone());
+    }
+    for s in sorted(undefined) {
+        let suggestion = closest(&s, grammar.terminals.iter().chain(prods.keys())).cloned();
+        issues.push(GrammarIssue::UndefinedNonTerminal { symbol: s, suggestion });
+    }
+
+    // 3. Unused symbols
+    for t in sorted(grammar.terminals.difference(&used).cloned().collect()) {
+        issues.push(GrammarIssue::UnusedTerminal(t));
+    }
+    let unused: HashSet<String> = prods
+        .keys()
+        .filter(|h| !used.contains(*h) && **h != grammar.init_symbol)
+        .cloned()
+        .collect();
+    for nt in sorted(unused.clone()) {
+        issues.push(GrammarIssue::UnusedNonTerminal(nt));
+    }
+
+    // 4. Reachability from the start symbol
+    let mut reachable: HashSet<String> = HashSet::from([grammar.init_symbol.clone()]);
+    let mut pending = vec![grammar.init_symbol.clone()];
+    while let Some(nt) = pending.pop() {
+        for body in prods.get(&nt).into_iter().flatten() {
+            for s in body {
+                if reachable.insert(s.clone()) {
+                    pending.push(s.clone());
+                }
+            }
+        }
+    }
+    for h in &heads {
+        if !reachable.contains(*h) && !unused.contains(*h) {
+            issues.push(GrammarIssue::Unreachable(h.to_string()));
+        }
+    }
+
+    // 5. Productive non terminals: fixpoint over bodies made of productive symbols
+    let mut productive: HashSet<&String> = HashSet::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (head, bodies) in prods {
+            if productive.contains(head) {
+                continue;
+            }
+            let derives = bodies.iter().any(|body| {
+                body.iter()
+                    .all(|s| grammar.terminals.contains(s) || productive.contains(s))
+            });
+            if derives {
+                productive.insert(head);
+                changed = true;
+            }
+        }
+    }
+    for h in &heads {
+        if productive.contains(*h) {
+            continue;
+        }
+        if **h == grammar.init_symbol {
+            issues.push(GrammarIssue::EmptyLanguage(h.to_string()));
+        } else {
+            issues.push(GrammarIssue::NonProductive(h.to_string()));
+        }
+    }
+
+    // 6. Repeated alternatives of the same head
+    for h in &heads {
+        let mut seen: HashMap<&Vec<String>, usize> = HashMap::new();
+        for body in &prods[*h] {
+            let count = seen.entry(body).or_insert(0);
+            *count += 1;
+            if *count == 2 {
+                issues.push(GrammarIssue::DuplicateProduction {
+                    head: h.to_string(),
+                    body: body.clone(),
+                });
+            }
+        }
+    }
+
+    // 7. Token names shared with the lexer
+    if let Some(produced) = lexer_tokens {
+        issues.extend(cross_check_tokens(grammar, produced));
+    }
+
+    issues
+}
+
+/// Levenshtein distance, ignoring case
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut diag = row[0];
+        row[0] = i;
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let next = (row[j] + 1).min(row[j - 1] + 1).min(diag + cost);
+            diag = row[j];
+            row[j] = next;
+        }
+    }
+    row[b.len()]
+}
+
+/// Closest candidate, as long as it is at most a third of `name` away
+pub(crate) fn closest<'a>(name: &str, candidates: impl Iterator<Item = &'a String>) -> Option<&'a String> {
+    let limit = (name.chars().count() / 3).max(1);
+    candidates
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|(d, _)| *d <= limit)
+        .min()
+        .map(|(_, c)| c)
+}
+
+/// Compares the names returned by the .yal actions with the tokens declared
+/// in the .yalp (`%token` and `IGNORE`).
+pub fn cross_check_tokens(grammar: &GrammarInfo, produced: &HashSet<String>) -> Vec<GrammarIssue> {
+    let mut issues = Vec::new();
+    let declared: HashSet<String> = grammar.terminals.union(&grammar.ignore).cloned().collect();
+    let missing = sorted(declared.difference(produced).cloned().collect());
+    let mut extra = sorted(produced.difference(&declared).cloned().collect());
+
+    // 1. A missing name close to an extra one is most likely a typo
+    for m in missing {
+        match closest(&m, extra.iter()).cloned() {
+            Some(returned) => {
+                extra.retain(|e| *e != returned);
+                issues.push(GrammarIssue::MisspelledToken { declared: m, returned });
+            }
+            None => issues.push(GrammarIssue::TokenNeverProduced(m)),
+        }
+    }
+
+    // 2. Names the parser will never expect
+    for e in extra {
+        let suggestion = closest(&e, declared.iter()).cloned();
+        issues.push(GrammarIssue::UnknownLexerToken { token: e, suggestion });
+    }
+    issues
+}
@@ -0,0 +1,375 @@
+//! Tokenizer and recursive-descent parser for the .yalp format
+//!
+//! ```text
+//! file        := decl* "%%" production* ("%%" anything)?
+//! decl        := "%token" SYMBOL+ | "IGNORE" SYMBOL+ | "%start" SYMBOL
+//! production  := SYMBOL ":" alternative ("|" alternative)* ";"
+//! alternative := "%empty" | item*
+//! item        := SYMBOL
+//!              | "[" alternative ("|" alternative)* "]"           (optional)
+//!              | "{" alternative ("|" alternative)* "}"           (zero or more)
+//!              | "(" alternative ("|" alternative)* ")" ("*" | "+")?
+//! ```
+//! A SYMBOL is a name (`[A-Za-z_][A-Za-z0-9_.]*`) or a quoted literal such as
+//! `'+'` or `"::"`. Comments `/* ... */` are removed beforehand. Groups are
+//! kept in the tree; `grammar_info` desugars them into fresh non terminals.
+
+use std::fmt;
+
+use crate::{
+    lex::yl_parser::Loc,
+    utility::comments::{strip_comments, YALP_COMMENTS},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sym {
+    pub name: String,
+    pub loc: Loc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GroupKind {
+    // ( ... )
+    Group,
+    // [ ... ]
+    Optional,
+    // { ... } and ( ... )*
+    Repeat,
+    // ( ... )+
+    Repeat1,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Symbol(Sym),
+    Group {
+        kind: GroupKind,
+        alternatives: Vec<Alternative>,
+        loc: Loc,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alternative {
+    pub items: Vec<Item>,
+    pub loc: Loc,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductionDef {
+    pub head: Sym,
+    pub alternatives: Vec<Alternative>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct YalpFile {
+    pub tokens: Vec<Sym>,
+    pub ignore: Vec<Sym>,
+    pub start: Option<Sym>,
+    pub productions: Vec<ProductionDef>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct YalpError {
+    pub msg: String,
+    pub loc: Loc,
+}
+
+impl fmt::Display for YalpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.loc, self.msg)
+    }
+}
+
+impl std::error::Error for YalpError {}
+
+fn error<T>(loc: Loc, msg: String) -> Result<T, YalpError> {
+    Err(YalpError { msg, loc })
+}
+
+/*──────────────────────────────────────────────────────────────*/
+/* TOKENIZER                                                    */
+/*──────────────────────────────────────────────────────────────*/
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Symbol(String),
+    // %token, %start, %empty, ... and the bare IGNORE keyword
+    Directive(String),
+    Separator,
+    Colon,
+    Bar,
+    Semicolon,
+    // ( ) [ ] { } * +
+    Punct(char),
+    Eof,
+}
+
+impl fmt::Display for Tok {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tok::Symbol(s) => write!(f, "`{}`", s),
+            Tok::Directive(d) => write!(f, "`{}`", d),
+            Tok::Separator => write!(f, "`%%`"),
+            Tok::Colon => write!(f, "`:`"),
+            Tok::Bar => write!(f, "`|`"),
+            Tok::Semicolon => write!(f, "`;`"),
+            Tok::Punct(c) => write!(f, "`{}`", c),
+            Tok::Eof => write!(f, "end of file"),
+        }
+    }
+}
+
+fn tokenize(src: &str) -> Result<Vec<(Tok, Loc)>, YalpError> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let mut loc = Loc { line: 1, col: 1 };
+    let advance = |i: &mut usize, loc: &mut Loc, n: usize| {
+        for _ in 0..n {
+            if chars[*i] == '\n' {
+                loc.line += 1;
+                loc.col = 1;
+            } else {
+                loc.col += 1;
+            }
+            *i += 1;
+        }
+    };
+    while i < chars.len() {
+        let c = chars[i];
+        let start = loc;
+        if c.is_whitespace() {
+            advance(&mut i, &mut loc, 1);
+            continue;
+        }
+        let (tok, len) = match c {
+            ':' => (Tok::Colon, 1),
+            '|' => (Tok::Bar, 1),
+            ';' => (Tok::Semicolon, 1),
+            '(' | ')' | '[' | ']' | '{' | '}' | '*' | '+' => (Tok::Punct(c), 1),
+            '%' if chars.get(i + 1) == Some(&'%') => {
+                // Everything after a second %% is ignored
+                if tokens.iter().any(|(t, _)| *t == Tok::Separator) {
+                    break;
+                }
+                (Tok::Separator, 2)
+            }
+            '%' => {
+                let len = 1 + chars[i + 1..].iter().take_while(|c| c.is_alphanumeric() || **c == '_').count();
+                if len == 1 {
+                    return error(start, "expected a directive name after `%`".to_string());
+                }
+                (Tok::Directive(chars[i..i + len].iter().collect()), len)
+            }
+            '\'' | '"' => {
+                let mut len = 1;
+                loop {
+                    match chars.get(i + len) {
+                        Some('\\') => len += 2,
+                        Some(q) if *q == c => break,
+                        Some('\n') | None => {
+                            return error(start, "unterminated quoted symbol".to_string())
+                        }
+                        _ => len += 1,
+                    }
+                }
+                len += 1;
+                if len == 2 {
+                    return error(start, "empty quoted symbol".to_string());
+                }
+                (Tok::Symbol(chars[i..i + len].iter().collect()), len)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let len = chars[i..]
+                    .iter()
+                    .take_while(|c| c.is_alphanumeric() || **c == '_' || **c == '.')
+                    .count();
+                let name: String = chars[i..i + len].iter().collect();
+                if name == "IGNORE" {
+                    (Tok::Directive(name), len)
+                } else {
+                    (Tok::Symbol(name), len)
+                }
+            }
+            c => return error(start, format!("unexpected character '{}'", c)),
+        };
+        advance(&mut i, &mut loc, len);
+        tokens.push((tok, start));
+    }
+    tokens.push((Tok::Eof, loc));
+    Ok(tokens)
+}
+
+/*──────────────────────────────────────────────────────────────*/
+/* PARSER                                                       */
+/*──────────────────────────────────────────────────────────────*/
+
+struct Parser {
+    tokens: Vec<(Tok, Loc)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Tok {
+        &self.tokens[self.pos].0
+    }
+
+    fn next(&mut self) -> (Tok, Loc) {
+        let tok = self.tokens[self.pos].clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn symbols(&mut self) -> Vec<Sym> {
+        let mut syms = Vec::new();
+        while let Tok::Symbol(name) = self.peek() {
+            syms.push(Sym { name: name.clone(), loc: self.tokens[self.pos].1 });
+            self.next();
+        }
+        syms
+    }
+
+    fn file(&mut self) -> Result<YalpFile, YalpError> {
+        let mut file = YalpFile::default();
+
+        // 1. Declarations
+        loop {
+            match self.next() {
+                (Tok::Separator, _) => break,
+                (Tok::Directive(d), loc) => {
+                    let syms = self.symbols();
+                    if syms.is_empty() {
+                        return error(loc, format!("`{}` needs at least one symbol", d));
+                    }
+                    match d.as_str() {
+                        "%token" => file.tokens.extend(syms),
+                        "IGNORE" => file.ignore.extend(syms),
+                        "%start" => {
+                            if syms.len() > 1 {
+                                return error(syms[1].loc, "`%start` takes a single symbol".to_string());
+                            }
+                            if file.start.is_some() {
+                                return error(loc, "`%start` is declared twice".to_string());
+                            }
+                            file.start = syms.into_iter().next();
+                        }
+                        _ => return error(loc, format!("unknown directive `{}`", d)),
+                    }
+                }
+                (Tok::Eof, loc) => return error(loc, "expected `%%` before the productions".to_string()),
+                (tok, loc) => return error(loc, format!("expected a declaration or `%%`, found {}", tok)),
+            }
+        }
+
+        // 2. Productions
+        while let Tok::Symbol(_) = self.peek() {
+            file.productions.push(self.production()?);
+        }
+        match self.next() {
+            (Tok::Eof, _) | (Tok::Separator, _) => Ok(file),
+            (tok, loc) => error(loc, format!("expected a production head, found {}", tok)),
+        }
+    }
+
+    fn production(&mut self) -> Result<ProductionDef, YalpError> {
+        let head = match self.next() {
+            (Tok::Symbol(name), loc) => Sym { name, loc },
+            (tok, loc) => return error(loc, format!("expected a production head, found {}", tok)),
+        };
+        match self.next() {
+            (Tok::Colon, _) => {}
+            (tok, loc) => return error(loc, format!("expected `:` after `{}`, found {}", head.name, tok)),
+        }
+        let mut alternatives = vec![self.alternative()?];
+        loop {
+            match self.next() {
+                (Tok::Bar, _) => alternatives.push(self.alternative()?),
+                (Tok::Semicolon, _) => return Ok(ProductionDef { head, alternatives }),
+                (Tok::Colon, loc) => {
+                    return error(loc, format!("unexpected `:`; the production of `{}` is missing its `;`", head.name))
+                }
+                (tok, loc) => return error(loc, format!("expected `|` or `;` in the production of `{}`, found {}", head.name, tok)),
+            }
+        }
+    }
+
+    fn alternative(&mut self) -> Result<Alternative, YalpError> {
+        let loc = self.tokens[self.pos].1;
+        if let Tok::Directive(d) = self.peek() {
+            if d == "%empty" {
+                self.next();
+                return Ok(Alternative { items: Vec::new(), loc });
+            }
+        }
+        let mut items = Vec::new();
+        loop {
+            match self.peek() {
+                Tok::Symbol(_) => items.extend(self.symbols().into_iter().map(Item::Symbol)),
+                Tok::Punct('(') | Tok::Punct('[') | Tok::Punct('{') => items.push(self.group()?),
+                _ => break,
+            }
+        }
+        // `A B : ...` means the `;` of the previous production is missing
+        if *self.peek() == Tok::Colon {
+            if let Some(Item::Symbol(sym)) = items.pop() {
+                return error(sym.loc, format!("`{}` looks like a new production; missing `;` before it", sym.name));
+            }
+        }
+        Ok(Alternative { items, loc })
+    }
+
+    fn group(&mut self) -> Result<Item, YalpError> {
+        let (open, loc) = match self.next() {
+            (Tok::Punct(c), loc) => (c, loc),
+            (tok, loc) => return error(loc, format!("expected a group, found {}", tok)),
+        };
+        let close = match open {
+            '(' => ')',
+            '[' => ']',
+            _ => '}',
+        };
+        let mut alternatives = vec![self.alternative()?];
+        loop {
+            match self.next() {
+                (Tok::Bar, _) => alternatives.push(self.alternative()?),
+                (Tok::Punct(c), _) if c == close => break,
+                (tok, at) => return error(at, format!("expected `|` or `{}` to close the `{}` at {}, found {}", close, open, loc, tok)),
+            }
+        }
+        if alternatives.iter().all(|a| a.items.is_empty()) {
+            return error(loc, format!("empty `{}{}` group", open, close));
+        }
+        let kind = match open {
+            '[' => GroupKind::Optional,
+            '{' => GroupKind::Repeat,
+            _ => match self.peek() {
+                Tok::Punct('*') => {
+                    self.next();
+                    GroupKind::Repeat
+                }
+                Tok::Punct('+') => {
+                    self.next();
+                    GroupKind::Repeat1
+                }
+                _ => GroupKind::Group,
+            },
+        };
+        Ok(Item::Group { kind, alternatives, loc })
+    }
+}
+
+/// Parses the contents of a .yalp file
+pub fn parse_yalp(src: &str) -> Result<YalpFile, YalpError> {
+    let source = strip_comments(src, &YALP_COMMENTS).map_err(|e| YalpError {
+        msg: "unterminated comment".to_string(),
+        loc: Loc { line: e.line, col: e.col },
+    })?;
+    let mut parser = Parser {
+        tokens: tokenize(&source)?,
+        pos: 0,
+    };
+    parser.file()
+}
@@ -1,178 +1,183 @@
-use std::{collections::{HashMap, HashSet}, hash::Hash};
+use std::{collections::{HashMap, HashSet}, fs};
 
-use crate::utility::reader::read_lines;
-
-#[derive(Eq, Hash, Debug, PartialEq, Clone)]
-pub struct TokenAction{
-    id: i32,
-    do_ignore: bool,
-    name: String
-}
+use super::bison_reader::read_bison;
+use super::precedence::Precedence;
+use super::yp_parser::{parse_yalp, Alternative, GroupKind, Item, YalpError, YalpFile};
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct GrammarInfo{
     pub productions: HashMap<String, Vec<Vec<String>>>,
     pub terminals: HashSet<String>,
     pub non_terminals: HashSet<String>,
-    pub ignore: HashSet<String>,    
+    pub ignore: HashSet<String>,
     pub init_symbol: String,
+    // Non terminals generated for EBNF groups, spliced into their parent
+    // in the parse tree
+    pub flatten: HashSet<String>,
+    // Only Bison grammars have one, see `precedence`
+    pub precedence: Precedence,
 }
 
-fn process_token(line: String, counter: i32)->Option<TokenAction>{
-    if line.is_empty(){
-        return None;
-    }
-    let mut action: bool = true;
-    let mut name = String::new();
-    let division:Vec<&str> = line.split_whitespace().collect();
-    if division.len() != 2{
-        panic!("Error in token definition. Couldnt determine the following: \n'{:?}'", line)
-    }
-    for (id, div) in division.into_iter().enumerate(){
-        if id == 0{
-            if div == "%token"{
-                action = false;
-            }
-        }
-        if id == 1{
-            name = div.to_string();
-        }
-    }
-    let ta = TokenAction{
-        id: counter,
-        do_ignore: action,
-        name: name
-    };
-    Some(ta)
+// Turns EBNF groups into fresh non terminals, reusing the ones already
+// generated for an identical group
+struct Desugar<'a>{
+    terminals: &'a HashSet<String>,
+    ignore: &'a HashSet<String>,
+    non_terminals: HashSet<String>,
+    generated: HashMap<(GroupKind, Vec<Vec<String>>), String>,
+    productions: HashMap<String, Vec<Vec<String>>>,
 }
 
-fn process_production(
-    prod_string: String,
-    non_terminals: &mut HashSet<String>,
-    terminals: &HashSet<String>
-)->(
-    String, // Head
-    Vec<Vec<String>> // Productions
-){
-    let mut prod_vec:Vec<Vec<String>> = Vec::new();
-    let mut head = String::new();
-    let mut cut = 0;
-    // Head extraction
-    for (id, ch) in prod_string.chars().into_iter().enumerate(){
-        if ch !=':'{
-            head.push(ch);
-        } else{
-            cut = id+1;
-            break;
-        }
-    }
-    // println!("Head: {:?}",head);
-    if !non_terminals.contains(&head){
-        non_terminals.insert(head.clone());
-    }
-    // Head cut
-    let byte_index = prod_string.char_indices().nth(cut)
-        .map(|(i, _)| i)
-        .unwrap_or_else(|| prod_string.len()); // handle out-of-bounds
-
-    let sliced = prod_string[byte_index..].to_string();
-    
-    // Whitespace split
-    let production_array: Vec<&str>= sliced.split('|').collect();
-    for p in production_array{
-        let tem_str: Vec<&str> = p.split_whitespace().collect();
-        let tem_string:Vec<String> = tem_str.iter().map(|s| s.to_string()).collect();
-        for t in tem_str{
-            if !terminals.contains(t){
-                if !non_terminals.contains(t){
-                    non_terminals.insert(t.to_string());
+impl Desugar<'_>{
+    fn body(&mut self, head: &str, alt: &Alternative)->Result<Vec<String>, YalpError>{
+        let mut body = Vec::new();
+        for item in &alt.items{
+            match item{
+                Item::Symbol(s) => {
+                    if self.ignore.contains(&s.name){
+                        return Err(YalpError { msg: format!("`{}` is ignored and never reaches the parser", s.name), loc: s.loc });
+                    }
+                    // Any other symbol is a non terminal, maybe without
+                    // productions: the validation reports those
+                    if !self.terminals.contains(&s.name){
+                        self.non_terminals.insert(s.name.clone());
+                    }
+                    body.push(s.name.clone());
+                }
+                Item::Group { kind, alternatives, .. } => {
+                    let mut inner = Vec::new();
+                    for a in alternatives{
+                        inner.push(self.body(head, a)?);
+                    }
+                    body.push(self.group(head, *kind, inner));
                 }
             }
         }
-        prod_vec.push(tem_string);
+        Ok(body)
     }
-    // println!("Prods: {:?}", prod_vec);
-    // Return
-    (head,prod_vec)
-}
 
-// Called 
-pub fn read_yalpar(filename: &str)->GrammarInfo{
-    // 1. Section division (ignoring comments)
-    let mut counter = 0;
-    let mut is_prod_section= false;
-    let mut production_string = String::new();
-    let mut tsec: Vec<TokenAction> = Vec::new();
-    let mut psec: Vec<String> = Vec::new();
-    if let Ok(lines) = read_lines(filename){
-        for line in lines{
-            if let Ok(content) = line{
-                // No es comentario
-                if !content.starts_with("/*"){
-                    if content.starts_with("%%"){
-                        is_prod_section = !is_prod_section;
-                    } else{
-                        if !is_prod_section{
-                            if let Some(tem) = process_token(content, counter){
-                                tsec.push(tem);
-                            }
-                        } 
-                        else{
-                            if content.contains(";"){
-                                // Production ended
-                                psec.push(production_string.clone());
-                                production_string.drain(..);
-                            } else{
-                                production_string+=&content;
-                            }
-                        }
-                    }
-                }
-            }
-            counter+=1;
+    fn group(&mut self, head: &str, kind: GroupKind, inner: Vec<Vec<String>>)->String{
+        let key = (kind, inner);
+        if let Some(name) = self.generated.get(&key){
+            return name.clone();
+        }
+        let suffix = match kind{
+            GroupKind::Group => "group",
+            GroupKind::Optional => "opt",
+            GroupKind::Repeat | GroupKind::Repeat1 => "list",
+        };
+        let base = format!("{}_{}", head, suffix);
+        let mut name = base.clone();
+        let mut n = 2;
+        while self.non_terminals.contains(&name) || self.terminals.contains(&name) || self.ignore.contains(&name){
+            name = format!("{}{}", base, n);
+            n += 1;
         }
+
+        // Lists are left recursive, so the LR stack doesn't grow with them
+        let inner = &key.1;
+        let bodies: Vec<Vec<String>> = match kind{
+            GroupKind::Group => inner.clone(),
+            GroupKind::Optional => inner.iter().cloned().chain([Vec::new()]).collect(),
+            GroupKind::Repeat => inner.iter()
+                .map(|b| [vec![name.clone()], b.clone()].concat())
+                .chain([Vec::new()])
+                .collect(),
+            GroupKind::Repeat1 => inner.iter()
+                .map(|b| [vec![name.clone()], b.clone()].concat())
+                .chain(inner.iter().cloned())
+                .collect(),
+        };
+        self.non_terminals.insert(name.clone());
+        self.productions.insert(name.clone(), bodies);
+        self.generated.insert(key, name.clone());
+        name
     }
+}
 
-    // 2. Token section
+/// Checks the symbols of a parsed .yalp file and collects its grammar
+pub fn grammar_info(file: &YalpFile)->Result<GrammarInfo, YalpError>{
+    // 1. Token section
     let mut terminals: HashSet<String> = HashSet::new();
     let mut ignore: HashSet<String> = HashSet::new();
-
-    for t in tsec{
-        if t.do_ignore{
-            ignore.insert(t.name);
-        } else{
-            terminals.insert(t.name);
+    for t in &file.tokens{
+        if !terminals.insert(t.name.clone()){
+            return Err(YalpError { msg: format!("token `{}` is declared twice", t.name), loc: t.loc });
         }
     }
-    // println!("Ignore: {:?}\n",ignore);
-    // println!("Terminals: {:?}\n", terminals)
+    for t in &file.ignore{
+        if terminals.contains(&t.name){
+            return Err(YalpError { msg: format!("`{}` is declared both as %token and IGNORE", t.name), loc: t.loc });
+        }
+        ignore.insert(t.name.clone());
+    }
 
-    // 3. Process production section
-    let mut productions: HashMap<String, Vec<Vec<String>>> = HashMap::new();
-    let mut init_symbol = String::new();
+    // 2. Heads are the non terminals
     let mut non_terminals: HashSet<String> = HashSet::new();
-    for (id, p) in psec.iter().enumerate(){
-        if id == 0{
-
+    for p in &file.productions{
+        let head = &p.head;
+        if terminals.contains(&head.name) || ignore.contains(&head.name){
+            return Err(YalpError { msg: format!("token `{}` can't be the head of a production", head.name), loc: head.loc });
         }
-        let (head, prods) = process_production(
-            p.to_string(),
-            &mut non_terminals,
-            &terminals
-        );
-        if id == 0{
-            init_symbol = head.clone();
+        non_terminals.insert(head.name.clone());
+    }
+
+    // 3. Bodies; groups become new heads
+    let mut desugar = Desugar{
+        terminals: &terminals,
+        ignore: &ignore,
+        non_terminals,
+        generated: HashMap::new(),
+        productions: HashMap::new(),
+    };
+    for p in &file.productions{
+        for alt in &p.alternatives{
+            let body = desugar.body(&p.head.name, alt)?;
+            desugar.productions.entry(p.head.name.clone()).or_default().push(body);
         }
-        productions.insert(head, prods);
     }
+    let flatten: HashSet<String> = desugar.generated.into_values().collect();
+    let productions = desugar.productions;
+    let non_terminals = desugar.non_terminals;
 
-    // Return Result
-    
-    GrammarInfo{
-        productions: productions,
-        terminals: terminals,
-        non_terminals: non_terminals,
-        ignore: ignore,    
-        init_symbol: init_symbol,
+    // 4. Initial symbol
+    let init_symbol = match (&file.start, file.productions.first()){
+        (Some(start), _) => {
+            if !non_terminals.contains(&start.name){
+                return Err(YalpError { msg: format!("start symbol `{}` has no productions", start.name), loc: start.loc });
+            }
+            start.name.clone()
+        }
+        (None, Some(first)) => first.head.name.clone(),
+        (None, None) => return Err(YalpError { msg: "the grammar has no productions".to_string(), loc: Default::default() }),
+    };
+
+    Ok(GrammarInfo{
+        productions,
+        terminals,
+        non_terminals,
+        ignore,
+        init_symbol,
+        flatten,
+        precedence: Precedence::default(),
+    })
+}
+
+// Called
+pub fn read_yalpar(filename: &str)->GrammarInfo{
+    let source = fs::read_to_string(filename)
+        .unwrap_or_else(|e| panic!("~ Error Syn: couldn't read '{}': {}", filename, e));
+    match parse_yalp(&source).and_then(|file| grammar_info(&file)) {
+        Ok(grammar) => grammar,
+        Err(e) => panic!("~ Error Syn: {}:{}", filename, e),
     }
+}
 
-}
\ No newline at end of file
+/// Reads a .yalp grammar, or a Bison one if the file ends in `.y`
+pub fn read_grammar(filename: &str)->GrammarInfo{
+    if filename.ends_with(".y"){
+        read_bison(filename).grammar
+    } else {
+        read_yalpar(filename)
+    }
+}
@@ -0,0 +1,45 @@
+use std::fs;
+
+use super::yp_reader::GrammarInfo;
+
+fn sorted<'a>(names: impl Iterator<Item = &'a String>) -> Vec<&'a String> {
+    let mut v: Vec<&String> = names.collect();
+    v.sort();
+    v
+}
+
+/// Renders a grammar back as .yalp, with the start symbol's productions first
+pub fn yalp_source(grammar: &GrammarInfo) -> String {
+    let mut out = String::new();
+    for t in sorted(grammar.terminals.iter()) {
+        out += &format!("%token {}\n", t);
+    }
+    if !grammar.ignore.is_empty() {
+        let ignore: Vec<&str> = sorted(grammar.ignore.iter()).into_iter().map(|s| s.as_str()).collect();
+        out += &format!("IGNORE {}\n", ignore.join(" "));
+    }
+    out += &format!("%start {}\n\n%%\n", grammar.init_symbol);
+
+    let mut heads = sorted(grammar.productions.keys().filter(|h| **h != grammar.init_symbol));
+    if grammar.productions.contains_key(&grammar.init_symbol) {
+        heads.insert(0, &grammar.init_symbol);
+    }
+    for head in heads {
+        out += &format!("{}:\n", head);
+        for (i, body) in grammar.productions[head].iter().enumerate() {
+            let sep = if i == 0 { " " } else { "|" };
+            if body.is_empty() {
+                out += &format!("    {} %empty\n", sep);
+            } else {
+                out += &format!("    {} {}\n", sep, body.join(" "));
+            }
+        }
+        out += ";\n\n";
+    }
+    out
+}
+
+pub fn write_yalpar(filename: &str, grammar: &GrammarInfo) {
+    fs::write(filename, yalp_source(grammar))
+        .unwrap_or_else(|e| panic!("~ Error Syn: couldn't write '{}': {}", filename, e));
+}
@@ -0,0 +1,332 @@
+//! Command line of `syntax_analyzer`: subcommands, flags and exit codes
+//!
+//! Only the arguments are read here; `main` runs the commands. Flags may go
+//! anywhere and override the config files, which are optional.
+
+use std::{
+    fmt, fs,
+    panic::{self, UnwindSafe},
+    path::PathBuf,
+};
+
+use super::read_config::{config_layers, Config, ParseMethod};
+
+pub const USAGE: &str = "Usage: syntax_analyzer <command> [flags]
+
+Commands:
+    generate <lex.yal> <syn.yalp>          Generate the lexer and parser tables
+    parse <lex> <syn> <input>              Parse an input with a .yal and .yalp, or the
+                                           .ron or .bin written by generate
+    check <lex.yal> <syn.yalp>             Validate the grammar and look for conflicts
+    explain-conflicts <syn.yalp>           Describe every conflict of the LR tables
+    repl <lex.yal> <syn.yalp>              Try inputs on a grammar interactively
+    render <file>...                       Draw the DFA of a .yal and the automaton of a .yalp
+    first-follow <syn.yalp>                Print the FIRST and FOLLOW sets
+    transform <syn.yalp> <out.yalp>        Rewrite the grammar for LL-style parsers
+    bison <grammar.y> <out.yalp>           Convert a Bison grammar
+    export <antlr4|ebnf> <syn> <lex> <out> Export the grammar and its lexer
+    config init [config.toml|.yaml]        Write the default config, every key commented
+
+Flags:
+    --config <path>        Configuration file, over ./config.json and <grammar>.config.json
+    --out-dir <dir>        Where to write: a parser crate for generate, drawings for render
+    --method <slr|lalr|lr1>
+    --static               With generate --out-dir, write the tables as Rust statics
+    --runtime <dir|version>
+                           With generate --out-dir, the syntax_analyzer the crate depends
+                           on: a checkout or a registry version; this one by default
+    --binary               With generate, write the tables as compact .bin files
+    --compress             With generate, pack ACTION and GOTO with default reductions and
+                           row displacement
+    --watch                With generate, generate again whenever the files change
+    --sample <input>       With --watch, an input to parse after every generation (watched too)
+    --format <text|json|jsonl>
+                           With parse, print the tokens, the tree and the errors as one JSON
+                           document, or as JSON Lines
+    -q, --quiet            No progress logs
+    -h, --help
+
+Every SYNAN_* variable overrides a setting: SYNAN_PARSE_METHOD, SYNAN_DEBUG_GENERATION,
+SYNAN_VIS_SLR_PNG... Config files may be JSON, TOML or YAML. Paths may use {grammar}
+and {method}.
+
+Exit codes: 0 ok, 1 I/O error, 2 bad usage, 3 grammar error, 4 lexical error, 5 syntax error";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Generate { lex: String, syn: String },
+    Parse { lex: String, syn: String, input: String },
+    Check { lex: String, syn: String },
+    ExplainConflicts { syn: String },
+    Repl { lex: String, syn: String },
+    Render { files: Vec<String> },
+    FirstFollow { syn: String },
+    Transform { syn: String, out: String },
+    Bison { input: String, out: String },
+    Export { format: String, syn: String, lex: String, out: String },
+    ConfigInit { path: String },
+    Help,
+}
+
+impl Command {
+    /// Files the command reads: grammars, serialized analyzers and inputs
+    pub fn inputs(&self) -> Vec<&str> {
+        match self {
+            Command::Generate { lex, syn } | Command::Check { lex, syn } | Command::Repl { lex, syn } => vec![lex, syn],
+            Command::Parse { lex, syn, input } => vec![lex, syn, input],
+            Command::ExplainConflicts { syn } | Command::FirstFollow { syn } | Command::Transform { syn, .. } => vec![syn],
+            Command::Render { files } => files.iter().map(|f| f.as_str()).collect(),
+            Command::Bison { input, .. } => vec![input],
+            Command::Export { syn, lex, .. } => vec![syn, lex],
+            Command::ConfigInit { .. } | Command::Help => Vec::new(),
+        }
+    }
+}
+
+/// How `parse` prints its results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    JsonLines,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Options {
+    pub config: Option<String>,
+    pub out_dir: Option<String>,
+    pub method: Option<ParseMethod>,
+    pub static_tables: bool,
+    pub runtime: Option<String>,
+    pub binary: bool,
+    pub compress: bool,
+    pub quiet: bool,
+    pub watch: bool,
+    pub sample: Option<String>,
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cli {
+    pub command: Command,
+    pub options: Options,
+}
+
+/// Why a command failed, each with its own exit code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliError {
+    Io(String),
+    Usage(String),
+    // The .yal, .yalp, or the tables built from them
+    Grammar(String),
+    // The input, by the lexer and by the parser
+    Lexical,
+    Syntax(String),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Io(_) => 1,
+            CliError::Usage(_) => 2,
+            CliError::Grammar(_) => 3,
+            CliError::Lexical => 4,
+            CliError::Syntax(_) => 5,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Io(msg) => write!(f, "~ Error: {}", msg),
+            CliError::Usage(msg) => write!(f, "~ Error: {}\n\n{}", msg, USAGE),
+            CliError::Grammar(msg) => write!(f, "~ Error Syn: {}", msg),
+            CliError::Lexical => write!(f, "~ Error Lex: the input has lexical errors"),
+            CliError::Syntax(msg) => write!(f, "~ Error Syn: {}", msg),
+        }
+    }
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(e: std::io::Error) -> Self {
+        CliError::Io(e.to_string())
+    }
+}
+
+/// An I/O error for files that can't be read, before the library panics
+/// on them as it does on errors in the grammar files
+pub fn readable(path: &str) -> Result<(), CliError> {
+    fs::File::open(path)
+        .map(|_| ())
+        .map_err(|e| CliError::Io(format!("couldn't read '{}': {}", path, e)))
+}
+
+/// Runs a step of the library, which panics on errors in the grammar files
+pub fn guarded<T>(f: impl FnOnce() -> T + UnwindSafe) -> Result<T, CliError> {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(f);
+    panic::set_hook(hook);
+    result.map_err(|e| {
+        let msg = e
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| e.downcast_ref::<&str>().map(|s| s.to_string()))
+            .unwrap_or_default();
+        // The library already prefixes its messages
+        CliError::Grammar(msg.trim_start_matches("~ Error Syn: ").trim_start_matches("~ Error Lex: ").to_string())
+    })
+}
+
+pub fn parse_method(name: &str) -> Result<ParseMethod, CliError> {
+    match name.to_lowercase().as_str() {
+        "slr" => Ok(ParseMethod::SLR),
+        "lalr" => Ok(ParseMethod::LALR),
+        "lr1" => Ok(ParseMethod::LR1),
+        _ => Err(CliError::Usage(format!("unknown method '{}', expected slr, lalr or lr1", name))),
+    }
+}
+
+pub fn output_format(name: &str) -> Result<OutputFormat, CliError> {
+    match name.to_lowercase().as_str() {
+        "text" => Ok(OutputFormat::Text),
+        "json" => Ok(OutputFormat::Json),
+        "jsonl" => Ok(OutputFormat::JsonLines),
+        _ => Err(CliError::Usage(format!("unknown format '{}', expected text, json or jsonl", name))),
+    }
+}
+
+/// Reads the arguments, without the program name. The old
+/// `syntax_analyzer lex.yal syn.yalp` is still taken as `generate`.
+pub fn parse_args(args: &[String]) -> Result<Cli, CliError> {
+    let mut options = Options::default();
+    let mut positional: Vec<String> = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        // `--flag value` or `--flag=value`
+        let (flag, inline) = match arg.split_once('=') {
+            Some((f, v)) if f.starts_with("--") => (f, Some(v.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = |name: &str| {
+            inline
+                .clone()
+                .or_else(|| args.next().cloned())
+                .ok_or_else(|| CliError::Usage(format!("{} needs a value", name)))
+        };
+        match flag {
+            "--config" => options.config = Some(value(flag)?),
+            "--out-dir" => options.out_dir = Some(value(flag)?),
+            "--method" => options.method = Some(parse_method(&value(flag)?)?),
+            "--static" => options.static_tables = true,
+            "--runtime" => options.runtime = Some(value(flag)?),
+            "--binary" => options.binary = true,
+            "--compress" => options.compress = true,
+            "--watch" => options.watch = true,
+            "--sample" => options.sample = Some(value(flag)?),
+            "--format" => options.format = output_format(&value(flag)?)?,
+            "-q" | "--quiet" => options.quiet = true,
+            "-h" | "--help" => {
+                return Ok(Cli { command: Command::Help, options });
+            }
+            f if f.starts_with('-') && f.len() > 1 => {
+                return Err(CliError::Usage(format!("unknown flag '{}'", f)));
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let p = &positional;
+    let arity = |n: usize| match p.len() - 1 == n {
+        true => Ok(()),
+        false => Err(CliError::Usage(format!("'{}' takes {} arguments, found {}", p[0], n, p.len() - 1))),
+    };
+    let command = match p.first().map(|s| s.as_str()) {
+        None => Command::Help,
+        Some("generate") => arity(2).map(|_| Command::Generate { lex: p[1].clone(), syn: p[2].clone() })?,
+        Some("parse") => {
+            arity(3).map(|_| Command::Parse { lex: p[1].clone(), syn: p[2].clone(), input: p[3].clone() })?
+        }
+        Some("check") => arity(2).map(|_| Command::Check { lex: p[1].clone(), syn: p[2].clone() })?,
+        Some("repl") => arity(2).map(|_| Command::Repl { lex: p[1].clone(), syn: p[2].clone() })?,
+        Some("explain-conflicts") => arity(1).map(|_| Command::ExplainConflicts { syn: p[1].clone() })?,
+        Some("render") if p.len() > 1 => Command::Render { files: p[1..].to_vec() },
+        Some("render") => return Err(CliError::Usage("'render' needs a .yal or .yalp".to_string())),
+        Some("first-follow") => arity(1).map(|_| Command::FirstFollow { syn: p[1].clone() })?,
+        Some("transform") => arity(2).map(|_| Command::Transform { syn: p[1].clone(), out: p[2].clone() })?,
+        Some("bison") => arity(2).map(|_| Command::Bison { input: p[1].clone(), out: p[2].clone() })?,
+        Some("export") => arity(4).map(|_| Command::Export {
+            format: p[1].clone(),
+            syn: p[2].clone(),
+            lex: p[3].clone(),
+            out: p[4].clone(),
+        })?,
+        Some("config") => match (p.get(1).map(|s| s.as_str()), p.len()) {
+            (Some("init"), 2) => Command::ConfigInit { path: "config.toml".to_string() },
+            (Some("init"), 3) => Command::ConfigInit { path: p[2].clone() },
+            (Some("init"), n) => return Err(CliError::Usage(format!("'config init' takes 1 argument, found {}", n - 2))),
+            _ => return Err(CliError::Usage("expected 'config init'".to_string())),
+        },
+        Some(_) if p.len() == 2 => Command::Generate { lex: p[0].clone(), syn: p[1].clone() },
+        Some(c) => return Err(CliError::Usage(format!("unknown command '{}'", c))),
+    };
+    if options.static_tables && options.out_dir.is_none() {
+        return Err(CliError::Usage("--static needs --out-dir".to_string()));
+    }
+    if options.runtime.is_some() && options.out_dir.is_none() {
+        return Err(CliError::Usage("--runtime needs --out-dir".to_string()));
+    }
+    if options.binary && !matches!(command, Command::Generate { .. }) {
+        return Err(CliError::Usage("--binary only works with generate".to_string()));
+    }
+    if options.compress && !matches!(command, Command::Generate { .. }) {
+        return Err(CliError::Usage("--compress only works with generate".to_string()));
+    }
+    if options.compress && options.static_tables {
+        return Err(CliError::Usage("--static writes the tables uncompressed, drop --compress".to_string()));
+    }
+    if options.binary && options.static_tables {
+        return Err(CliError::Usage("--binary and --static are different formats, pick one".to_string()));
+    }
+    if options.watch && !matches!(command, Command::Generate { .. }) {
+        return Err(CliError::Usage("--watch only works with generate".to_string()));
+    }
+    if options.sample.is_some() && !options.watch {
+        return Err(CliError::Usage("--sample needs --watch".to_string()));
+    }
+    if options.format != OutputFormat::Text && !matches!(command, Command::Parse { .. }) {
+        return Err(CliError::Usage("--format only works with parse".to_string()));
+    }
+    Ok(Cli { command, options })
+}
+
+impl Options {
+    /// Config files for `grammars`, lowest priority first: ./config.json,
+    /// `<grammar>.config.json` next to each grammar, then `--config`
+    pub fn config_layers(&self, grammars: &[&str]) -> Result<Vec<PathBuf>, CliError> {
+        config_layers(grammars, self.config.as_deref()).map_err(CliError::Io)
+    }
+
+    /// Every layer merged, then the `SYNAN_*` variables, then `--method`
+    /// and `--quiet`, implied by a JSON `--format`. `{grammar}` is left in
+    /// the paths, see `Config::for_grammar`. Unknown keys are only warned
+    /// about.
+    pub fn config(&self, grammars: &[&str]) -> Result<Config, CliError> {
+        let layers = self.config_layers(grammars)?;
+        let (mut config, warnings) = Config::from_layers(&layers, std::env::vars())
+            .map_err(|e| CliError::Io(format!("couldn't read config: {}", e)))?;
+        for warning in warnings {
+            eprintln!("~ Warning Config: {}", warning);
+        }
+        if let Some(method) = self.method {
+            config.parse_method = method;
+        }
+        if self.quiet || self.format != OutputFormat::Text {
+            config.debug.generation = false;
+            config.debug.parsing = false;
+        }
+        Ok(config)
+    }
+}
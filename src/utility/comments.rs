@@ -0,0 +1,156 @@
+use std::fmt;
+
+/// Comment syntax of a grammar file.
+pub struct CommentStyle {
+    pub open: &'static str,
+    pub close: &'static str,
+    // Whether `open` inside a comment starts a nested one (OCaml style)
+    pub nested: bool,
+    // Whether `{ ... }` blocks hold host code where comments must not be searched
+    pub code_blocks: bool,
+}
+
+/// `(* ... *)`, nestable, as in ocamllex. Actions in braces are left untouched.
+pub const YAL_COMMENTS: CommentStyle = CommentStyle {
+    open: "(*",
+    close: "*)",
+    nested: true,
+    code_blocks: true,
+};
+
+/// `/* ... */`, as in yacc.
+pub const YALP_COMMENTS: CommentStyle = CommentStyle {
+    open: "/*",
+    close: "*/",
+    nested: false,
+    code_blocks: false,
+};
+
+#[derive(Debug, PartialEq)]
+pub struct UnterminatedComment {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for UnterminatedComment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unterminated comment opened at line {}, column {}",
+            self.line, self.col
+        )
+    }
+}
+
+/// Replaces every comment in `src` with spaces. Newlines are kept, so line
+/// and column numbers of the remaining text stay the same.
+pub fn strip_comments(src: &str, style: &CommentStyle) -> Result<String, UnterminatedComment> {
+    let chars: Vec<char> = src.chars().collect();
+    let open: Vec<char> = style.open.chars().collect();
+    let close: Vec<char> = style.close.chars().collect();
+    let mut out = String::with_capacity(src.len());
+
+    let mut i = 0;
+    let mut line = 1;
+    let mut col = 1;
+    // Comment depth and where the outermost one was opened
+    let mut depth = 0;
+    let mut opened_at = (0, 0);
+    let mut brace_depth = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if depth > 0 {
+            if chars[i..].starts_with(&close) {
+                depth -= 1;
+                for _ in 0..close.len() {
+                    out.push(' ');
+                }
+                i += close.len();
+                col += close.len();
+                continue;
+            }
+            if style.nested && chars[i..].starts_with(&open) {
+                depth += 1;
+                for _ in 0..open.len() {
+                    out.push(' ');
+                }
+                i += open.len();
+                col += open.len();
+                continue;
+            }
+            out.push(if c == '\n' { '\n' } else { ' ' });
+        } else if c == '"' || is_char_literal(&chars[i..]) {
+            // Copy string and char literals verbatim
+            let end = literal_end(&chars, i);
+            for &lc in &chars[i..end] {
+                out.push(lc);
+                if lc == '\n' {
+                    line += 1;
+                    col = 1;
+                } else {
+                    col += 1;
+                }
+            }
+            i = end;
+            continue;
+        } else if brace_depth == 0 && chars[i..].starts_with(&open) {
+            depth = 1;
+            opened_at = (line, col);
+            for _ in 0..open.len() {
+                out.push(' ');
+            }
+            i += open.len();
+            col += open.len();
+            continue;
+        } else {
+            if style.code_blocks {
+                if c == '{' {
+                    brace_depth += 1;
+                } else if c == '}' && brace_depth > 0 {
+                    brace_depth -= 1;
+                }
+            }
+            out.push(c);
+        }
+
+        if c == '\n' {
+            line += 1;
+            col = 1;
+        } else {
+            col += 1;
+        }
+        i += 1;
+    }
+
+    if depth > 0 {
+        return Err(UnterminatedComment {
+            line: opened_at.0,
+            col: opened_at.1,
+        });
+    }
+    Ok(out)
+}
+
+// 'c' or '\c', but not a lifetime like 'static
+fn is_char_literal(rest: &[char]) -> bool {
+    match rest {
+        ['\'', '\\', _, '\'', ..] => true,
+        ['\'', c, '\'', ..] => *c != '\\',
+        _ => false,
+    }
+}
+
+// Index right after the literal starting at `start`
+fn literal_end(chars: &[char], start: usize) -> usize {
+    let quote = chars[start];
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            c if c == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    chars.len()
+}
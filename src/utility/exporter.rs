@@ -0,0 +1,308 @@
+//! Grammar exporters: ANTLR4 combined grammars (.g4) and W3C-style EBNF
+//!
+//! Parser rules come from the productions of a `GrammarInfo`, with the start
+//! symbol first. Lexer rules come from the .yal entries whose action returns
+//! a token (`return "ID";`), in the order of the .yal so the priorities hold;
+//! entries returning nothing, or an IGNORE token, are skipped by the lexer.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    lex::{
+        yl_parser::{Regex, SetItem, YalFile},
+        yl_reader::action_token,
+    },
+    syn::yp_reader::GrammarInfo,
+};
+
+/// A lexer rule: every .yal entry returning the same token is an alternative
+struct LexRule {
+    token: Option<String>,
+    alternatives: Vec<Regex>,
+}
+
+fn lexer_rules(file: &YalFile) -> Vec<LexRule> {
+    let mut rules: Vec<LexRule> = Vec::new();
+    for entry in file.rules.iter().flat_map(|r| &r.entries) {
+        let token = action_token(&entry.action);
+        match rules.iter_mut().find(|r| token.is_some() && r.token == token) {
+            Some(rule) => rule.alternatives.push(entry.regex.clone()),
+            None => rules.push(LexRule { token, alternatives: vec![entry.regex.clone()] }),
+        }
+    }
+    rules
+}
+
+/// Start symbol first, then the rest of the heads in name order
+fn ordered_heads(grammar: &GrammarInfo) -> Vec<&String> {
+    let mut heads: Vec<&String> = grammar.productions.keys().filter(|h| **h != grammar.init_symbol).collect();
+    heads.sort();
+    if grammar.productions.contains_key(&grammar.init_symbol) {
+        heads.insert(0, &grammar.init_symbol);
+    }
+    heads
+}
+
+/// Text of a quoted .yalp symbol such as `'\n'`, without quotes or escapes
+fn unquote(symbol: &str) -> String {
+    let mut out = String::new();
+    let mut chars = symbol[1..symbol.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some(e) => out.push(e),
+            None => {}
+        }
+    }
+    out
+}
+
+fn is_quoted(symbol: &str) -> bool {
+    symbol.len() > 2 && (symbol.starts_with('\'') || symbol.starts_with('"'))
+}
+
+/// Writes a regex in either notation, which only differ in their literals
+fn write_regex(
+    regex: &Regex,
+    literal: &dyn Fn(&str) -> String,
+    set_char: &dyn Fn(char) -> String,
+    vars: &HashMap<String, String>,
+) -> String {
+    let sub = |r: &Regex| write_regex(r, literal, set_char, vars);
+    match regex {
+        Regex::Literal(s) => literal(s),
+        Regex::Set(items) => {
+            let inner: String = items
+                .iter()
+                .map(|i| match i {
+                    SetItem::Char(c) => set_char(*c),
+                    SetItem::Range(a, b) => format!("{}-{}", set_char(*a), set_char(*b)),
+                })
+                .collect();
+            format!("[{}]", inner)
+        }
+        Regex::Var(name, _) => vars[name].clone(),
+        Regex::Concat(parts) => parts
+            .iter()
+            .map(|p| if matches!(p, Regex::Concat(_)) { format!("({})", sub(p)) } else { sub(p) })
+            .collect::<Vec<_>>()
+            .join(" "),
+        Regex::Union(options) => {
+            format!("({})", options.iter().map(sub).collect::<Vec<_>>().join(" | "))
+        }
+        Regex::Kleene(inner) | Regex::Plus(inner) | Regex::Optional(inner) => {
+            let operand = if matches!(**inner, Regex::Concat(_) | Regex::Kleene(_) | Regex::Plus(_) | Regex::Optional(_)) {
+                format!("({})", sub(inner))
+            } else {
+                sub(inner)
+            };
+            let op = match regex {
+                Regex::Kleene(_) => '*',
+                Regex::Plus(_) => '+',
+                _ => '?',
+            };
+            format!("{}{}", operand, op)
+        }
+    }
+}
+
+/// Makes `name` unique among `taken`, appending `_` as needed
+fn unique(mut name: String, taken: &mut HashSet<String>) -> String {
+    while !taken.insert(name.clone()) {
+        name.push('_');
+    }
+    name
+}
+
+fn clean(name: &str) -> String {
+    name.chars().map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' }).collect()
+}
+
+fn with_first(name: &str, upper: bool) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if upper => c.to_uppercase().chain(chars).collect(),
+        Some(c) => c.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/*──────────────────────────────────────────────────────────────*/
+/* ANTLR4                                                       */
+/*──────────────────────────────────────────────────────────────*/
+
+fn antlr_escape(c: char) -> String {
+    match c {
+        '\n' => "\\n".to_string(),
+        '\t' => "\\t".to_string(),
+        '\r' => "\\r".to_string(),
+        '\\' => "\\\\".to_string(),
+        '\'' => "\\'".to_string(),
+        c => c.to_string(),
+    }
+}
+
+fn antlr_set_char(c: char) -> String {
+    match c {
+        ']' | '-' => format!("\\{}", c),
+        c => antlr_escape(c),
+    }
+}
+
+/// ANTLR4 combined grammar named `name`. Parser rules must start in
+/// lowercase and lexer rules in uppercase, so symbols are renamed as needed.
+pub fn antlr4(name: &str, grammar: &GrammarInfo, lexer: Option<&YalFile>) -> String {
+    let mut taken: HashSet<String> = HashSet::new();
+    let mut names: HashMap<String, String> = HashMap::new();
+    for t in grammar.terminals.iter().chain(&grammar.ignore) {
+        let renamed = if is_quoted(t) {
+            format!("'{}'", unquote(t).chars().map(antlr_escape).collect::<String>())
+        } else {
+            unique(with_first(&clean(t), true), &mut taken)
+        };
+        names.insert(t.clone(), renamed);
+    }
+    let mut heads: Vec<&String> = grammar.non_terminals.iter().collect();
+    heads.sort();
+    for nt in heads {
+        names.insert(nt.clone(), unique(with_first(&clean(nt), false), &mut taken));
+    }
+
+    let mut out = format!("grammar {};\n\n", name);
+
+    // 1. Parser rules
+    for head in ordered_heads(grammar) {
+        let bodies: Vec<String> = grammar.productions[head]
+            .iter()
+            .map(|b| match b.is_empty() {
+                true => "/* empty */".to_string(),
+                false => b.iter().map(|s| names[s].clone()).collect::<Vec<_>>().join(" "),
+            })
+            .collect();
+        out += &format!("{}\n    : {}\n    ;\n\n", names[head], bodies.join("\n    | "));
+    }
+
+    // 2. Lexer rules, with the .yal variables as fragments
+    let Some(file) = lexer else {
+        return out;
+    };
+    let literal = |s: &str| format!("'{}'", s.chars().map(antlr_escape).collect::<String>());
+    let mut vars: HashMap<String, String> = HashMap::new();
+    for l in &file.lets {
+        let fragment = unique(with_first(&clean(&l.name), true), &mut taken);
+        let body = write_regex(&l.regex, &literal, &antlr_set_char, &vars);
+        out += &format!("fragment {}\n    : {}\n    ;\n\n", fragment, body);
+        vars.insert(l.name.clone(), fragment);
+    }
+    for (i, rule) in lexer_rules(file).iter().enumerate() {
+        let (rule_name, skip) = match &rule.token {
+            Some(t) if !is_quoted(t) => {
+                let name = match names.get(t) {
+                    Some(n) => n.clone(),
+                    None => unique(with_first(&clean(t), true), &mut taken),
+                };
+                (name, grammar.ignore.contains(t))
+            }
+            Some(t) => (unique(format!("LIT_{}", i), &mut taken), grammar.ignore.contains(t)),
+            None => (unique(format!("SKIP_{}", i), &mut taken), true),
+        };
+        let bodies: Vec<String> = rule.alternatives.iter().map(|r| write_regex(r, &literal, &antlr_set_char, &vars)).collect();
+        out += &format!(
+            "{}\n    : {}{}\n    ;\n\n",
+            rule_name,
+            bodies.join("\n    | "),
+            if skip { " -> skip" } else { "" }
+        );
+    }
+    out
+}
+
+/*──────────────────────────────────────────────────────────────*/
+/* W3C EBNF                                                     */
+/*──────────────────────────────────────────────────────────────*/
+
+fn w3c_char(c: char) -> String {
+    if c.is_ascii_graphic() && c != '[' && c != ']' && c != '-' && c != '^' {
+        c.to_string()
+    } else {
+        format!("#x{:X}", c as u32)
+    }
+}
+
+/// Literals can't hold escapes; anything that isn't printable is a `#xN`
+fn w3c_literal(s: &str) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let flush = |current: &mut String, parts: &mut Vec<String>| {
+        if !current.is_empty() {
+            let quote = if current.contains('\'') { '"' } else { '\'' };
+            parts.push(format!("{}{}{}", quote, current, quote));
+            current.clear();
+        }
+    };
+    for c in s.chars() {
+        // A literal can't hold both quotes, nor a quote after a double one
+        if c.is_ascii_graphic() || c == ' ' {
+            if (c == '"' && current.contains('\'')) || (c == '\'' && current.contains('"')) {
+                flush(&mut current, &mut parts);
+            }
+            current.push(c);
+        } else {
+            flush(&mut current, &mut parts);
+            parts.push(format!("#x{:X}", c as u32));
+        }
+    }
+    flush(&mut current, &mut parts);
+    if parts.len() == 1 {
+        parts.remove(0)
+    } else {
+        format!("({})", parts.join(" "))
+    }
+}
+
+/// W3C-style EBNF, as in the XML recommendation (`Name ::= ...`)
+pub fn w3c_ebnf(grammar: &GrammarInfo, lexer: Option<&YalFile>) -> String {
+    let symbol = |s: &String| if is_quoted(s) { w3c_literal(&unquote(s)) } else { s.clone() };
+
+    // 1. Parser rules
+    let mut out = String::new();
+    for head in ordered_heads(grammar) {
+        let bodies: Vec<String> = grammar.productions[head]
+            .iter()
+            .map(|b| match b.is_empty() {
+                true => "/* empty */".to_string(),
+                false => b.iter().map(symbol).collect::<Vec<_>>().join(" "),
+            })
+            .collect();
+        let pad = " ".repeat(head.chars().count());
+        out += &format!("{} ::= {}\n", head, bodies.join(&format!("\n{}   | ", pad)));
+    }
+
+    // 2. Lexer rules and variables
+    let Some(file) = lexer else {
+        return out;
+    };
+    out += "\n/* Tokens */\n";
+    let mut vars: HashMap<String, String> = HashMap::new();
+    for l in &file.lets {
+        let body = write_regex(&l.regex, &w3c_literal, &w3c_char, &vars);
+        out += &format!("{} ::= {}\n", l.name, body);
+        vars.insert(l.name.clone(), l.name.clone());
+    }
+    for rule in lexer_rules(file) {
+        let name = match &rule.token {
+            Some(t) if !is_quoted(t) => t.clone(),
+            // Quoted tokens are written as literals in the productions
+            _ => continue,
+        };
+        let bodies: Vec<String> = rule.alternatives.iter().map(|r| write_regex(r, &w3c_literal, &w3c_char, &vars)).collect();
+        out += &format!("{} ::= {}\n", name, bodies.join(" | "));
+    }
+    out
+}
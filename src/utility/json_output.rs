@@ -0,0 +1,190 @@
+//! `parse --format json`: the tokens, the parse tree and the diagnostics of
+//! an input, for editors and test harnesses
+//!
+//! `json` prints a single document. `jsonl` prints one object per line, each
+//! with its `type`: every token, every parsing step, the diagnostics, the
+//! tree and, last, the `end` with the outcome.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::cli::CliError;
+use crate::{
+    codegen::runtime::Lexer,
+    lex::lex_analyzer::{Kind, Symbol},
+    syn::{
+        parse_tree::ParseNode,
+        syn_analyzer::{ParsingStep, SynAnalyzer},
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticKind {
+    // The files couldn't be read, or the analyzers generated
+    Io,
+    Grammar,
+    Lexical,
+    Syntax,
+}
+
+impl DiagnosticKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DiagnosticKind::Io => "io",
+            DiagnosticKind::Grammar => "grammar",
+            DiagnosticKind::Lexical => "lexical",
+            DiagnosticKind::Syntax => "syntax",
+        }
+    }
+}
+
+/// Where a diagnostic is in the input: byte offsets and line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+}
+
+impl Location {
+    pub fn of<K>(symbol: &Symbol<K>) -> Location {
+        Location { start: symbol.start, end: symbol.end, line: symbol.line }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub message: String,
+    // None at the end of the input, and for the files
+    pub location: Option<Location>,
+}
+
+/// Everything a parse produced, errors included
+pub struct Report<K = String> {
+    // Without the IGNORE ones, as the parser takes them
+    pub tokens: Vec<Symbol<K>>,
+    pub steps: Vec<ParsingStep>,
+    pub tree: Option<ParseNode>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl<K: Kind> Report<K> {
+    /// Lexes and parses `input` like `runtime::parse`, but collecting every
+    /// lexic error instead of printing them. Nothing is printed.
+    pub fn new(lexer: &Lexer<K>, syn: &SynAnalyzer, input: &str) -> Report<K> {
+        let (raw, _) = lexer.analyzer.scan(input.to_string());
+        let mut tokens = Vec::new();
+        let mut diagnostics = Vec::new();
+        for s in raw {
+            if s.token == "!error" {
+                diagnostics.push(Diagnostic {
+                    kind: DiagnosticKind::Lexical,
+                    message: format!("unknown characters `{}`", s.content),
+                    location: Some(Location::of(&s)),
+                });
+            } else if let Some(kind) = lexer.kind(&s) {
+                if !syn.ignore.contains(kind.name()) {
+                    let kind = kind.clone();
+                    tokens.push(s.with_kind(kind));
+                }
+            }
+        }
+        if !diagnostics.is_empty() {
+            return Report { tokens, steps: Vec::new(), tree: None, diagnostics };
+        }
+
+        let (steps, tree) = syn.parse_tree_silent(&tokens);
+        let tree = match tree {
+            Ok(tree) => Some(tree),
+            Err(error) => {
+                diagnostics.push(Diagnostic {
+                    kind: DiagnosticKind::Syntax,
+                    location: tokens.get(error.index).map(Location::of),
+                    message: error.message,
+                });
+                None
+            }
+        };
+        Report { tokens, steps, tree, diagnostics }
+    }
+
+    /// A report with only the error that kept the input from being parsed
+    pub fn failed(error: &CliError) -> Report<K> {
+        let (kind, message) = match error {
+            CliError::Io(msg) | CliError::Usage(msg) => (DiagnosticKind::Io, msg.clone()),
+            CliError::Grammar(msg) => (DiagnosticKind::Grammar, msg.clone()),
+            CliError::Lexical => (DiagnosticKind::Lexical, "the input has lexical errors".to_string()),
+            CliError::Syntax(msg) => (DiagnosticKind::Syntax, msg.clone()),
+        };
+        Report {
+            tokens: Vec::new(),
+            steps: Vec::new(),
+            tree: None,
+            diagnostics: vec![Diagnostic { kind, message, location: None }],
+        }
+    }
+
+    pub fn ok(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// The error to exit with, the first diagnostic
+    pub fn error(&self) -> Option<CliError> {
+        let first = self.diagnostics.first()?;
+        Some(match first.kind {
+            DiagnosticKind::Io => CliError::Io(first.message.clone()),
+            DiagnosticKind::Grammar => CliError::Grammar(first.message.clone()),
+            DiagnosticKind::Lexical => CliError::Lexical,
+            DiagnosticKind::Syntax => CliError::Syntax(first.message.clone()),
+        })
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "ok": self.ok(),
+            "tokens": self.tokens.iter().map(symbol_json).collect::<Vec<_>>(),
+            "steps": self.steps,
+            "tree": self.tree.as_ref().map(node_json),
+            "diagnostics": self.diagnostics.iter().map(diagnostic_json).collect::<Vec<_>>(),
+        })
+    }
+
+    pub fn to_json_lines(&self) -> Vec<String> {
+        let tagged = |kind: &str, mut value: Value| {
+            value["type"] = json!(kind);
+            value.to_string()
+        };
+        let mut lines: Vec<String> = Vec::new();
+        lines.extend(self.tokens.iter().map(|s| tagged("token", symbol_json(s))));
+        lines.extend(self.steps.iter().map(|s| tagged("step", json!(s))));
+        lines.extend(self.diagnostics.iter().map(|d| tagged("diagnostic", diagnostic_json(d))));
+        if let Some(tree) = &self.tree {
+            lines.push(tagged("tree", json!({ "tree": node_json(tree) })));
+        }
+        lines.push(tagged("end", json!({ "ok": self.ok() })));
+        lines
+    }
+}
+
+fn symbol_json<K: Kind>(s: &Symbol<K>) -> Value {
+    json!({ "token": s.token_name.name(), "content": s.content, "start": s.start, "end": s.end, "line": s.line })
+}
+
+fn node_json(n: &ParseNode) -> Value {
+    match &n.content {
+        Some(content) => json!({ "symbol": n.symbol, "content": content }),
+        None => json!({ "symbol": n.symbol, "children": n.children.iter().map(node_json).collect::<Vec<_>>() }),
+    }
+}
+
+fn diagnostic_json(d: &Diagnostic) -> Value {
+    let mut value = json!({ "severity": "error", "kind": d.kind.name(), "message": d.message });
+    if let Some(at) = d.location {
+        value["start"] = json!(at.start);
+        value["end"] = json!(at.end);
+        value["line"] = json!(at.line);
+    }
+    value
+}
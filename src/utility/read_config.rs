@@ -1,16 +1,35 @@
 use serde::{Deserialize, Serialize};
-use std::{fs::File, io::Read, path::Path};
+use serde_json::Value;
+use std::{
+    collections::HashSet,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::syn::validation::closest;
+
+/// Prefijo de las variables de entorno que sobreescriben la configuración:
+/// `SYNAN_PARSE_METHOD`, `SYNAN_DEBUG_GENERATION`, `SYNAN_VIS_SLR_PNG`...
+pub const ENV_PREFIX: &str = "SYNAN_";
+
+/// Formatos aceptados, en el orden en que se buscan junto a cada capa
+pub const CONFIG_EXTENSIONS: [&str; 4] = ["json", "toml", "yaml", "yml"];
 
 /// Método de parsing posible
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum ParseMethod {
     SLR,
     LALR,
+    // LR(1) canónico: más estados que LALR, sin sus conflictos reduce/reduce
+    LR1,
 }
 
-/// Configuración general de la aplicación, mapeada desde `config.json`
-#[derive(Debug, Serialize, Deserialize)]
+/// Configuración general de la aplicación, mapeada desde `config.json`.
+/// Los campos que falten toman su valor por defecto.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub parse_method: ParseMethod,
 
@@ -19,13 +38,15 @@ pub struct Config {
     pub vis: VisConfig,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DebugConfig {
     pub generation: bool,
     pub parsing: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct VisConfig {
     pub slr_png: Option<String>,
     pub parse_table: Option<String>,
@@ -37,16 +58,308 @@ pub struct VisConfig {
 }
 
 impl Config {
-    /// Lee y deserializa `config.json` desde la raíz del proyecto
-    pub fn from_file<P: AsRef<std::path::Path>>(
-        path: P,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut file = std::fs::File::open(path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        let cfg = serde_json::from_str(&contents)?;
+    /// Lee un archivo de configuración en JSON, TOML o YAML, según su
+    /// extensión, sin sus advertencias
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let (cfg, _) = Config::from_layers(&[path.as_ref().to_path_buf()], Vec::new())?;
         Ok(cfg)
     }
+
+    /// Une las capas en orden (cada una sobreescribe a las anteriores) sobre
+    /// la configuración por defecto, y luego las variables de entorno.
+    /// Las capas pueden tener sólo algunos campos; devuelve también las
+    /// advertencias de cada una, como las claves desconocidas.
+    pub fn from_layers(
+        layers: &[PathBuf],
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<(Self, Vec<String>), Box<dyn Error>> {
+        let mut value = serde_json::to_value(Config::default())?;
+        let mut warnings = Vec::new();
+        for path in layers {
+            let mut layer = read_layer(path)?;
+            let found = validate_layer(&mut layer).map_err(|e| format!("'{}': {}", path.display(), e))?;
+            warnings.extend(found.into_iter().map(|w| format!("'{}': {}", path.display(), w)));
+            merge(&mut value, layer);
+        }
+        warnings.extend(apply_env(&mut value, env)?);
+        Ok((serde_json::from_value(value)?, warnings))
+    }
+
+    /// Reemplaza `{grammar}` y `{method}` en las rutas de `vis`, para que
+    /// varias gramáticas no escriban sobre los mismos archivos
+    pub fn expand_paths(&mut self, grammar: &str) {
+        let method = format!("{:?}", self.parse_method).to_lowercase();
+        let vis = &mut self.vis;
+        for path in [
+            &mut vis.slr_png,
+            &mut vis.parse_table,
+            &mut vis.parse_steps,
+            &mut vis.symbol_table,
+            &mut vis.grammar_tree,
+            &mut vis.dfa,
+            &mut vis.lalr_png,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *path = path.replace("{grammar}", grammar).replace("{method}", &method);
+        }
+    }
+
+    /// Copia con las rutas expandidas para el archivo de gramática `path`;
+    /// el lexer y el parser usan cada uno la suya
+    pub fn for_grammar(&self, path: &str) -> Config {
+        let mut config = self.clone();
+        config.expand_paths(&grammar_name(path));
+        config
+    }
+}
+
+/// Archivos de configuración posibles, de menor a mayor prioridad:
+/// `./config.json`, `<gramática>.config.json` junto a cada gramática y la
+/// ruta explícita. Cada uno también en los otros `CONFIG_EXTENSIONS`.
+pub fn config_candidates(grammars: &[&str], explicit: Option<&str>) -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = CONFIG_EXTENSIONS.iter().map(|ext| PathBuf::from(format!("config.{}", ext))).collect();
+    for g in grammars {
+        let path = Path::new(g);
+        if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+            for ext in CONFIG_EXTENSIONS {
+                candidates.push(path.with_file_name(format!("{}.config.{}", stem, ext)));
+            }
+        }
+    }
+    candidates.extend(explicit.map(PathBuf::from));
+    // calc.yal y calc.yalp comparten los suyos
+    let mut seen = HashSet::new();
+    candidates.retain(|c| seen.insert(c.clone()));
+    candidates
+}
+
+/// Las capas de `config_candidates` que existen; la explícita debe existir
+pub fn config_layers(grammars: &[&str], explicit: Option<&str>) -> Result<Vec<PathBuf>, String> {
+    if let Some(explicit) = explicit {
+        if !Path::new(explicit).is_file() {
+            return Err(format!("config '{}' doesn't exist", explicit));
+        }
+    }
+    let mut layers = config_candidates(grammars, explicit);
+    layers.retain(|l| l.is_file());
+    Ok(layers)
+}
+
+/// Nombre de la gramática para `{grammar}`: el archivo sin extensión
+pub fn grammar_name(path: &str) -> String {
+    Path::new(path).file_stem().and_then(|s| s.to_str()).unwrap_or("grammar").to_string()
+}
+
+/// Lee una capa según su extensión: `.toml`, `.yaml`/`.yml` o JSON
+pub fn read_layer(path: &Path) -> Result<Value, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("couldn't read '{}': {}", path.display(), e))?;
+    let layer = match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str::<Value>(&contents).map_err(|e| e.to_string()),
+        Some("yaml") | Some("yml") => serde_yaml::from_str::<Value>(&contents).map_err(|e| e.to_string()),
+        _ => serde_json::from_str::<Value>(&contents).map_err(|e| e.to_string()),
+    }
+    .map_err(|e| format!("'{}': {}", path.display(), e.trim_end()))?;
+    // Un YAML vacío es null
+    Ok(if layer.is_null() { Value::Object(Default::default()) } else { layer })
+}
+
+/// Revisa una capa contra la configuración por defecto, que hace de esquema.
+/// Un tipo equivocado es un error; una clave desconocida, una advertencia.
+/// Normaliza lo que admite: el método en minúsculas y las rutas vacías,
+/// que son null (TOML no tiene null).
+pub fn validate_layer(layer: &mut Value) -> Result<Vec<String>, String> {
+    let schema = serde_json::to_value(Config::default()).map_err(|e| e.to_string())?;
+    let mut warnings = Vec::new();
+    check(layer, &schema, "", &mut warnings)?;
+    Ok(warnings)
+}
+
+fn check(value: &mut Value, schema: &Value, key: &str, warnings: &mut Vec<String>) -> Result<(), String> {
+    match schema {
+        Value::Object(fields) => {
+            let Value::Object(map) = value else {
+                return Err(format!("`{}` must be a table, found {}", key, value));
+            };
+            let known: Vec<String> = fields.keys().cloned().collect();
+            for (name, v) in map.iter_mut() {
+                let path = if key.is_empty() { name.clone() } else { format!("{}.{}", key, name) };
+                match fields.get(name) {
+                    Some(field) => check(v, field, &path, warnings)?,
+                    None => warnings.push(match closest(name, known.iter()) {
+                        Some(c) => format!("unknown key `{}`, did you mean `{}`?", path, c),
+                        None => format!("unknown key `{}`", path),
+                    }),
+                }
+            }
+        }
+        Value::Bool(_) if !value.is_boolean() => {
+            return Err(format!("`{}` must be true or false, found {}", key, value));
+        }
+        Value::Bool(_) => {}
+        // El único texto es `parse_method`
+        Value::String(_) => {
+            let method = value.as_str().map(|m| Value::String(m.to_uppercase()));
+            match method.filter(|m| serde_json::from_value::<ParseMethod>(m.clone()).is_ok()) {
+                Some(m) => *value = m,
+                None => return Err(format!("`{}` must be SLR, LALR or LR1, found {}", key, value)),
+            }
+        }
+        // Rutas de `vis`, null por defecto
+        _ => match value {
+            Value::String(s) if s.is_empty() => *value = Value::Null,
+            Value::String(_) | Value::Null => {}
+            _ => return Err(format!("`{}` must be a path or null, found {}", key, value)),
+        },
+    }
+    Ok(())
+}
+
+fn merge(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Object(base), Value::Object(layer)) => {
+            for (key, value) in layer {
+                merge(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+/// Las variables desconocidas, como SYNAN_HOME, sólo son advertencias; los
+/// valores inválidos son errores
+fn apply_env(value: &mut Value, env: impl IntoIterator<Item = (String, String)>) -> Result<Vec<String>, String> {
+    let mut warnings = Vec::new();
+    for (name, raw) in env {
+        let Some(key) = name.strip_prefix(ENV_PREFIX).map(|k| k.to_lowercase()) else {
+            continue;
+        };
+        let (section, field, new) = if key == "parse_method" {
+            (None, key.as_str(), Value::String(raw.to_uppercase()))
+        } else if let Some(field) = key.strip_prefix("debug_") {
+            let on = match raw.to_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => true,
+                "0" | "false" | "no" | "off" | "" => false,
+                _ => return Err(format!("{}: expected true or false, found '{}'", name, raw)),
+            };
+            (Some("debug"), field, Value::Bool(on))
+        } else if let Some(field) = key.strip_prefix("vis_") {
+            // Vacía o `none` desactiva la visualización
+            let path = match raw.as_str() {
+                "" | "none" => Value::Null,
+                _ => Value::String(raw.clone()),
+            };
+            (Some("vis"), field, path)
+        } else {
+            warnings.push(format!("unknown variable {}", name));
+            continue;
+        };
+        let target = match section {
+            Some(section) => &mut value[section],
+            None => &mut *value,
+        };
+        match target.get_mut(field) {
+            Some(slot) => *slot = new,
+            None => warnings.push(format!("unknown variable {}", name)),
+        }
+    }
+    Ok(warnings)
+}
+
+/// Configuración por defecto con cada clave explicada, para `config init`
+pub const DEFAULT_TOML: &str = r#"# syntax_analyzer configuration
+#
+# Layers, lowest priority first: ./config.{json,toml,yaml}, <grammar>.config.*
+# next to each grammar, then --config. A layer only needs the keys it changes.
+# SYNAN_* variables override every layer, such as SYNAN_PARSE_METHOD=slr.
+
+# SLR, LALR or LR1
+parse_method = "LALR"
+
+# Progress logs
+[debug]
+# While generating the lexer and the parser
+generation = false
+# While parsing an input
+parsing = false
+
+# Drawings and tables, each written to its path, or skipped when empty.
+# Paths may use {grammar}, the grammar file without its extension, and
+# {method}, the parsing method in lowercase: "./graph/{grammar}_{method}"
+[vis]
+# SLR automaton, as .png and .dot
+slr_png = ""
+# LALR automaton, as .png and .dot
+lalr_png = ""
+# ACTION and GOTO tables
+parse_table = ""
+# Stack, input and action of every parsing step
+parse_steps = ""
+# Tokens of the input
+symbol_table = ""
+# Syntax tree of the lexer's regular expression
+grammar_tree = ""
+# DFA of the lexer, as .png and .dot
+dfa = ""
+"#;
+
+/// Lo mismo que `DEFAULT_TOML`, en YAML
+pub const DEFAULT_YAML: &str = r#"# syntax_analyzer configuration
+#
+# Layers, lowest priority first: ./config.{json,toml,yaml}, <grammar>.config.*
+# next to each grammar, then --config. A layer only needs the keys it changes.
+# SYNAN_* variables override every layer, such as SYNAN_PARSE_METHOD=slr.
+
+# SLR, LALR or LR1
+parse_method: LALR
+
+# Progress logs
+debug:
+  # While generating the lexer and the parser
+  generation: false
+  # While parsing an input
+  parsing: false
+
+# Drawings and tables, each written to its path, or skipped when null.
+# Paths may use {grammar}, the grammar file without its extension, and
+# {method}, the parsing method in lowercase: "./graph/{grammar}_{method}"
+vis:
+  # SLR automaton, as .png and .dot
+  slr_png: null
+  # LALR automaton, as .png and .dot
+  lalr_png: null
+  # ACTION and GOTO tables
+  parse_table: null
+  # Stack, input and action of every parsing step
+  parse_steps: null
+  # Tokens of the input
+  symbol_table: null
+  # Syntax tree of the lexer's regular expression
+  grammar_tree: null
+  # DFA of the lexer, as .png and .dot
+  dfa: null
+"#;
+
+/// La configuración comentada para `path`, según su extensión. JSON no
+/// admite comentarios.
+pub fn commented_default(path: &Path) -> Result<&'static str, String> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => Ok(DEFAULT_TOML),
+        Some("yaml") | Some("yml") => Ok(DEFAULT_YAML),
+        _ => Err(format!("'{}': a commented config must be .toml or .yaml", path.display())),
+    }
+}
+
+/// Configuración sin depuración ni visualizaciones, para generar desde build.rs
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            parse_method: ParseMethod::LALR,
+            debug: DebugConfig::default(),
+            vis: VisConfig::default(),
+        }
+    }
 }
 
 /// Conveniencia para el binario: carga “config.json” o panic si falla
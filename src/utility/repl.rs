@@ -0,0 +1,163 @@
+//! Interactive mode: a .yal and a .yalp loaded once, inputs typed line by line
+//!
+//! Every line is lexed and parsed right away, printing its tokens and its
+//! parse tree or the error. Lines starting with `:` are commands, see `HELP`.
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, BufRead, Write},
+};
+
+use super::{
+    cli::{guarded, CliError},
+    read_config::{Config, VisConfig},
+};
+use crate::{
+    codegen::runtime::Lexer,
+    lex::lex_analyzer::LexAnalyzer,
+    syn::{
+        first_follow::{find_first, find_follow},
+        syn_analyzer::SynAnalyzer,
+        yp_reader::read_grammar,
+    },
+};
+
+pub const HELP: &str = "Type an input to see its tokens and its parse tree, or:
+    :reload      Generate the analyzers again, after editing the files
+    :first X     FIRST set of X
+    :follow X    FOLLOW set of X
+    :state N     Items of the state N of the automaton
+    :trace       Show or hide the parsing steps
+    :help
+    :quit";
+
+pub struct Repl {
+    lex_path: String,
+    syn_path: String,
+    config: Config,
+    pub lex: LexAnalyzer,
+    pub syn: SynAnalyzer,
+    first: HashMap<String, HashSet<String>>,
+    follow: HashMap<String, HashSet<String>>,
+    // Print the ParsingSteps of every input
+    pub trace: bool,
+}
+
+impl Repl {
+    pub fn load(lex_path: &str, syn_path: &str, config: Config) -> Result<Repl, CliError> {
+        // Loads and reloads draw nothing, the files of `vis` are left alone
+        let config = Config { vis: VisConfig::default(), ..config };
+        let lex = guarded(|| LexAnalyzer::generate(lex_path, &config))?;
+        let grammar = guarded(|| read_grammar(syn_path))?;
+        let first = find_first(grammar.productions.clone(), grammar.terminals.clone(), grammar.non_terminals.clone());
+        let follow = find_follow(
+            &grammar.productions,
+            &grammar.terminals,
+            &grammar.non_terminals,
+            &first,
+            &grammar.init_symbol,
+        );
+        let tokens = lex.token_names();
+        let syn = guarded(|| SynAnalyzer::from_grammar(grammar, syn_path, &config, Some(&tokens)))?;
+        Ok(Repl {
+            lex_path: lex_path.to_string(),
+            syn_path: syn_path.to_string(),
+            config,
+            lex,
+            syn,
+            first,
+            follow,
+            trace: false,
+        })
+    }
+
+    /// Reads both files again; on errors the previous analyzers are kept
+    pub fn reload(&mut self) -> Result<(), CliError> {
+        let trace = self.trace;
+        *self = Repl::load(&self.lex_path, &self.syn_path, self.config.clone())?;
+        self.trace = trace;
+        Ok(())
+    }
+
+    /// Output for one line of the user, `None` to quit
+    pub fn eval(&mut self, line: &str) -> Option<String> {
+        let line = line.trim();
+        let (command, arg) = match line.split_once(char::is_whitespace) {
+            Some((c, a)) => (c, a.trim()),
+            None => (line, ""),
+        };
+        let out = match command {
+            "" => String::new(),
+            ":q" | ":quit" => return None,
+            ":h" | ":help" => HELP.to_string(),
+            ":reload" => match self.reload() {
+                Ok(()) => format!("~ Reloaded '{}' and '{}'", self.lex_path, self.syn_path),
+                Err(e) => format!("{}\n~ Kept the previous version", e),
+            },
+            ":trace" => {
+                self.trace = !self.trace;
+                format!("~ Trace {}", if self.trace { "on" } else { "off" })
+            }
+            ":first" => set(&self.first, "FIRST", arg),
+            ":follow" => set(&self.follow, "FOLLOW", arg),
+            ":state" => match arg.parse::<usize>().ok().and_then(|n| self.syn.states.get(n)) {
+                Some(items) => items.trim_end().to_string(),
+                None => format!("~ No state '{}', the automaton has {}", arg, self.syn.states.len()),
+            },
+            c if c.starts_with(':') => format!("~ Unknown command '{}', see :help", c),
+            _ => self.parse(line),
+        };
+        Some(out)
+    }
+
+    fn parse(&self, input: &str) -> String {
+        let lexer = Lexer::new(&self.lex, |id| self.lex.token(id));
+        let mut symbols = match lexer.tokens(input) {
+            Some(symbols) => symbols,
+            None => return "~ Error Lex: the input has lexical errors".to_string(),
+        };
+        symbols.retain(|s| !self.syn.ignore.contains(s.token_name));
+        let tokens: Vec<String> = symbols.iter().map(|s| format!("{}({})", s.token_name, s.content)).collect();
+        let mut out = format!("Tokens: {}\n", tokens.join(" "));
+
+        let (steps, tree) = self.syn.parse_tree_silent(&symbols);
+        if self.trace {
+            for step in &steps {
+                out += &format!("{} | {} | {}\n", step.stack, step.input, step.action);
+            }
+        }
+        match tree {
+            Ok(tree) => out += &tree.to_string(),
+            Err(error) => out += &format!("{}\n~ Error Syn: {}", error.visual(), error.message),
+        }
+        out.trim_end().to_string()
+    }
+
+    /// Prompts for lines of `input` until it ends or `:quit`
+    pub fn run(&mut self, input: impl BufRead, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "~ '{}' and '{}' loaded, :help for the commands", self.lex_path, self.syn_path)?;
+        let mut lines = input.lines();
+        loop {
+            write!(out, "> ")?;
+            out.flush()?;
+            let Some(line) = lines.next() else { break };
+            match self.eval(&line?) {
+                Some(reply) if reply.is_empty() => {}
+                Some(reply) => writeln!(out, "{}", reply)?,
+                None => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn set(sets: &HashMap<String, HashSet<String>>, name: &str, symbol: &str) -> String {
+    match sets.get(symbol) {
+        Some(s) => {
+            let mut v: Vec<&str> = s.iter().map(|t| t.as_str()).collect();
+            v.sort();
+            format!("{}({}) = {{ {} }}", name, symbol, v.join(", "))
+        }
+        None => format!("~ No {} set for '{}'", name, symbol),
+    }
+}
@@ -0,0 +1,187 @@
+//! `generate --watch`: generate again whenever the grammar files change
+//!
+//! Files are polled for changes. After each generation a `Snapshot` is taken,
+//! and only what differs from the previous one is printed: conflicts, number
+//! of states, tokens whose regex changed and the outcome of a sample input.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use super::{cli::guarded, read_config::Config};
+use crate::{
+    codegen::runtime::{self, Lexer},
+    lex::{
+        flex_reader::read_flex_file,
+        lex_analyzer::LexAnalyzer,
+        yl_reader::{action_token, lexer_data, read_yal_file, token_patterns},
+    },
+    syn::{conflicts::find_conflicts, syn_analyzer::SynAnalyzer, yp_reader::read_grammar},
+};
+
+/// Polls files for changes of their modification time or size
+pub struct Watcher {
+    files: Vec<PathBuf>,
+    stamps: Vec<Option<(SystemTime, u64)>>,
+}
+
+fn stamp(path: &PathBuf) -> Option<(SystemTime, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+impl Watcher {
+    pub fn new(files: &[&str]) -> Watcher {
+        let files: Vec<PathBuf> = files.iter().map(PathBuf::from).collect();
+        let stamps = files.iter().map(stamp).collect();
+        Watcher { files, stamps }
+    }
+
+    /// Files changed since the last call, or since `new`
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for (file, old) in self.files.iter().zip(self.stamps.iter_mut()) {
+            let new = stamp(file);
+            if new != *old {
+                *old = new;
+                changed.push(file.clone());
+            }
+        }
+        changed
+    }
+
+    /// Blocks until some file changes
+    pub fn wait(&mut self, interval: Duration) -> Vec<PathBuf> {
+        loop {
+            let changed = self.changed();
+            if !changed.is_empty() {
+                return changed;
+            }
+            thread::sleep(interval);
+        }
+    }
+}
+
+/// What a generation produced, to be compared with the next one
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    // Why the files couldn't be read or the analyzers generated
+    pub error: Option<String>,
+    pub states: Option<usize>,
+    // Without state numbers, which change with any edit of the grammar
+    pub conflicts: Vec<String>,
+    pub tokens: BTreeMap<String, String>,
+    pub sample: Option<String>,
+}
+
+impl Snapshot {
+    /// Generates both analyzers, returned unless there were errors
+    pub fn take(
+        lex_path: &str,
+        syn_path: &str,
+        config: &Config,
+        sample: Option<&str>,
+    ) -> (Snapshot, Option<(LexAnalyzer, SynAnalyzer)>) {
+        let mut snapshot = Snapshot::default();
+        let file = match guarded(|| if lex_path.ends_with(".l") { read_flex_file(lex_path) } else { read_yal_file(lex_path) }) {
+            Ok(file) => file,
+            Err(e) => return (Snapshot { error: Some(e.to_string()), ..snapshot }, None),
+        };
+        snapshot.tokens = token_patterns(&file);
+        let grammar = match guarded(|| read_grammar(syn_path)) {
+            Ok(grammar) => grammar,
+            Err(e) => return (Snapshot { error: Some(e.to_string()), ..snapshot }, None),
+        };
+        snapshot.conflicts = find_conflicts(&grammar, config.parse_method)
+            .iter()
+            .map(|c| {
+                let choices: Vec<&str> =
+                    c.choices.iter().map(|ch| if ch.starts_with("shift") { "shift" } else { ch.as_str() }).collect();
+                format!("{} on `{}`: {}", c.kind(), c.token, choices.join(" / "))
+            })
+            .collect();
+
+        let data = lexer_data(&file);
+        let produced = data.actions.values().filter_map(|a| action_token(a)).collect();
+        let built = guarded(|| {
+            let lex = LexAnalyzer::from_lexer_data(data, &config.for_grammar(lex_path));
+            let syn = SynAnalyzer::from_grammar(grammar, syn_path, &config.for_grammar(syn_path), Some(&produced));
+            (lex, syn)
+        });
+        let (lex, syn) = match built {
+            Ok(built) => built,
+            // The conflicts already tell why the tables couldn't be built
+            Err(_) if !snapshot.conflicts.is_empty() => return (snapshot, None),
+            Err(e) => return (Snapshot { error: Some(e.to_string()), ..snapshot }, None),
+        };
+        snapshot.states = Some(syn.states.len());
+        snapshot.sample = sample.map(|path| match fs::read_to_string(path) {
+            Ok(input) => match runtime::parse(&Lexer::new(&lex, |id| lex.token(id)), &syn, &input) {
+                None => "lexical error".to_string(),
+                Some((_, _, Some(error))) => format!("syntax error ({})", error),
+                Some((_, _, None)) => "accepted".to_string(),
+            },
+            Err(e) => format!("couldn't read '{}': {}", path, e),
+        });
+        (snapshot, Some((lex, syn)))
+    }
+
+    /// One line, for the first generation
+    pub fn summary(&self) -> String {
+        if let Some(e) = &self.error {
+            return e.clone();
+        }
+        let mut line = format!(
+            "~ {} states, {} conflicts, {} tokens",
+            self.states.map_or("?".to_string(), |s| s.to_string()),
+            self.conflicts.len(),
+            self.tokens.len()
+        );
+        if let Some(sample) = &self.sample {
+            line += &format!(", sample {}", sample);
+        }
+        line
+    }
+
+    /// What changed since `old`, a line each
+    pub fn diff(&self, old: &Snapshot) -> Vec<String> {
+        if let Some(e) = &self.error {
+            return vec![e.clone()];
+        }
+        let mut lines = Vec::new();
+        for c in self.conflicts.iter().filter(|c| !old.conflicts.contains(c)) {
+            lines.push(format!("+ conflict: {}", c));
+        }
+        for c in old.conflicts.iter().filter(|c| !self.conflicts.contains(c)) {
+            lines.push(format!("- conflict: {}", c));
+        }
+        if let (Some(before), Some(after)) = (old.states, self.states) {
+            if before != after {
+                lines.push(format!("~ states: {} -> {}", before, after));
+            }
+        }
+        for (token, regex) in &self.tokens {
+            match old.tokens.get(token) {
+                None => lines.push(format!("+ token {}: {}", token, regex)),
+                Some(before) if before != regex => lines.push(format!("~ token {}: {} -> {}", token, before, regex)),
+                Some(_) => {}
+            }
+        }
+        for token in old.tokens.keys().filter(|t| !self.tokens.contains_key(*t)) {
+            lines.push(format!("- token {}", token));
+        }
+        if let (Some(before), Some(after)) = (&old.sample, &self.sample) {
+            if before != after {
+                lines.push(format!("~ sample: {} -> {}", before, after));
+            }
+        }
+        if lines.is_empty() {
+            lines.push("~ Nothing changed".to_string());
+        }
+        lines
+    }
+}
@@ -1,5 +1,7 @@
 use console::Style;
 
+use crate::syn::validation::{GrammarIssue, Severity};
+
 pub fn print_log(log: &str, done: usize, total: usize, color: &Style){
     let n_log = &format!("{}", color.apply_to(log));
     let pbar = draw_progress(done, total);
@@ -24,4 +26,15 @@ fn draw_progress(done: usize, total: usize)->String{
         bar,
         percent
     )
-}
\ No newline at end of file
+}
+pub fn print_grammar_issues(issues: &[GrammarIssue]){
+    let red = Style::new().red().bold();
+    let yellow = Style::new().yellow().bold();
+    for issue in issues{
+        let title = match issue.severity(){
+            Severity::Error => red.apply_to("GRAMMAR ERROR"),
+            Severity::Warning => yellow.apply_to("GRAMMAR WARNING"),
+        };
+        eprintln!("{}: {}", title, issue);
+    }
+}
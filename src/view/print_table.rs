@@ -1,13 +1,13 @@
 use std::collections::{HashMap, HashSet};
-use crate::lex::lex_analyzer::Symbol;
+use crate::lex::lex_analyzer::{Kind, Symbol};
 use crate::syn::syn_analyzer::ParsingStep;
 
 use prettytable::{row, Cell, Table};
 use std::fs::File;
 use std::path::Path;
 
-pub fn print_symbol_table(
-    symbols: &Vec<Symbol>,
+pub fn print_symbol_table<K: Kind>(
+    symbols: &Vec<Symbol<K>>,
     filename: &str
 )->std::io::Result<()>{
     let mut table = Table::new();
@@ -18,7 +18,7 @@ pub fn print_symbol_table(
             s.id,
             s.content,
             s.token,
-            s.token_name,
+            s.token_name.name(),
             s.line,
             s.start,
             s.end
@@ -0,0 +1,89 @@
+use std::{env, fs, path::PathBuf, process::Command};
+
+mod common;
+
+use common::analyzers;
+use ron::ser::{to_string_pretty, PrettyConfig};
+use syntax_analyzer::{
+    codegen::{
+        binary::{decode_lexer, decode_parser, encode_lexer, encode_parser, BinaryError, FORMAT_VERSION, LEXER, PARSER},
+        emit::{emit_crate, Runtime, TableFormat},
+        runtime::{load_lexer_bin, load_parser_bin, parse, Lexer},
+    },
+};
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("syntax_analyzer_binary_{}_{}", std::process::id(), name))
+}
+
+#[test]
+fn tables_round_trip_and_are_smaller() {
+    let (lex, syn) = analyzers();
+    let (lex_bin, syn_bin) = (encode_lexer(&lex), encode_parser(&syn));
+    assert_eq!(&lex_bin[..4], b"SYNA");
+
+    let loaded = decode_lexer(&lex_bin).unwrap();
+    assert_eq!(loaded.map, lex.map);
+    assert_eq!(loaded.token_list, lex.token_list);
+    assert_eq!(loaded.tokens, lex.tokens);
+    let loaded = decode_parser(&syn_bin).unwrap();
+    assert_eq!(loaded.action, syn.action);
+    assert_eq!(loaded.goto, syn.goto);
+    assert_eq!(loaded.productions, syn.productions);
+
+    let ron = to_string_pretty(&syn, PrettyConfig::default()).unwrap();
+    assert!(syn_bin.len() * 3 < ron.len(), "{} bytes against {}", syn_bin.len(), ron.len());
+}
+
+#[test]
+fn other_files_are_rejected() {
+    let (lex, syn) = analyzers();
+    let lex_bin = encode_lexer(&lex);
+    assert_eq!(decode_lexer(b"(map: {})").unwrap_err(), BinaryError::NotTables);
+    assert_eq!(decode_lexer(&encode_parser(&syn)).unwrap_err(), BinaryError::WrongKind { expected: LEXER, found: PARSER });
+    assert!(matches!(decode_lexer(&lex_bin[..5]), Err(BinaryError::Corrupt(_))));
+    assert!(matches!(decode_lexer(&lex_bin[..lex_bin.len() / 2]), Err(BinaryError::Corrupt(_))));
+
+    let mut newer = lex_bin.clone();
+    newer[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+    let e = decode_lexer(&newer).unwrap_err();
+    assert_eq!(e, BinaryError::Version { found: FORMAT_VERSION + 1, generator: env!("CARGO_PKG_VERSION").to_string() });
+    assert!(e.to_string().contains("generate it again"));
+}
+
+#[test]
+fn generated_crates_can_embed_them() {
+    let (lex, syn) = analyzers();
+    let dir = temp_path("crate");
+    emit_crate(&dir, &lex, &syn, TableFormat::Binary, &Runtime::local()).unwrap();
+    assert!(!dir.join("src/lex_analyzer.ron").exists());
+    let lib = fs::read_to_string(dir.join("src/lib.rs")).unwrap();
+    assert!(lib.contains("load_parser_bin(include_bytes!(\"syn_analyzer.bin\"))"));
+
+    let lex = load_lexer_bin(&fs::read(dir.join("src/lex_analyzer.bin")).unwrap());
+    let syn = load_parser_bin(&fs::read(dir.join("src/syn_analyzer.bin")).unwrap());
+    let (_, _, error) = parse(&Lexer::new(&lex, |id| lex.token(id)), &syn, "if (x) y = 2;").unwrap();
+    assert!(error.is_none());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn parse_reads_bin_files() {
+    let (lex, syn) = analyzers();
+    let (lex_bin, syn_bin, input) = (temp_path("lex.bin"), temp_path("syn.bin"), temp_path("input.txt"));
+    fs::write(&lex_bin, encode_lexer(&lex)).unwrap();
+    fs::write(&syn_bin, encode_parser(&syn)).unwrap();
+    fs::write(&input, "x = 1;").unwrap();
+    let run = |lex: &PathBuf, syn: &PathBuf| {
+        Command::new(env!("CARGO_BIN_EXE_syntax_analyzer"))
+            .arg("parse")
+            .args([lex, syn, &input])
+            .args(["--config", "tests/corpus/quiet.json"])
+            .output()
+            .unwrap()
+            .status
+            .code()
+    };
+    assert_eq!(run(&lex_bin, &syn_bin), Some(0));
+    assert_eq!(run(&syn_bin, &lex_bin), Some(1));
+}
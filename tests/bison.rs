@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+
+use syntax_analyzer::{
+    lex::lex_analyzer::Symbol,
+    syn::{
+        bison_reader::{parse_bison, read_bison},
+        conflicts::find_conflicts,
+        parse_tree::ParseNode,
+        syn_analyzer::SynAnalyzer,
+        yp_parser::parse_yalp,
+        yp_reader::{grammar_info, GrammarInfo},
+        yp_writer::yalp_source,
+    },
+    utility::read_config::{Config, ParseMethod},
+};
+
+fn bodies(grammar: &syntax_analyzer::syn::yp_reader::GrammarInfo, head: &str) -> Vec<String> {
+    grammar.productions[head].iter().map(|b| b.join(" ")).collect()
+}
+
+#[test]
+fn rules_are_read_from_a_bison_file() {
+    let bison = read_bison("tests/corpus/calc.y");
+    let g = &bison.grammar;
+    assert_eq!(g.init_symbol, "input");
+    assert_eq!(bodies(g, "input"), vec!["", "input line"]);
+    assert_eq!(bodies(g, "line"), vec!["'\\n'", "exp '\\n'", "error '\\n'"]);
+    assert_eq!(bodies(g, "term"), vec!["term '*' factor", "term '/' factor", "factor"]);
+    assert_eq!(bodies(g, "factor"), vec!["NUM", "FN '(' exp ')'", "'(' exp ')'"]);
+    let terminals: HashSet<&str> = g.terminals.iter().map(|s| s.as_str()).collect();
+    assert_eq!(
+        terminals,
+        HashSet::from(["NUM", "FN", "error", "'\\n'", "'+'", "'-'", "'*'", "'/'", "'('", "')'"])
+    );
+    assert_eq!(bison.precedence[1], ("%left".to_string(), vec!["'*'".to_string(), "'/'".to_string()]));
+}
+
+#[test]
+fn actions_are_kept_as_opaque_blocks() {
+    let bison = read_bison("tests/corpus/calc.y");
+    let divide = bison.actions.iter().find(|a| a.head == "term" && a.alternative == 1).unwrap();
+    assert_eq!(divide.code, "{ if ($3 == 0) { yyerror (\"division by zero }\"); } $$ = $1 / $3; }");
+    assert_eq!(divide.position, 3);
+
+    // Mid-rule action of `'(' { depth++; } exp ')'`
+    let mid: Vec<usize> = bison.actions.iter().filter(|a| a.head == "factor" && a.alternative == 2).map(|a| a.position).collect();
+    assert_eq!(mid, vec![1, 3]);
+}
+
+#[test]
+fn imported_grammar_round_trips_through_yalp() {
+    // Precedence has no .yalp syntax and is lost on the way
+    let g = read_bison("tests/corpus/calc.y").grammar;
+    let without = GrammarInfo { precedence: Default::default(), ..g.clone() };
+    assert_eq!(grammar_info(&parse_yalp(&yalp_source(&g)).unwrap()).unwrap(), without);
+}
+
+#[test]
+fn undeclared_symbols_are_reported() {
+    let err = parse_bison("%token A\n%%\ns: A b ;\n").unwrap_err();
+    assert_eq!(err.to_string(), "3:6: `b` is used, but is not defined as a token and has no rules");
+}
+
+// The tree of `input`, tokens split on spaces and named as in the grammar
+fn tree(source: &str, method: ParseMethod, input: &str) -> Option<ParseNode> {
+    let grammar = parse_bison(source).unwrap().grammar;
+    assert!(find_conflicts(&grammar, method).is_empty(), "{:?}", method);
+    let config = Config { parse_method: method, ..Config::default() };
+    let syn = SynAnalyzer::from_grammar(grammar, "test.y", &config, None);
+    let tokens: Vec<Symbol> = input
+        .split(' ')
+        .enumerate()
+        .map(|(i, t)| Symbol {
+            id: i,
+            token: i.to_string(),
+            token_name: if t == "NUM" { t.to_string() } else { format!("'{}'", t) },
+            start: i,
+            end: i + 1,
+            line: 1,
+            content: t.to_string(),
+        })
+        .collect();
+    syn.parse_tree(&tokens).1.ok()
+}
+
+// Children of the root, each as the number of its own children
+fn shape(node: &ParseNode) -> Vec<usize> {
+    node.children.iter().map(|c| c.children.len()).collect()
+}
+
+#[test]
+fn precedence_settles_shift_reduce() {
+    let source = "%token NUM\n%left '+'\n%left '*'\n%%\nexp: exp '+' exp | exp '*' exp | NUM ;\n";
+    for method in [ParseMethod::SLR, ParseMethod::LALR] {
+        // NUM + (NUM * NUM)
+        assert_eq!(shape(&tree(source, method, "NUM + NUM * NUM").unwrap()), vec![1, 0, 3]);
+        // (NUM * NUM) + NUM
+        assert_eq!(shape(&tree(source, method, "NUM * NUM + NUM").unwrap()), vec![3, 0, 1]);
+        // (NUM + NUM) + NUM
+        assert_eq!(shape(&tree(source, method, "NUM + NUM + NUM").unwrap()), vec![3, 0, 1]);
+    }
+}
+
+#[test]
+fn nonassoc_tokens_dont_chain() {
+    let source = "%token NUM\n%nonassoc '<'\n%%\nexp: exp '<' exp | NUM ;\n";
+    for method in [ParseMethod::SLR, ParseMethod::LALR] {
+        assert!(tree(source, method, "NUM < NUM").is_some());
+        assert!(tree(source, method, "NUM < NUM < NUM").is_none());
+    }
+}
+
+#[test]
+fn prec_gives_a_rule_the_precedence_of_its_token() {
+    // Below '-', so `- NUM - NUM` is - (NUM - NUM)
+    let source = "%token NUM\n%left LOW\n%left '-'\n%%\nexp: exp '-' exp | '-' exp %prec LOW | NUM ;\n";
+    for method in [ParseMethod::SLR, ParseMethod::LALR] {
+        assert_eq!(shape(&tree(source, method, "- NUM - NUM").unwrap()), vec![0, 3]);
+    }
+}
+
+#[test]
+fn character_tokens_can_be_declared() {
+    let g = parse_bison("%token '+' NUM\n%%\ne: e '+' NUM | NUM ;\n").unwrap().grammar;
+    assert!(g.terminals.contains("'+'"));
+}
@@ -0,0 +1,146 @@
+use std::{env, fs, path::PathBuf, process::Command as Process};
+
+use ron::ser::{to_string_pretty, PrettyConfig};
+use syntax_analyzer::{
+    lex::lex_analyzer::LexAnalyzer,
+    syn::syn_analyzer::SynAnalyzer,
+    utility::{
+        cli::{parse_args, CliError, Command},
+        read_config::{Config, ParseMethod},
+    },
+};
+
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(|s| s.to_string()).collect()
+}
+
+fn temp_file(name: &str, contents: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("syntax_analyzer_cli_{}_{}", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+/// Exit code of the binary, with the quiet config of the corpus
+fn run(line: &str) -> i32 {
+    Process::new(env!("CARGO_BIN_EXE_syntax_analyzer"))
+        .args(args(line))
+        .args(["--config", "tests/corpus/quiet.json"])
+        .output()
+        .unwrap()
+        .status
+        .code()
+        .unwrap()
+}
+
+#[test]
+fn flags_go_anywhere() {
+    let cli = parse_args(&args("--method=slr check a.yal -q --config c.json b.yalp")).unwrap();
+    assert_eq!(cli.command, Command::Check { lex: "a.yal".to_string(), syn: "b.yalp".to_string() });
+    assert_eq!(cli.options.method, Some(ParseMethod::SLR));
+    assert_eq!(cli.options.config.as_deref(), Some("c.json"));
+    assert!(cli.options.quiet);
+}
+
+#[test]
+fn two_files_still_generate() {
+    let cli = parse_args(&args("lexer.yal grammar.yalp")).unwrap();
+    assert_eq!(cli.command, Command::Generate { lex: "lexer.yal".to_string(), syn: "grammar.yalp".to_string() });
+    assert_eq!(parse_args(&[]).unwrap().command, Command::Help);
+}
+
+#[test]
+fn runtime_goes_with_an_out_dir() {
+    let cli = parse_args(&args("generate a b --out-dir o --runtime 0.1")).unwrap();
+    assert_eq!(cli.options.runtime.as_deref(), Some("0.1"));
+    assert!(matches!(parse_args(&args("generate a b --runtime 0.1")), Err(CliError::Usage(_))));
+}
+
+#[test]
+fn bad_usage_is_reported() {
+    for line in [
+        "parse a.yal b.yalp", "frobnicate a b c", "check a b --method ll1", "generate a b --static", "check a b --nope",
+        "check a b --watch", "generate a b --sample in.txt",
+        "config", "config init a.toml b.toml",
+        "check a b --format json", "parse a b c --format xml",
+        "parse a b c --binary", "generate a b --out-dir o --static --binary",
+        "parse a b c --compress", "generate a b --out-dir o --static --compress",
+    ] {
+        let e = parse_args(&args(line)).unwrap_err();
+        assert!(matches!(e, CliError::Usage(_)), "{}: {:?}", line, e);
+        assert_eq!(e.exit_code(), 2);
+    }
+}
+
+#[test]
+fn exit_codes() {
+    // plain.yal has no rule for line breaks
+    let ok = temp_file("ok.txt", "x = 1; if (x) y = 2;");
+    let lexical = temp_file("lexical.txt", "x = 1 $;");
+    let syntax = temp_file("syntax.txt", "x = = 1;");
+    let undefined = temp_file("undefined.yalp", "%token ID\n%%\nS: ID T ;\n");
+    let plain = "tests/corpus/plain.yal tests/corpus/plain.yalp";
+
+    assert_eq!(run("check"), 2);
+    assert_eq!(run(&format!("check {}", plain)), 0);
+    assert_eq!(run(&format!("check tests/corpus/plain.yal {}", undefined.display())), 3);
+    assert_eq!(run(&format!("parse {} {}", plain, ok.display())), 0);
+    assert_eq!(run(&format!("parse {} {}", plain, lexical.display())), 4);
+    assert_eq!(run(&format!("parse {} {}", plain, syntax.display())), 5);
+    assert_eq!(run(&format!("parse {} missing.txt", plain)), 1);
+    assert_eq!(run("check tests/corpus/plain.yal missing.yalp"), 1);
+    assert_eq!(run("first-follow missing.yalp"), 1);
+}
+
+#[test]
+fn parses_with_serialized_analyzers() {
+    let config = Config::from_file("tests/corpus/quiet.json").unwrap();
+    let lex = LexAnalyzer::generate("tests/corpus/plain.yal", &config);
+    let syn = SynAnalyzer::generate("tests/corpus/plain.yalp", &config, Some(&lex.token_names()));
+    assert_eq!(lex.tokens.values().filter(|t| *t == "SEMICOLON").count(), 1);
+
+    let lex_ron = temp_file("lex.ron", &to_string_pretty(&lex, PrettyConfig::default()).unwrap());
+    let syn_ron = temp_file("syn.ron", &to_string_pretty(&syn, PrettyConfig::default()).unwrap());
+    let loaded = LexAnalyzer::from_ron(&lex_ron).unwrap();
+    assert_eq!(loaded.tokens, lex.tokens);
+
+    let ok = temp_file("ron_ok.txt", "if (x) y = 2;");
+    let syntax = temp_file("ron_syntax.txt", "if x = 2;");
+    let rons = format!("{} {}", lex_ron.display(), syn_ron.display());
+    assert_eq!(run(&format!("parse {} {}", rons, ok.display())), 0);
+    assert_eq!(run(&format!("parse {} {}", rons, syntax.display())), 5);
+    assert_eq!(run(&format!("parse {} {} {}", syn_ron.display(), lex_ron.display(), ok.display())), 1);
+}
+
+#[test]
+fn older_lexers_get_their_tokens_from_the_actions() {
+    let mut lex = LexAnalyzer::generate("tests/corpus/plain.yal", &Config::default());
+    let tokens = std::mem::take(&mut lex.tokens);
+    // As written before the lexer kept its tokens
+    let source = to_string_pretty(&lex, PrettyConfig::default()).unwrap().replace("    tokens: {},\n", "");
+    assert!(!source.contains("tokens:"));
+
+    let loaded = LexAnalyzer::from_ron(temp_file("old_lex.ron", &source)).unwrap();
+    assert_eq!(loaded.tokens, tokens);
+    let (id, name) = tokens.iter().next().unwrap();
+    assert_eq!(loaded.token(*id), Some(name.as_str()));
+}
+
+#[test]
+fn input_ending_in_unknown_characters() {
+    let trailing = temp_file("trailing.txt", "x = 1; $$");
+    assert_eq!(run(&format!("parse tests/corpus/plain.yal tests/corpus/plain.yalp {}", trailing.display())), 4);
+}
+
+#[test]
+fn parsing_prints_no_table_lookups() {
+    let ok = temp_file("quiet_ok.txt", "x = 1;");
+    let output = Process::new(env!("CARGO_BIN_EXE_syntax_analyzer"))
+        .args(["parse", "tests/corpus/plain.yal", "tests/corpus/plain.yalp"])
+        .arg(&ok)
+        .args(["--config", "tests/corpus/quiet.json"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("]= "), "{}", stdout);
+}
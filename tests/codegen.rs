@@ -0,0 +1,289 @@
+mod common;
+
+use std::{collections::HashMap, fs, path::PathBuf, process::Command, sync::OnceLock};
+
+use common::config;
+use syntax_analyzer::{
+    codegen::{
+        build::{generate_parser_to, PARSER_FILE},
+        emit::{
+            emit_crate, lib_source, module_source, package_name, tables_source, variant_name, Runtime, TableFormat,
+        },
+        runtime::{load_lexer, load_parser, parse, Kind, Lexer, StaticElement::*, StaticLexer, StaticParser},
+    },
+    lex::{lex_analyzer::LexAnalyzer, yl_reader::action_token},
+    syn::{slr_automata::Element, syn_analyzer::SynAnalyzer},
+    utility::read_config::ParseMethod,
+};
+
+fn analyzers() -> (LexAnalyzer, SynAnalyzer) {
+    let lex = LexAnalyzer::generate("tests/corpus/calc.l", &config(ParseMethod::SLR));
+    let syn = SynAnalyzer::generate("tests/corpus/calc.y", &config(ParseMethod::SLR), Some(&lex.token_names()));
+    (lex, syn)
+}
+
+/// What the generated `actions` of grammar/lexer.yal would return
+fn yal_actions(id: usize) -> Option<&'static str> {
+    static TOKENS: OnceLock<HashMap<usize, String>> = OnceLock::new();
+    let tokens = TOKENS.get_or_init(|| {
+        let lex = LexAnalyzer::generate("grammar/lexer.yal", &config(ParseMethod::SLR));
+        lex.actions.iter().filter_map(|(id, a)| action_token(a).map(|t| (*id, t))).collect()
+    });
+    tokens.get(&id).map(|t| t.as_str())
+}
+
+fn out_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("syntax_analyzer_codegen_{}", std::process::id())).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn package_name_comes_from_the_directory() {
+    assert_eq!(package_name(&PathBuf::from("out/Calc-Parser")), "calc-parser");
+    assert_eq!(package_name(&PathBuf::from("out/my parser")), "my_parser");
+    assert_eq!(package_name(&PathBuf::from("out/2nd")), "parser_2nd");
+}
+
+#[test]
+fn emitted_crate_has_tables_actions_and_driver() {
+    let (lex, syn) = analyzers();
+    let dir = out_dir("calc");
+    emit_crate(&dir, &lex, &syn, TableFormat::Ron, &Runtime::local()).unwrap();
+
+    let manifest = fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+    assert!(manifest.contains("name = \"calc\""));
+    assert!(manifest.contains("syntax_analyzer = { path = \"../"));
+    assert!(!manifest.contains("[workspace]"));
+    let main = fs::read_to_string(dir.join("src/main.rs")).unwrap();
+    assert!(main.contains("runtime::run(&calc::lexer(), &calc::parser(), calc::actions)"));
+    for table in ["src/lex_analyzer.ron", "src/syn_analyzer.ron"] {
+        assert!(dir.join(table).exists(), "missing {}", table);
+    }
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn actions_are_sorted_and_keep_the_header() {
+    let (lex, syn) = analyzers();
+    let lib = lib_source(&lex, &syn, TableFormat::Ron);
+    for h in &lex.header {
+        assert!(lib.contains(h.as_str()));
+    }
+    let ids: Vec<usize> = lib
+        .lines()
+        .filter_map(|l| l.trim().split_once(" => ").and_then(|(id, _)| id.parse().ok()))
+        .collect();
+    let mut sorted = ids.clone();
+    sorted.sort();
+    assert!(!ids.is_empty());
+    assert_eq!(ids, sorted);
+    assert!(lib.contains("include_str!(\"lex_analyzer.ron\")"));
+}
+
+#[test]
+fn emitted_tables_parse_like_the_originals() {
+    let lex = LexAnalyzer::generate("grammar/lexer.yal", &config(ParseMethod::SLR));
+    let syn = SynAnalyzer::generate("grammar/test_grammar.yalp", &config(ParseMethod::SLR), Some(&lex.token_names()));
+    let dir = out_dir("roundtrip");
+    emit_crate(&dir, &lex, &syn, TableFormat::Ron, &Runtime::local()).unwrap();
+    let lex = load_lexer(&fs::read_to_string(dir.join("src/lex_analyzer.ron")).unwrap());
+    let syn = load_parser(&fs::read_to_string(dir.join("src/syn_analyzer.ron")).unwrap());
+
+    let lexer = Lexer::new(&lex, yal_actions);
+    let (symbols, _, error) = parse(&lexer, &syn, "myvar = \"Hi\";\nwhat = 3;\n").unwrap();
+    assert!(error.is_none(), "{:?}", error);
+    assert!(symbols.iter().all(|s| !s.token_name.is_empty()));
+    let (_, _, error) = parse(&lexer, &syn, "what = ;\n").unwrap();
+    assert!(error.is_some());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn runtime_can_be_a_version() {
+    let (lex, syn) = analyzers();
+    let dir = out_dir("calc_version");
+    emit_crate(&dir, &lex, &syn, TableFormat::Ron, &Runtime::Version("0.1".to_string())).unwrap();
+    let manifest = fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+    assert!(manifest.contains("syntax_analyzer = \"0.1\""));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn static_format_writes_tables_instead_of_ron() {
+    let (lex, syn) = analyzers();
+    let dir = out_dir("calc_static");
+    emit_crate(&dir, &lex, &syn, TableFormat::Static, &Runtime::local()).unwrap();
+    assert!(dir.join("src/tables.rs").exists());
+    assert!(!dir.join("src/lex_analyzer.ron").exists());
+    let lib = fs::read_to_string(dir.join("src/lib.rs")).unwrap();
+    assert!(lib.contains("mod tables;"));
+    assert!(lib.contains("tables::PARSER.load()"));
+    assert!(!lib.contains("include_str!"));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn emitted_crate_builds_and_parses() {
+    let lex = LexAnalyzer::generate("grammar/lexer.yal", &config(ParseMethod::SLR));
+    let syn = SynAnalyzer::generate("grammar/test_grammar.yalp", &config(ParseMethod::SLR), Some(&lex.token_names()));
+    let dir = out_dir("built");
+    emit_crate(&dir, &lex, &syn, TableFormat::Ron, &Runtime::local()).unwrap();
+    // Shared between runs, so only the first one builds the dependencies
+    let target = std::env::temp_dir().join("syntax_analyzer_codegen_target");
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let build = Command::new(&cargo)
+        .args(["build", "--offline", "--quiet"])
+        .current_dir(&dir)
+        .env("CARGO_TARGET_DIR", &target)
+        .output()
+        .unwrap();
+    assert!(build.status.success(), "{}", String::from_utf8_lossy(&build.stderr));
+
+    let parser = target.join("debug").join("built");
+    let run = |input: &str| {
+        fs::write(dir.join("input.txt"), input).unwrap();
+        let out = Command::new(&parser).arg("input.txt").current_dir(&dir).output().unwrap();
+        (out.status.code(), String::from_utf8(out.stdout).unwrap())
+    };
+    let (code, out) = run("myvar = \"Hi\";\nwhat = 3;\n");
+    assert_eq!(code, Some(0));
+    assert!(out.contains("Parsing Completed"));
+    let (code, out) = run("what = ;\n");
+    assert_eq!(code, Some(5));
+    assert!(out.contains("Parsing Error"));
+
+    // Wrong arguments and a broken config.json are errors too
+    let code = |cmd: &mut Command| cmd.current_dir(&dir).output().unwrap().status.code();
+    assert_eq!(code(&mut Command::new(&parser)), Some(2));
+    fs::write(dir.join("config.json"), "{ \"parse_method\": 3 }").unwrap();
+    assert_eq!(code(Command::new(&parser).arg("input.txt")), Some(1));
+    // The same `ron` as the runtime, not a version of its own
+    let ron = fs::read_to_string("Cargo.toml").unwrap().lines().find(|l| l.starts_with("ron = ")).unwrap().to_string();
+    assert!(fs::read_to_string(dir.join("Cargo.toml")).unwrap().lines().any(|l| l == ron));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn tables_source_lists_every_entry_in_order() {
+    let (lex, syn) = analyzers();
+    let src = tables_source(&lex, &syn);
+    assert!(src.contains(&format!("start: {:?},", lex.start)));
+    for ((st, t), a) in &syn.action {
+        assert!(src.contains(&format!("({}, {:?}, {:?}),", st, t, a)));
+    }
+    let gotos: Vec<&str> = src.lines().skip_while(|l| !l.contains("goto: &[")).skip(1).take_while(|l| l.trim() != "],").collect();
+    assert_eq!(gotos.len(), syn.goto.len());
+    let mut sorted = gotos.clone();
+    sorted.sort_by_key(|l| l.trim().trim_start_matches('(').split(',').next().unwrap().parse::<u8>().unwrap());
+    assert_eq!(gotos, sorted);
+}
+
+#[test]
+fn static_tables_load_into_analyzers() {
+    static LEXER: StaticLexer = StaticLexer {
+        start: 'A',
+        accept: &['C'],
+        map: &[('A', &[("a-z", 'B')]), ('B', &[("0", 'C')])],
+        token_list: &["0"],
+    };
+    static PARSER: StaticParser = StaticParser {
+        productions: &[(0, &[NonTerminal("S'"), NonTerminal("S")]), (1, &[NonTerminal("S"), Terminal("ID")])],
+        action: &[(0, "ID", "s2"), (1, "$", "acc"), (2, "$", "r1")],
+        goto: &[(0, "S", 1)],
+        ignore: &["WS"],
+        flatten: &[],
+    };
+    let lex = LEXER.load();
+    assert_eq!(lex.map[&'A'][&"a-z".to_string()], 'B');
+    assert!(lex.accept.contains(&'C'));
+
+    let syn = PARSER.load();
+    assert_eq!(syn.action[&(2, "$".to_string())], "r1");
+    assert_eq!(syn.goto[&(0, "S".to_string())], 1);
+    assert_eq!(syn.productions[&1], vec![Element::NonTerminal("S".into()), Element::Terminal("ID".into())]);
+    assert!(syn.ignore.contains("WS"));
+}
+
+#[test]
+fn variant_names_are_rust_identifiers() {
+    assert_eq!(variant_name("NUM"), "NUM");
+    assert_eq!(variant_name("'+'"), "Lit_2B");
+    assert_eq!(variant_name("\"<=\""), "Lit_3C_3D");
+    assert_eq!(variant_name("2D"), "T_2D");
+    assert_eq!(variant_name("a-b"), "a_b");
+}
+
+#[test]
+fn token_kind_has_a_variant_per_terminal() {
+    let (lex, syn) = analyzers();
+    let lib = lib_source(&lex, &syn, TableFormat::Ron);
+    let start = lib.find("pub enum TokenKind {").unwrap();
+    let body = &lib[start..start + lib[start..].find('}').unwrap()];
+    for variant in ["NUM", "FN", "Lit_2B", "Lit_28", "Lit_29"] {
+        assert!(body.contains(&format!("    {},\n", variant)), "missing {}", variant);
+    }
+    assert!(lib.contains("TokenKind::Lit_2B => \"'+'\","));
+    assert!(lib.contains("=> Some(TokenKind::NUM),"));
+    // ID is returned by calc.l but never used by calc.y
+    let id = body.find("    ID,").unwrap();
+    assert!(body[..id].ends_with("not a token of the grammar\n"));
+}
+
+#[test]
+fn tokens_carry_their_kind() {
+    #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+    enum Calc {
+        Num,
+        Plus,
+    }
+    impl Kind for Calc {
+        fn name(&self) -> &str {
+            match self {
+                Calc::Num => "NUM",
+                Calc::Plus => "'+'",
+            }
+        }
+    }
+    static IDS: OnceLock<HashMap<usize, String>> = OnceLock::new();
+    fn calc_actions(id: usize) -> Option<Calc> {
+        match IDS.get().unwrap().get(&id).map(|t| t.as_str()) {
+            Some("NUM") => Some(Calc::Num),
+            Some("'+'") => Some(Calc::Plus),
+            _ => None,
+        }
+    }
+    let (lex, _) = analyzers();
+    let _ = IDS.set(lex.actions.iter().filter_map(|(id, a)| action_token(a).map(|t| (*id, t))).collect());
+
+    let tokens = Lexer::new(&lex, calc_actions).tokens("1 + 22\n").unwrap();
+    let kinds: Vec<Calc> = tokens.iter().map(|t| t.token_name).collect();
+    assert_eq!(kinds, vec![Calc::Num, Calc::Plus, Calc::Num]);
+    assert_eq!(tokens[2].content, "22");
+    assert_eq!(tokens[1].token_name.name(), "'+'");
+}
+
+#[test]
+fn build_entry_point_writes_an_includable_module() {
+    let dir = out_dir("build");
+    let path = generate_parser_to("tests/corpus/calc.l", "tests/corpus/calc.y", &dir, &config(ParseMethod::SLR)).unwrap();
+    assert_eq!(path, dir.join(PARSER_FILE));
+    let module = fs::read_to_string(&path).unwrap();
+    assert!(module.contains("pub enum TokenKind {"));
+    assert!(module.contains("pub fn parser() -> syntax_analyzer::syn::syn_analyzer::SynAnalyzer {"));
+    assert!(module.contains("\nmod tables {\n    use syntax_analyzer::codegen::runtime::"));
+    // Inner attributes can't be included
+    assert!(!module.contains("#!["));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn module_source_leaves_the_yal_header_out() {
+    let lex = LexAnalyzer::generate("grammar/lexer.yal", &config(ParseMethod::SLR));
+    let syn = SynAnalyzer::generate("grammar/test_grammar.yalp", &config(ParseMethod::SLR), Some(&lex.token_names()));
+    assert!(!lex.header.is_empty());
+    let module = module_source(&lex, &syn);
+    assert!(!module.contains("use ron::de::from_reader;"));
+    assert!(module.contains("pub static PARSER: StaticParser"));
+    assert!(module.trim_end().ends_with('}'));
+}
@@ -0,0 +1,43 @@
+use syntax_analyzer::{
+    lex::yl_reader::read_yalex,
+    syn::yp_reader::read_yalpar,
+    utility::comments::{strip_comments, YAL_COMMENTS, YALP_COMMENTS},
+};
+
+#[test]
+fn commented_yal_matches_plain() {
+    let plain = read_yalex("tests/corpus/plain.yal");
+    let commented = read_yalex("tests/corpus/commented.yal");
+    assert_eq!(plain, commented);
+}
+
+#[test]
+fn commented_yalp_matches_plain() {
+    let plain = read_yalpar("tests/corpus/plain.yalp");
+    let commented = read_yalpar("tests/corpus/commented.yalp");
+    assert_eq!(plain, commented);
+}
+
+#[test]
+fn yal_comments_nest_and_keep_positions() {
+    let src = "a (* b (* c *) d *) e\n(* f\n*) g";
+    let stripped = strip_comments(src, &YAL_COMMENTS).unwrap();
+    assert_eq!(stripped, "a                   e\n    \n   g");
+}
+
+#[test]
+fn comment_markers_in_literals_and_actions_are_kept() {
+    let src = "\"(*\" { (*ptr).len() } (* gone *)";
+    let stripped = strip_comments(src, &YAL_COMMENTS).unwrap();
+    assert_eq!(stripped.trim_end(), "\"(*\" { (*ptr).len() }");
+
+    let src = "A: B /* C /* D */ E ;";
+    let stripped = strip_comments(src, &YALP_COMMENTS).unwrap();
+    assert_eq!(stripped, format!("A: B {} E ;", " ".repeat(12)));
+}
+
+#[test]
+fn unterminated_comment_reports_its_start() {
+    let err = strip_comments("let a = b\n  (* never closed", &YAL_COMMENTS).unwrap_err();
+    assert_eq!((err.line, err.col), (2, 3));
+}
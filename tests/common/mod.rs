@@ -0,0 +1,22 @@
+//! Fixtures shared by the integration tests
+// Every test crate only uses some of them
+#![allow(dead_code)]
+
+use syntax_analyzer::{
+    lex::lex_analyzer::LexAnalyzer,
+    syn::syn_analyzer::SynAnalyzer,
+    utility::read_config::{Config, ParseMethod},
+};
+
+/// Configuration without logs nor drawings
+pub fn config(parse_method: ParseMethod) -> Config {
+    Config { parse_method, ..Config::default() }
+}
+
+/// Analyzers of the plain corpus grammar, generated without logs
+pub fn analyzers() -> (LexAnalyzer, SynAnalyzer) {
+    let config = Config::from_file("tests/corpus/quiet.json").unwrap();
+    let lex = LexAnalyzer::generate("tests/corpus/plain.yal", &config);
+    let syn = SynAnalyzer::generate("tests/corpus/plain.yalp", &config, Some(&lex.token_names()));
+    (lex, syn)
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::analyzers;
+use ron::ser::{to_string_pretty, PrettyConfig};
+use syntax_analyzer::{
+    codegen::{
+        binary::{decode_parser, encode_parser},
+        runtime::{parse, Lexer},
+    },
+    syn::{
+        compress::{Action, CompressedTables},
+        syn_analyzer::SynAnalyzer,
+    },
+};
+
+const INPUTS: [&str; 7] = ["x = 1;", "if (x) y = 2;", "x = = 1;", "if x) y = 2;", "x = 1", "= 1;", "if (x) y = 2; z = 3;"];
+
+#[test]
+fn parses_match_the_plain_tables() {
+    let (lex, syn) = analyzers();
+    let (_, mut packed) = analyzers();
+    packed.compress();
+    assert!(packed.action.is_empty() && packed.goto.is_empty());
+    let lexer = Lexer::new(&lex, |id| lex.token(id));
+    for input in INPUTS {
+        let (symbols, _, plain) = parse(&lexer, &syn, input).unwrap();
+        let (_, _, compressed) = parse(&lexer, &packed, input).unwrap();
+        assert_eq!(plain.map(|e| e.index), compressed.map(|e| e.index), "{}", input);
+        assert_eq!(syn.parse_tree_silent(&symbols).1.ok(), packed.parse_tree_silent(&symbols).1.ok(), "{}", input);
+    }
+}
+
+#[test]
+fn tables_are_smaller_and_round_trip() {
+    let (_, syn) = analyzers();
+    let tables = CompressedTables::new(&syn.action, &syn.goto);
+    assert!(tables.cells() < tables.raw_cells, "{} cells against {}", tables.cells(), tables.raw_cells);
+    for ((st, t), a) in &syn.action {
+        if tables.default_reductions[*st as usize].is_none() {
+            assert_eq!(tables.action(*st, t).map(|a| a.code()).as_ref(), Some(a));
+        }
+    }
+    for ((st, nt), to) in &syn.goto {
+        assert_eq!(tables.goto(*st, nt), Some(*to));
+    }
+
+    let (_, mut packed) = analyzers();
+    packed.compress();
+    let ron = to_string_pretty(&packed, PrettyConfig::default()).unwrap();
+    assert_eq!(ron::from_str::<SynAnalyzer>(&ron).unwrap().compressed, packed.compressed);
+    assert_eq!(decode_parser(&encode_parser(&packed)).unwrap().compressed, packed.compressed);
+}
+
+#[test]
+fn default_reductions_and_displacement() {
+    let entries = |list: &[(u8, &str, &str)]| -> HashMap<(u8, String), String> {
+        list.iter().map(|(st, t, a)| ((*st, t.to_string()), a.to_string())).collect()
+    };
+    let action = entries(&[(0, "a", "s1"), (0, "b", "s2"), (1, "a", "r1"), (1, "b", "r1"), (1, "$", "r2"), (2, "$", "acc")]);
+    let tables = CompressedTables::new(&action, &HashMap::new());
+    assert_eq!(tables.default_reductions, vec![None, Some(1), None]);
+    assert_eq!(tables.action(1, "a"), Some(Action::Reduce(1)));
+    assert_eq!(tables.action(1, "$"), Some(Action::Reduce(2)));
+    assert_eq!(tables.action(1, "c"), Some(Action::Reduce(1)));
+    // Rows without a default miss on every other column
+    assert_eq!(tables.action(0, "$"), None);
+    assert_eq!(tables.action(2, "a"), None);
+    assert_eq!(tables.action(2, "$"), Some(Action::Accept));
+    // Three rows with four entries left, laid over four slots
+    assert_eq!(tables.action.slots.len(), 4);
+}
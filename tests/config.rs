@@ -0,0 +1,157 @@
+use std::{env, fs, path::PathBuf, process::Command};
+
+use syntax_analyzer::utility::read_config::{
+    config_layers, validate_layer, Config, ParseMethod, DEFAULT_TOML, DEFAULT_YAML,
+};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("syntax_analyzer_config_{}_{}", std::process::id(), name));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn no_env() -> Vec<(String, String)> {
+    Vec::new()
+}
+
+fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+    vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+#[test]
+fn later_layers_override_earlier_ones() {
+    let dir = temp_dir("layers");
+    let root = dir.join("root.json");
+    let grammar = dir.join("grammar.json");
+    fs::write(&root, r#"{ "parse_method": "SLR", "vis": { "dfa": "./graph/dfa", "slr_png": "./graph/slr" } }"#).unwrap();
+    fs::write(&grammar, r#"{ "vis": { "dfa": null }, "debug": { "parsing": true } }"#).unwrap();
+
+    let (config, _) = Config::from_layers(&[root, grammar], no_env()).unwrap();
+    assert_eq!(config.parse_method, ParseMethod::SLR);
+    assert_eq!(config.vis.dfa, None);
+    assert_eq!(config.vis.slr_png.as_deref(), Some("./graph/slr"));
+    assert!(config.debug.parsing);
+    assert!(!config.debug.generation);
+}
+
+#[test]
+fn grammar_configs_are_found_next_to_the_grammar() {
+    let dir = temp_dir("discovery");
+    let yalp = dir.join("calc.yalp");
+    let own = dir.join("calc.config.json");
+    let explicit = dir.join("ci.json");
+    fs::write(&own, "{}").unwrap();
+    fs::write(&explicit, "{}").unwrap();
+
+    let grammars = [yalp.to_str().unwrap(), "elsewhere/lexer.yal"];
+    let layers = config_layers(&grammars, Some(explicit.to_str().unwrap())).unwrap();
+    let expected: Vec<PathBuf> = [PathBuf::from("config.json"), own.clone(), explicit].into_iter().filter(|p| p.is_file()).collect();
+    assert_eq!(layers, expected);
+    assert!(config_layers(&grammars, Some("missing.json")).is_err());
+
+    // A lexer and a grammar with the same name share it, read once
+    let yal = dir.join("calc.yal");
+    let layers = config_layers(&[yal.to_str().unwrap(), yalp.to_str().unwrap()], None).unwrap();
+    assert_eq!(layers.iter().filter(|l| **l == own).count(), 1);
+}
+
+#[test]
+fn environment_overrides_files() {
+    let vars = env(&[
+        ("SYNAN_PARSE_METHOD", "lr1"),
+        ("SYNAN_DEBUG_GENERATION", "1"),
+        ("SYNAN_VIS_PARSE_TABLE", "./out/{grammar}.txt"),
+        ("SYNAN_VIS_DFA", ""),
+        ("PATH", "/usr/bin"),
+    ]);
+    let (config, _) = Config::from_layers(&[], vars).unwrap();
+    assert_eq!(config.parse_method, ParseMethod::LR1);
+    assert!(config.debug.generation);
+    assert_eq!(config.vis.parse_table.as_deref(), Some("./out/{grammar}.txt"));
+    assert_eq!(config.vis.dfa, None);
+
+    // Unknown variables are only warned about
+    let (config, warnings) = Config::from_layers(&[], env(&[("SYNAN_VIS_SLR_PGN", "x"), ("SYNAN_HOME", "/opt")])).unwrap();
+    assert_eq!(warnings, vec!["unknown variable SYNAN_VIS_SLR_PGN", "unknown variable SYNAN_HOME"]);
+    assert_eq!(config.vis.slr_png, Config::default().vis.slr_png);
+    assert!(Config::from_layers(&[], env(&[("SYNAN_DEBUG_PARSING", "maybe")])).is_err());
+    assert!(Config::from_layers(&[], env(&[("SYNAN_PARSE_METHOD", "ll1")])).is_err());
+}
+
+#[test]
+fn paths_are_expanded() {
+    let mut config = Config { parse_method: ParseMethod::SLR, ..Config::default() };
+    config.vis.slr_png = Some("./graph/{grammar}/{method}".to_string());
+    config.vis.dfa = Some("./graph/dfa".to_string());
+    // Each analyzer gets the paths of its own file
+    assert_eq!(config.for_grammar("grammar/lexer.yal").vis.slr_png.as_deref(), Some("./graph/lexer/slr"));
+    assert_eq!(config.for_grammar("grammar/test_grammar.yalp").vis.slr_png.as_deref(), Some("./graph/test_grammar/slr"));
+    config.expand_paths("calc");
+    assert_eq!(config.vis.slr_png.as_deref(), Some("./graph/calc/slr"));
+    assert_eq!(config.vis.dfa.as_deref(), Some("./graph/dfa"));
+}
+
+#[test]
+fn toml_and_yaml_layers() {
+    let dir = temp_dir("formats");
+    let toml = dir.join("base.toml");
+    let yaml = dir.join("calc.config.yaml");
+    fs::write(&toml, "parse_method = \"slr\"\n[vis]\ndfa = \"./graph/dfa\"\nslr_png = \"./graph/slr\"\n").unwrap();
+    fs::write(&yaml, "debug:\n  parsing: true\nvis:\n  dfa: null\n").unwrap();
+
+    let (config, warnings) = Config::from_layers(&[toml.clone(), yaml], no_env()).unwrap();
+    assert!(warnings.is_empty());
+    assert_eq!(config.parse_method, ParseMethod::SLR);
+    assert!(config.debug.parsing);
+    assert_eq!(config.vis.dfa, None);
+    assert_eq!(config.vis.slr_png.as_deref(), Some("./graph/slr"));
+
+    // TOML has no null, an empty path turns it off
+    let off = dir.join("off.toml");
+    fs::write(&off, "[vis]\nslr_png = \"\"\n").unwrap();
+    let (config, _) = Config::from_layers(&[toml, off], no_env()).unwrap();
+    assert_eq!(config.vis.slr_png, None);
+}
+
+#[test]
+fn unknown_keys_are_warnings_and_wrong_types_errors() {
+    let mut layer = serde_json::json!({ "debug": { "parsng": true }, "colour": "red" });
+    let warnings = validate_layer(&mut layer).unwrap();
+    assert_eq!(warnings, ["unknown key `colour`", "unknown key `debug.parsng`, did you mean `parsing`?"]);
+
+    let dir = temp_dir("types");
+    let bad = dir.join("bad.toml");
+    fs::write(&bad, "[debug]\nparsing = \"yes\"\n").unwrap();
+    let e = Config::from_layers(&[bad], no_env()).unwrap_err().to_string();
+    assert!(e.contains("bad.toml") && e.contains("`debug.parsing` must be true or false"), "{}", e);
+    assert!(validate_layer(&mut serde_json::json!({ "parse_method": "LL1" })).is_err());
+    assert!(validate_layer(&mut serde_json::json!({ "vis": { "dfa": 3 } })).is_err());
+}
+
+#[test]
+fn commented_defaults_are_the_defaults() {
+    let default = serde_json::to_value(Config::default()).unwrap();
+    let dir = temp_dir("defaults");
+    for (name, contents) in [("default.toml", DEFAULT_TOML), ("default.yaml", DEFAULT_YAML)] {
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        let (config, warnings) = Config::from_layers(&[path], no_env()).unwrap();
+        assert!(warnings.is_empty(), "{}: {:?}", name, warnings);
+        assert_eq!(serde_json::to_value(config).unwrap(), default, "{}", name);
+    }
+}
+
+#[test]
+fn config_init_writes_once() {
+    let dir = temp_dir("init");
+    let path = dir.join("config.toml");
+    let _ = fs::remove_file(&path);
+    let init = |file: &PathBuf| {
+        Command::new(env!("CARGO_BIN_EXE_syntax_analyzer")).args(["config", "init"]).arg(file).output().unwrap().status.code()
+    };
+    assert_eq!(init(&path), Some(0));
+    assert_eq!(fs::read_to_string(&path).unwrap(), DEFAULT_TOML);
+    // Never over an existing file, and JSON can't hold the comments
+    assert_eq!(init(&path), Some(1));
+    assert_eq!(init(&dir.join("config.json")), Some(2));
+}
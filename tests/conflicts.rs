@@ -0,0 +1,45 @@
+use std::{env, fs};
+
+use syntax_analyzer::{
+    syn::{conflicts::find_conflicts, yp_reader::read_grammar},
+    utility::read_config::ParseMethod,
+};
+
+fn grammar(name: &str, source: &str) -> syntax_analyzer::syn::yp_reader::GrammarInfo {
+    let path = env::temp_dir().join(format!("syntax_analyzer_conflicts_{}_{}.yalp", std::process::id(), name));
+    fs::write(&path, source).unwrap();
+    read_grammar(path.to_str().unwrap())
+}
+
+#[test]
+fn ambiguous_sum_is_shift_reduce() {
+    let g = grammar("sum", "%token NUM PLUS\n%%\ne: e PLUS e | NUM ;\n");
+    for method in [ParseMethod::SLR, ParseMethod::LALR, ParseMethod::LR1] {
+        let conflicts = find_conflicts(&g, method);
+        assert_eq!(conflicts.len(), 1, "{:?}", method);
+        let c = &conflicts[0];
+        assert_eq!(c.token, "PLUS");
+        assert_eq!(c.kind(), "shift/reduce");
+        assert!(c.choices.iter().any(|s| s == "reduce by e -> e PLUS e"), "{:?}", c.choices);
+    }
+}
+
+#[test]
+fn plain_grammar_has_none() {
+    let g = read_grammar("tests/corpus/plain.yalp");
+    assert!(find_conflicts(&g, ParseMethod::SLR).is_empty());
+    assert!(find_conflicts(&g, ParseMethod::LALR).is_empty());
+}
+
+#[test]
+fn lr1_keeps_what_lalr_merges() {
+    // LR(1) but not LALR(1): merging the states of `c` gives reduce/reduce
+    let g = grammar(
+        "lr1",
+        "%token A B C D E\n%%\ns: A x D | A y E | B x E | B y D ;\nx: C ;\ny: C ;\n",
+    );
+    let lalr = find_conflicts(&g, ParseMethod::LALR);
+    assert!(!lalr.is_empty());
+    assert!(lalr.iter().all(|c| c.kind() == "reduce/reduce"));
+    assert!(find_conflicts(&g, ParseMethod::LR1).is_empty());
+}
@@ -0,0 +1,46 @@
+/* Infix calculator, adapted from the Bison manual */
+%{
+  #include <stdio.h>
+  int yylex (void);
+  void yyerror (char const *);
+%}
+
+%define api.value.type {double}
+%token NUM
+%token <double> FN "function"
+%left '-' '+'
+%left '*' '/'
+%start input
+
+%% /* Grammar rules and actions follow. */
+
+input:
+  %empty
+| input line
+;
+
+line:
+  '\n'
+| exp '\n'      { printf ("\t%.10g\n", $1); }
+| error '\n'    { yyerrok; }
+;
+
+exp:
+  exp '+' term        { $$ = $1 + $3; }
+| exp '-' term        { $$ = $1 - $3; }
+| term
+;
+
+term
+  : term '*' factor   { $$ = $1 * $3; }
+  | term '/' factor   { if ($3 == 0) { yyerror ("division by zero }"); } $$ = $1 / $3; }
+  | factor
+
+factor[f]: NUM
+  | "function" '(' exp ')'  { $$ = call ($1, $3); }
+  | '(' { depth++; } exp ')' { depth--; $$ = $3; }
+  ;
+
+%%
+
+int main (void) { return yyparse (); }
@@ -0,0 +1,82 @@
+mod common;
+
+use std::collections::HashSet;
+
+use common::config;
+use syntax_analyzer::{
+    lex::lex_analyzer::Symbol,
+    syn::{
+        parse_tree::ParseNode,
+        syn_analyzer::SynAnalyzer,
+        yp_parser::parse_yalp,
+        yp_reader::{grammar_info, GrammarInfo},
+    },
+    utility::read_config::ParseMethod,
+};
+
+fn grammar(src: &str) -> GrammarInfo {
+    grammar_info(&parse_yalp(src).unwrap()).unwrap()
+}
+
+fn bodies(g: &GrammarInfo, head: &str) -> Vec<String> {
+    g.productions[head].iter().map(|b| b.join(" ")).collect()
+}
+
+fn symbols(names: &[&str]) -> Vec<Symbol> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(id, name)| Symbol {
+            id,
+            token: String::new(),
+            token_name: name.to_string(),
+            start: 0,
+            end: 0,
+            line: 1,
+            content: name.to_lowercase(),
+        })
+        .collect()
+}
+
+fn leaves(node: &ParseNode) -> Vec<String> {
+    node.children.iter().map(|c| c.symbol.clone()).collect()
+}
+
+#[test]
+fn groups_become_fresh_non_terminals() {
+    let g = grammar("%token ID COMMA SEMI PLUS MINUS\n%%\nS: ID { COMMA ID } [ SEMI ] ( PLUS | MINUS )+ ;");
+    assert_eq!(bodies(&g, "S"), vec!["ID S_list S_opt S_list2"]);
+    assert_eq!(bodies(&g, "S_list"), vec!["S_list COMMA ID", ""]);
+    assert_eq!(bodies(&g, "S_opt"), vec!["SEMI", ""]);
+    assert_eq!(bodies(&g, "S_list2"), vec!["S_list2 PLUS", "S_list2 MINUS", "PLUS", "MINUS"]);
+    let flatten: HashSet<String> = ["S_list", "S_opt", "S_list2"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(g.flatten, flatten);
+}
+
+#[test]
+fn identical_groups_share_their_non_terminal() {
+    let g = grammar("%token A B\n%%\nS: ( A )* B | T ( A | B ) ;\nT: { A } ;");
+    assert_eq!(bodies(&g, "S"), vec!["S_list B", "T S_group"]);
+    assert_eq!(bodies(&g, "S_group"), vec!["A", "B"]);
+    assert_eq!(bodies(&g, "T"), vec!["S_list"]);
+}
+
+#[test]
+fn unclosed_group_is_reported() {
+    let err = parse_yalp("%token A B\n%%\nS: [ A B ;").unwrap_err();
+    assert_eq!(err.to_string(), "3:10: expected `|` or `]` to close the `[` at 3:4, found `;`");
+}
+
+#[test]
+fn lists_are_flattened_in_the_parse_tree() {
+    let path = std::env::temp_dir().join("ebnf_list.yalp");
+    std::fs::write(&path, "%token ID COMMA SEMI\n%%\nS: ID { COMMA ID } SEMI ;").unwrap();
+    for method in [ParseMethod::SLR, ParseMethod::LALR] {
+        let syn = SynAnalyzer::generate(path.to_str().unwrap(), &config(method), None);
+        let (_, tree) = syn.parse_tree(&symbols(&["ID", "COMMA", "ID", "COMMA", "ID", "SEMI"]));
+        let tree = tree.unwrap();
+        assert_eq!(tree.symbol, "S");
+        assert_eq!(leaves(&tree), vec!["ID", "COMMA", "ID", "COMMA", "ID", "SEMI"]);
+        assert_eq!(tree.children[2].content.as_deref(), Some("id"));
+    }
+}
@@ -0,0 +1,45 @@
+use syntax_analyzer::{
+    lex::yl_parser::parse_yal,
+    syn::{bison_reader::read_bison, yp_parser::parse_yalp, yp_reader::grammar_info},
+    utility::exporter::{antlr4, w3c_ebnf},
+};
+
+const YALP: &str = "%token NUM PLUS '(' ')'\nIGNORE WS\n%%\nExpr: Expr PLUS Term | Term ;\nTerm: NUM | '(' Expr ')' | %empty ;";
+const YAL: &str = "let digit = [0-9]\nrule t =\n  digit+ { return \"NUM\"; }\n| '+' { return \"PLUS\"; }\n| (' ' | '\\t')+ { return \"WS\"; }\n| \"//\" { }";
+
+#[test]
+fn antlr_rules_are_renamed_by_case() {
+    let grammar = grammar_info(&parse_yalp(YALP).unwrap()).unwrap();
+    let lexer = parse_yal(YAL).unwrap();
+    let g4 = antlr4("Calc", &grammar, Some(&lexer));
+    assert_eq!(
+        g4,
+        "grammar Calc;\n\n\
+         expr\n    : expr PLUS term\n    | term\n    ;\n\n\
+         term\n    : NUM\n    | '(' expr ')'\n    | /* empty */\n    ;\n\n\
+         fragment Digit\n    : [0-9]\n    ;\n\n\
+         NUM\n    : Digit+\n    ;\n\n\
+         PLUS\n    : '+'\n    ;\n\n\
+         WS\n    : (' ' | '\\t')+ -> skip\n    ;\n\n\
+         SKIP_3\n    : '//' -> skip\n    ;\n\n"
+    );
+}
+
+#[test]
+fn w3c_literals_use_hex_for_unprintable_chars() {
+    let grammar = grammar_info(&parse_yalp(YALP).unwrap()).unwrap();
+    let lexer = parse_yal(YAL).unwrap();
+    let ebnf = w3c_ebnf(&grammar, Some(&lexer));
+    assert!(ebnf.starts_with("Expr ::= Expr PLUS Term\n       | Term\nTerm ::= NUM\n       | '(' Expr ')'\n"), "{}", ebnf);
+    assert!(ebnf.contains("\ndigit ::= [0-9]\nNUM ::= digit+\n"), "{}", ebnf);
+    assert!(ebnf.contains("\nWS ::= (' ' | #x9)+\n"), "{}", ebnf);
+}
+
+#[test]
+fn bison_char_literals_are_exported_as_literals() {
+    let grammar = read_bison("tests/corpus/calc.y").grammar;
+    let ebnf = w3c_ebnf(&grammar, None);
+    assert!(ebnf.contains("line ::= #xA\n       | exp #xA\n"), "{}", ebnf);
+    let g4 = antlr4("Calc", &grammar, None);
+    assert!(g4.contains("    : term '*' factor\n"), "{}", g4);
+}
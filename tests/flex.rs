@@ -0,0 +1,71 @@
+mod common;
+
+use common::config;
+use syntax_analyzer::{
+    lex::{
+        flex_reader::parse_flex,
+        lex_analyzer::LexAnalyzer,
+        yl_parser::{Loc, Regex},
+        yl_reader::action_token,
+    },
+    utility::read_config::ParseMethod,
+};
+
+#[test]
+fn rules_keep_their_token_and_skip_other_start_conditions() {
+    let flex = parse_flex(&std::fs::read_to_string("tests/corpus/calc.l").unwrap()).unwrap();
+    let names: Vec<String> = flex.yal.lets.iter().map(|l| l.name.clone()).collect();
+    assert_eq!(names, vec!["digit", "id", "number"]);
+
+    let actions: Vec<&str> = flex.yal.rules[0].entries.iter().map(|e| e.action.as_str()).collect();
+    assert_eq!(
+        actions,
+        vec![
+            "return \"NUM\";", "return \"FN\";", "return \"FN\";", "return \"ID\";",
+            "return \"'('\";", "return \"')'\";", "return \"'*'\";", "return \"'+'\";",
+            "return \"'-'\";", "return \"'/'\";", "return \"'\\\\n'\";", "", "",
+        ]
+    );
+    let skipped: Vec<usize> = flex.skipped.iter().map(|(loc, _)| loc.line).collect();
+    assert_eq!(skipped, vec![25, 26, 28]);
+    // `%{ %}` holds C code, so nothing goes into the header
+    assert_eq!(flex.yal.header, None);
+}
+
+#[test]
+fn flex_lexer_tokenizes_input() {
+    let lex = LexAnalyzer::generate("tests/corpus/calc.l", &config(ParseMethod::SLR));
+    let symbols = lex.simulate("x1 + 2.5*(sin 3)\n".to_string()).unwrap();
+    let tokens: Vec<String> = symbols
+        .iter()
+        .filter_map(|s| action_token(&lex.actions[&s.token.parse::<usize>().unwrap()]))
+        .collect();
+    assert_eq!(tokens, vec!["ID", "'+'", "NUM", "'*'", "'('", "FN", "NUM", "')'", "'\\n'"]);
+}
+
+#[test]
+fn quoted_chars_are_returned_as_they_are() {
+    let flex = parse_flex("%%\n\" \"  return ' ';\n\"(\"  return ( '(' );\n\\\"  return '\"';\n").unwrap();
+    let actions: Vec<&str> = flex.yal.rules[0].entries.iter().map(|e| e.action.as_str()).collect();
+    assert_eq!(actions, vec!["return \"' '\";", "return \"'('\";", "return \"'\\\"'\";"]);
+    let tokens: Vec<String> = actions.iter().filter_map(|a| action_token(a)).collect();
+    assert_eq!(tokens, vec!["' '", "'('", "'\"'"]);
+}
+
+#[test]
+fn unsupported_patterns_are_reported() {
+    let err = parse_flex("%%\n^abc   return A;\n").unwrap_err();
+    assert_eq!(err.loc, Loc { line: 2, col: 1 });
+    let err = parse_flex("%%\n{nope}  return A;\n").unwrap_err();
+    assert_eq!(err.to_string(), "2:1: undefined definition `nope`");
+}
+
+#[test]
+fn repetitions_are_expanded() {
+    let flex = parse_flex("%%\na{2,3}  return A;\n").unwrap();
+    let a = Regex::Literal("a".to_string());
+    assert_eq!(
+        flex.yal.rules[0].entries[0].regex,
+        Regex::Concat(vec![a.clone(), a.clone(), Regex::Optional(Box::new(a))])
+    );
+}
@@ -0,0 +1,93 @@
+use std::{env, fs, process::Command};
+
+use serde_json::Value;
+use syntax_analyzer::{
+    codegen::runtime::Lexer,
+    lex::lex_analyzer::LexAnalyzer,
+    syn::syn_analyzer::SynAnalyzer,
+    utility::{
+        cli::CliError,
+        json_output::{DiagnosticKind, Location, Report},
+        read_config::Config,
+    },
+};
+
+fn report(input: &str) -> Report {
+    let config = Config::from_file("tests/corpus/quiet.json").unwrap();
+    let lex = LexAnalyzer::generate("tests/corpus/plain.yal", &config);
+    let syn = SynAnalyzer::generate("tests/corpus/plain.yalp", &config, Some(&lex.token_names()));
+    Report::new(&Lexer::new(&lex, |id| lex.token(id).map(String::from)), &syn, input)
+}
+
+#[test]
+fn accepted_inputs_have_tokens_and_a_tree() {
+    let json = report("x = 1;").to_json();
+    assert_eq!(json["ok"], true);
+    let tokens: Vec<&str> = json["tokens"].as_array().unwrap().iter().map(|t| t["token"].as_str().unwrap()).collect();
+    assert_eq!(tokens, ["ID", "ASSIGN", "INT", "SEMICOLON"]);
+    assert_eq!(json["tokens"][2]["content"], "1");
+    assert_eq!(json["tokens"][2]["start"], 4);
+    assert_eq!(json["tree"]["symbol"], "S");
+    assert_eq!(json["tree"]["children"][0]["children"][0]["children"][0]["content"], "x");
+    assert_eq!(json["steps"].as_array().unwrap().last().unwrap()["action"], "ACCEPTANCE");
+    assert_eq!(json["diagnostics"], Value::Array(Vec::new()));
+}
+
+#[test]
+fn errors_are_diagnostics_at_their_token() {
+    let syntax = report("x = = 1;");
+    assert_eq!(syntax.diagnostics.len(), 1);
+    assert_eq!(syntax.diagnostics[0].kind, DiagnosticKind::Syntax);
+    assert_eq!(syntax.diagnostics[0].location, Some(Location { start: 4, end: 5, line: 0 }));
+    assert!(syntax.tree.is_none());
+    assert!(matches!(syntax.error(), Some(CliError::Syntax(_))));
+
+    let lexical = report("x = 1 $ $;");
+    let kinds: Vec<DiagnosticKind> = lexical.diagnostics.iter().map(|d| d.kind).collect();
+    assert_eq!(kinds, [DiagnosticKind::Lexical, DiagnosticKind::Lexical]);
+    assert!(lexical.diagnostics[0].message.contains('$'));
+    assert_eq!(lexical.error(), Some(CliError::Lexical));
+}
+
+#[test]
+fn json_lines_end_with_the_outcome() {
+    let lines: Vec<Value> = report("x = = 1;").to_json_lines().iter().map(|l| serde_json::from_str(l).unwrap()).collect();
+    let types: Vec<&str> = lines.iter().map(|l| l["type"].as_str().unwrap()).collect();
+    assert_eq!(&types[..5], ["token"; 5]);
+    assert!(types.contains(&"step"));
+    assert_eq!(types[types.len() - 2..], ["diagnostic", "end"]);
+    assert_eq!(lines.last().unwrap()["ok"], false);
+}
+
+#[test]
+fn parse_prints_only_json() {
+    let input = env::temp_dir().join(format!("syntax_analyzer_json_{}.txt", std::process::id()));
+    fs::write(&input, "if (x) y = 2;").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_syntax_analyzer"))
+        .args(["parse", "tests/corpus/plain.yal", "tests/corpus/plain.yalp"])
+        .arg(&input)
+        .args(["--format", "json", "--config", "tests/corpus/quiet.json"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["ok"], true);
+
+    let missing = Command::new(env!("CARGO_BIN_EXE_syntax_analyzer"))
+        .args(["parse", "tests/corpus/plain.yal", "tests/corpus/plain.yalp", "missing.txt", "--format=jsonl"])
+        .args(["--config", "tests/corpus/quiet.json"])
+        .output()
+        .unwrap();
+    assert_eq!(missing.status.code(), Some(1));
+    let first: Value = serde_json::from_str(String::from_utf8(missing.stdout).unwrap().lines().next().unwrap()).unwrap();
+    assert_eq!(first["kind"], "io");
+
+    let grammar = Command::new(env!("CARGO_BIN_EXE_syntax_analyzer"))
+        .args(["parse", "tests/corpus/plain.yal", "missing.yalp", "input.txt", "--format=json"])
+        .args(["--config", "tests/corpus/quiet.json"])
+        .output()
+        .unwrap();
+    assert_eq!(grammar.status.code(), Some(1));
+    let json: Value = serde_json::from_slice(&grammar.stdout).unwrap();
+    assert_eq!(json["diagnostics"][0]["kind"], "io");
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use std::{env, fs};
+
+use common::config;
+use syntax_analyzer::{
+    lex::lex_analyzer::{LexAnalyzer, Symbol},
+    syn::{conflicts::find_conflicts, syn_analyzer::SynAnalyzer, yp_reader::read_grammar},
+    utility::read_config::ParseMethod,
+};
+
+#[test]
+fn states_are_numbered_the_same_every_time() {
+    let lex = LexAnalyzer::generate("tests/corpus/plain.yal", &config(ParseMethod::LALR));
+    let first = SynAnalyzer::generate("tests/corpus/plain.yalp", &config(ParseMethod::LALR), Some(&lex.token_names()));
+    for _ in 0..5 {
+        let again = SynAnalyzer::generate("tests/corpus/plain.yalp", &config(ParseMethod::LALR), Some(&lex.token_names()));
+        assert_eq!(again.action, first.action);
+        assert_eq!(again.goto, first.goto);
+    }
+}
+
+fn parser(name: &str, source: &str) -> SynAnalyzer {
+    let path = env::temp_dir().join(format!("syntax_analyzer_lalr_{}_{}.yalp", std::process::id(), name));
+    fs::write(&path, source).unwrap();
+    SynAnalyzer::from_grammar(read_grammar(path.to_str().unwrap()), name, &config(ParseMethod::LALR), None)
+}
+
+fn accepts(syn: &SynAnalyzer, input: &str) -> bool {
+    let tokens: Vec<Symbol> = input
+        .split(' ')
+        .enumerate()
+        .map(|(i, t)| Symbol {
+            id: i,
+            token: i.to_string(),
+            token_name: t.to_string(),
+            start: i,
+            end: i + 1,
+            line: 1,
+            content: t.to_string(),
+        })
+        .collect();
+    syn.parse(&tokens).1.is_none()
+}
+
+#[test]
+fn lookaheads_come_from_what_follows() {
+    // y -> A . is only followed by B, so it doesn't clash with the shift of C
+    let source = "%token A B C\n%%\nt: s C ;\ns: y B | A C C ;\ny: A ;\n";
+    let path = env::temp_dir().join(format!("syntax_analyzer_lalr_{}_follows.yalp", std::process::id()));
+    fs::write(&path, source).unwrap();
+    assert!(find_conflicts(&read_grammar(path.to_str().unwrap()), ParseMethod::LALR).is_empty());
+
+    // Past a nullable n, B is a lookahead of y -> A . too
+    let syn = parser("nullable", "%token A B C\n%%\ns: y n B ;\ny: A ;\nn: %empty | C ;\n");
+    assert!(accepts(&syn, "A B"));
+    assert!(accepts(&syn, "A C B"));
+    assert!(!accepts(&syn, "A C"));
+}
+
+#[test]
+fn lookaheads_reach_states_found_again() {
+    // LALR(1) but not SLR(1); the $ of r -> l . comes through l -> STAR r
+    let syn = parser("assign", "%token ID STAR EQ\n%%\ns: l EQ r | r ;\nl: STAR r | ID ;\nr: l ;\n");
+    for input in ["ID", "STAR ID", "ID EQ STAR ID", "STAR STAR ID EQ ID"] {
+        assert!(accepts(&syn, input), "{}", input);
+    }
+    assert!(!accepts(&syn, "ID EQ"));
+}
+
+#[test]
+fn items_shifting_the_same_token_agree() {
+    let syn = parser("shared", "%token A B C\n%%\ns: A x | A y ;\nx: B ;\ny: C ;\n");
+    assert!(accepts(&syn, "A B"));
+    assert!(accepts(&syn, "A C"));
+    assert!(!accepts(&syn, "A"));
+}
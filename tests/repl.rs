@@ -0,0 +1,85 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use syntax_analyzer::utility::{read_config::Config, repl::Repl};
+
+fn repl() -> Repl {
+    let config = Config::from_file("tests/corpus/quiet.json").unwrap();
+    Repl::load("tests/corpus/plain.yal", "tests/corpus/plain.yalp", config).unwrap()
+}
+
+#[test]
+fn inputs_show_tokens_and_tree() {
+    let mut repl = repl();
+    let out = repl.eval("x = 1;").unwrap();
+    assert!(out.starts_with("Tokens: ID(x) ASSIGN(=) INT(1) SEMICOLON(;)\nS\n"), "{}", out);
+    assert!(out.contains("      INT '1'"));
+
+    let out = repl.eval("x = = 1;").unwrap();
+    assert!(out.contains("~ Error Syn:"), "{}", out);
+    // Unknown characters at the end used to hang the lexer
+    assert_eq!(repl.eval("x = 1 $").unwrap(), "~ Error Lex: the input has lexical errors");
+}
+
+#[test]
+fn trace_toggles_the_steps() {
+    let mut repl = repl();
+    assert!(!repl.eval("x = 1;").unwrap().contains("ACCEPTANCE"));
+    assert_eq!(repl.eval(":trace").unwrap(), "~ Trace on");
+    assert!(repl.eval("x = 1;").unwrap().contains("ACCEPTANCE"));
+    repl.eval(":reload").unwrap();
+    assert!(repl.trace);
+}
+
+#[test]
+fn nothing_is_drawn() {
+    let table = std::env::temp_dir().join(format!("syntax_analyzer_repl_{}_table.txt", std::process::id()));
+    let mut config = Config::from_file("tests/corpus/quiet.json").unwrap();
+    config.vis.parse_table = Some(table.to_str().unwrap().to_string());
+    let mut repl = Repl::load("tests/corpus/plain.yal", "tests/corpus/plain.yalp", config).unwrap();
+    repl.eval(":reload").unwrap();
+    assert!(!table.exists());
+}
+
+#[test]
+fn sets_and_states() {
+    let mut repl = repl();
+    assert_eq!(repl.eval(":first X").unwrap(), "FIRST(X) = { ID, IF }");
+    assert_eq!(repl.eval(":follow T").unwrap(), "FOLLOW(T) = { $ }");
+    assert!(repl.eval(":first Nope").unwrap().starts_with("~ No FIRST set"));
+
+    let state = repl.eval(":state 0").unwrap();
+    assert!(state.starts_with("I0\n~ S' -> . S , $"), "{}", state);
+    let last = repl.syn.states.len();
+    assert!(repl.eval(&format!(":state {}", last)).unwrap().starts_with("~ No state"));
+    assert!(repl.eval(":what").unwrap().starts_with("~ Unknown command"));
+    assert_eq!(repl.eval(":quit"), None);
+}
+
+#[test]
+fn run_reads_until_quit() {
+    let mut repl = repl();
+    let mut out = Vec::new();
+    repl.run(":first S\n:quit\nx = 1;\n".as_bytes(), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("> FIRST(S) = { ID, IF }\n> "));
+    assert!(!out.contains("Tokens"));
+}
+
+#[test]
+fn accepted_inputs_print_nothing_past_the_writer() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_syntax_analyzer"))
+        .args(["repl", "tests/corpus/plain.yal", "tests/corpus/plain.yalp", "--config", "tests/corpus/quiet.json"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"x = 1;\n:quit\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Tokens: ID(x)"), "{}", stdout);
+    assert!(!stdout.contains("Parsing Completed"), "{}", stdout);
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use std::fs;
+
+use common::analyzers;
+use syntax_analyzer::{
+    codegen::runtime::{parse, Lexer},
+    lex::lex_analyzer::Symbol,
+    syn::syn_analyzer::{ParseError, ParsingStep},
+};
+
+#[test]
+fn symbols_round_trip() {
+    let (lex, _) = analyzers();
+    let symbols = Lexer::new(&lex, |id| lex.token(id).map(String::from)).tokens("if (x) y = 2;").unwrap();
+    let json = serde_json::to_string(&symbols).unwrap();
+    assert_eq!(serde_json::from_str::<Vec<Symbol>>(&json).unwrap(), symbols);
+    let ron = ron::to_string(&symbols).unwrap();
+    assert_eq!(ron::from_str::<Vec<Symbol>>(&ron).unwrap(), symbols);
+
+    let renamed = Symbol { token_name: "OTHER".to_string(), ..symbols[0].clone() };
+    assert_ne!(renamed, symbols[0]);
+}
+
+#[test]
+fn traces_can_be_golden_files() {
+    let (lex, syn) = analyzers();
+    let lexer = Lexer::new(&lex, |id| lex.token(id));
+    let (_, steps, error) = parse(&lexer, &syn, "x = 1;").unwrap();
+    assert!(error.is_none());
+    let golden = fs::read_to_string("tests/corpus/plain.steps.json").unwrap();
+    assert_eq!(serde_json::to_string_pretty(&steps).unwrap(), golden.trim_end());
+
+    let loaded: Vec<ParsingStep> = serde_json::from_str(&golden).unwrap();
+    assert_eq!(loaded, steps);
+    assert_eq!(
+        loaded[0],
+        ParsingStep { stack: "[0] []".to_string(), input: "ID ASSIGN INT SEMICOLON $".to_string(), action: "Shift 1".to_string() }
+    );
+    assert_eq!(loaded.last().unwrap().action, "ACCEPTANCE");
+}
+
+#[test]
+fn parse_errors_round_trip() {
+    let (lex, syn) = analyzers();
+    let (symbols, _, error) = parse(&Lexer::new(&lex, |id| lex.token(id)), &syn, "x = = 1;").unwrap();
+    let error = error.unwrap();
+    assert_eq!(error.index, 2);
+    assert_eq!(symbols[error.index].content, "=");
+    assert_eq!(error.to_string(), "Syntax error at (11, 'ASSIGN')");
+    // Colors are only added when it is shown
+    assert!(error.visual().contains("\x1b[31m=\x1b[0m"));
+
+    let ron = ron::to_string(&error).unwrap();
+    assert!(!ron.contains('\x1b'), "{}", ron);
+    assert_eq!(ron::from_str::<ParseError>(&ron).unwrap(), error);
+}
+
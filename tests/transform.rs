@@ -0,0 +1,77 @@
+use std::collections::HashSet;
+
+use syntax_analyzer::syn::{
+    transform::{left_factor, remove_left_recursion, remove_unit_productions, remove_useless, transform_grammar},
+    yp_parser::parse_yalp,
+    yp_reader::{grammar_info, read_yalpar, GrammarInfo},
+    yp_writer::yalp_source,
+};
+
+fn grammar(src: &str) -> GrammarInfo {
+    grammar_info(&parse_yalp(src).unwrap()).unwrap()
+}
+
+fn bodies(g: &GrammarInfo, head: &str) -> Vec<String> {
+    g.productions[head].iter().map(|b| b.join(" ")).collect()
+}
+
+// Non terminals that can appear first in a derivation of `head`
+fn left_corners(g: &GrammarInfo, head: &str) -> HashSet<String> {
+    let mut found = HashSet::new();
+    let mut pending = vec![head.to_string()];
+    while let Some(nt) = pending.pop() {
+        for body in &g.productions[&nt] {
+            if let Some(first) = body.first() {
+                if g.productions.contains_key(first) && found.insert(first.clone()) {
+                    pending.push(first.clone());
+                }
+            }
+        }
+    }
+    found
+}
+
+const EXPR: &str = "%token PLUS TIMES LPAREN RPAREN ID\n%%\n\
+    E: E PLUS T | T ;\nT: T TIMES F | F ;\nF: LPAREN E RPAREN | ID ;";
+
+#[test]
+fn direct_left_recursion_becomes_a_tail() {
+    let g = remove_left_recursion(&grammar(EXPR));
+    assert_eq!(bodies(&g, "E"), vec!["T E_tail"]);
+    assert_eq!(bodies(&g, "E_tail"), vec!["PLUS T E_tail", ""]);
+    assert_eq!(bodies(&g, "T_tail"), vec!["TIMES F T_tail", ""]);
+}
+
+#[test]
+fn indirect_left_recursion_is_removed() {
+    let g = remove_left_recursion(&grammar("%token a b\n%%\nS: A a | b ;\nA: S b | a ;"));
+    for head in g.productions.keys() {
+        assert!(!left_corners(&g, head).contains(head), "{} is still left recursive", head);
+    }
+    assert_eq!(bodies(&g, "A"), vec!["b b A_tail", "a A_tail"]);
+}
+
+#[test]
+fn common_prefixes_are_factored() {
+    let g = left_factor(&grammar("%token IF THEN ELSE X\n%%\nS: IF X THEN S | IF X THEN S ELSE S | X ;"));
+    assert_eq!(bodies(&g, "S"), vec!["IF X THEN S S_factor", "X"]);
+    assert_eq!(bodies(&g, "S_factor"), vec!["", "ELSE S"]);
+}
+
+#[test]
+fn useless_and_unit_productions_are_dropped() {
+    let g = grammar("%token a b\n%%\nS: A | B ;\nA: a ;\nB: B b ;\nC: a ;");
+    let g = remove_unit_productions(&remove_useless(&g));
+    assert_eq!(bodies(&g, "S"), vec!["a"]);
+    let g = remove_useless(&g);
+    assert_eq!(g.non_terminals, HashSet::from(["S".to_string()]));
+}
+
+#[test]
+fn transformed_grammar_round_trips_through_yalp() {
+    let g = transform_grammar(&read_yalpar("grammar/test_grammar.yalp"));
+    for head in g.productions.keys() {
+        assert!(!left_corners(&g, head).contains(head), "{} is still left recursive", head);
+    }
+    assert_eq!(grammar(&yalp_source(&g)), g);
+}
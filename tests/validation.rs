@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+
+use syntax_analyzer::syn::{
+    validation::{validate_grammar, GrammarIssue, Severity},
+    yp_parser::parse_yalp,
+    yp_reader::{grammar_info, read_yalpar},
+};
+
+fn issues(src: &str, lexer: Option<&HashSet<String>>) -> Vec<GrammarIssue> {
+    let grammar = grammar_info(&parse_yalp(src).unwrap()).unwrap();
+    validate_grammar(&grammar, lexer)
+}
+
+#[test]
+fn test_grammar_has_no_errors() {
+    let grammar = read_yalpar("grammar/test_grammar.yalp");
+    let found = validate_grammar(&grammar, None);
+    assert!(found.iter().all(|i| i.severity() == Severity::Warning), "{:?}", found);
+}
+
+#[test]
+fn misspelled_tokens_are_undefined_non_terminals() {
+    let found = validate_grammar(&read_yalpar("tests/corpus/typo.yalp"), None);
+    assert_eq!(
+        found,
+        vec![
+            GrammarIssue::UndefinedNonTerminal { symbol: "ASIGN".to_string(), suggestion: Some("ASSIGN".to_string()) },
+            GrammarIssue::UnusedTerminal("ASSIGN".to_string()),
+            GrammarIssue::EmptyLanguage("S".to_string()),
+            GrammarIssue::NonProductive("X".to_string()),
+        ]
+    );
+    assert_eq!(found[0].severity(), Severity::Error);
+    assert_eq!(found[0].to_string(), "`ASIGN` is used but has no productions (did you mean `ASSIGN`?)");
+}
+
+#[test]
+fn unused_and_unreachable_symbols_are_warnings() {
+    let found = issues("%token A B\n%%\nS: A ;\nT: A ;\nU: A U | A ;", None);
+    assert_eq!(
+        found,
+        vec![
+            GrammarIssue::UnusedTerminal("B".to_string()),
+            GrammarIssue::UnusedNonTerminal("T".to_string()),
+            GrammarIssue::Unreachable("U".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn non_productive_start_symbol_is_an_error() {
+    let found = issues("%token A\n%%\nS: A S ;", None);
+    assert_eq!(found, vec![GrammarIssue::EmptyLanguage("S".to_string())]);
+    assert_eq!(found[0].severity(), Severity::Error);
+}
+
+#[test]
+fn duplicate_alternatives_and_missing_lexer_tokens() {
+    let lexer = HashSet::from(["A".to_string()]);
+    let found = issues("%token A B\n%%\nS: A | A B | A ;", Some(&lexer));
+    assert_eq!(
+        found,
+        vec![
+            GrammarIssue::DuplicateProduction { head: "S".to_string(), body: vec!["A".to_string()] },
+            GrammarIssue::TokenNeverProduced("B".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn misspelled_token_names_are_paired() {
+    let lexer: HashSet<String> = ["SEMICOLN", "ID", "PLUS", "WS"].iter().map(|s| s.to_string()).collect();
+    let found = issues("%token SEMICOLON ID IDS\nIGNORE WS\n%%\nS: ID SEMICOLON IDS ;", Some(&lexer));
+    assert_eq!(
+        found,
+        vec![
+            GrammarIssue::TokenNeverProduced("IDS".to_string()),
+            GrammarIssue::MisspelledToken { declared: "SEMICOLON".to_string(), returned: "SEMICOLN".to_string() },
+            GrammarIssue::UnknownLexerToken { token: "PLUS".to_string(), suggestion: None },
+        ]
+    );
+    assert_eq!(found[1].severity(), Severity::Warning);
+}
+
+#[test]
+fn close_short_names_dont_block_generation() {
+    let lexer: HashSet<String> = ["ID", "NUM", "WS"].iter().map(|s| s.to_string()).collect();
+    let found = issues("%token IF NUM\nIGNORE WS\n%%\nS: NUM | IF NUM ;", Some(&lexer));
+    assert_eq!(found, vec![GrammarIssue::MisspelledToken { declared: "IF".to_string(), returned: "ID".to_string() }]);
+    assert_eq!(found[0].severity(), Severity::Warning);
+}
+
+#[test]
+fn unknown_lexer_tokens_suggest_the_closest_declared_name() {
+    let lexer: HashSet<String> = ["Number", "NUMBER"].iter().map(|s| s.to_string()).collect();
+    let found = issues("%token NUMBER\n%%\nS: NUMBER ;", Some(&lexer));
+    assert_eq!(found[0].to_string(), "the lexer returns `Number`, which is not a %token nor IGNORE (did you mean `NUMBER`?)");
+}
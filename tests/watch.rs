@@ -0,0 +1,86 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use syntax_analyzer::utility::{
+    read_config::Config,
+    watch::{Snapshot, Watcher},
+};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("syntax_analyzer_watch_{}_{}", std::process::id(), name));
+    fs::create_dir_all(&dir).unwrap();
+    fs::copy("tests/corpus/plain.yal", dir.join("plain.yal")).unwrap();
+    fs::copy("tests/corpus/plain.yalp", dir.join("plain.yalp")).unwrap();
+    dir
+}
+
+fn take(dir: &Path, sample: &str) -> Snapshot {
+    let config = Config::from_file("tests/corpus/quiet.json").unwrap();
+    fs::write(dir.join("input.txt"), sample).unwrap();
+    let path = |f: &str| dir.join(f).to_str().unwrap().to_string();
+    Snapshot::take(&path("plain.yal"), &path("plain.yalp"), &config, Some(&path("input.txt"))).0
+}
+
+#[test]
+fn watcher_sees_changes_once() {
+    let dir = temp_dir("watcher");
+    let yal = dir.join("plain.yal");
+    let missing = dir.join("missing.yalp");
+    let mut watcher = Watcher::new(&[yal.to_str().unwrap(), missing.to_str().unwrap()]);
+    assert!(watcher.changed().is_empty());
+
+    fs::write(&yal, fs::read_to_string(&yal).unwrap() + "\n").unwrap();
+    assert_eq!(watcher.changed(), vec![yal.clone()]);
+    assert!(watcher.changed().is_empty());
+
+    fs::write(&missing, "").unwrap();
+    assert_eq!(watcher.changed(), vec![missing]);
+}
+
+#[test]
+fn token_and_sample_changes() {
+    let dir = temp_dir("tokens");
+    let before = take(&dir, "x = 1;");
+    assert_eq!(before.states, Some(14));
+    assert!(before.conflicts.is_empty());
+    assert_eq!(before.sample.as_deref(), Some("accepted"));
+    assert_eq!(before.diff(&before), vec!["~ Nothing changed"]);
+
+    let yal = dir.join("plain.yal");
+    fs::write(&yal, fs::read_to_string(&yal).unwrap().replace("| \"=\" ", "| \":=\" ")).unwrap();
+    let after = take(&dir, "x = 1;");
+    assert_eq!(
+        after.diff(&before),
+        vec!["~ token ASSIGN: = -> :=", "~ sample: accepted -> lexical error"]
+    );
+}
+
+#[test]
+fn new_conflicts_are_listed() {
+    let dir = temp_dir("conflicts");
+    let before = take(&dir, "x = 1;");
+    let yalp = dir.join("plain.yalp");
+    let source = fs::read_to_string(&yalp).unwrap();
+    fs::write(&yalp, source.replace("    T\n;", "    T\n    | S S\n;")).unwrap();
+
+    let after = take(&dir, "x = 1;");
+    assert_eq!(after.error, None);
+    assert_eq!(after.states, None);
+    let diff = after.diff(&before);
+    assert!(!diff.is_empty());
+    assert!(diff.iter().all(|l| l.starts_with("+ conflict: shift/reduce")), "{:?}", diff);
+    assert!(diff.iter().any(|l| l.contains("reduce by S -> S S / shift")), "{:?}", diff);
+    assert!(before.diff(&after).iter().all(|l| l.starts_with("- conflict: ")));
+}
+
+#[test]
+fn errors_are_reported_alone() {
+    let dir = temp_dir("errors");
+    let before = take(&dir, "x = 1;");
+    fs::write(dir.join("plain.yalp"), "%token ID\n%%\nS: ID T ;\n").unwrap();
+    let after = take(&dir, "x = 1;");
+    assert!(after.error.is_some());
+    assert_eq!(after.diff(&before).len(), 1);
+}
@@ -0,0 +1,68 @@
+use syntax_analyzer::{
+    lex::{
+        lex_analyzer::LexAnalyzer,
+        yl_parser::{parse_yal, Loc, Regex, SetItem},
+        yl_reader::{lexer_data, read_yalex},
+    },
+    utility::read_config::Config,
+};
+
+#[test]
+fn entries_may_span_lines_and_actions_may_hold_braces() {
+    let src = "let d = [0-9]\nrule t =\n  d\n  +  { return \"X\"; }\n | \"}\" { if a { \"|}\" } }\n";
+    let file = parse_yal(src).unwrap();
+    let entries = &file.rules[0].entries;
+    assert_eq!(entries.len(), 2);
+    assert_eq!(
+        entries[0].regex,
+        Regex::Plus(Box::new(Regex::Var("d".to_string(), Loc { line: 3, col: 3 })))
+    );
+    assert_eq!(entries[1].action, "if a { \"|}\" }");
+}
+
+#[test]
+fn variables_that_prefix_others_are_expanded_separately() {
+    let src = "let digit = [0-9]\nlet digits = digit+\nrule t = digits { }";
+    let file = parse_yal(src).unwrap();
+    let data = lexer_data(&file);
+    assert_eq!(data.merged, "(((([0-9])+)){0})");
+}
+
+#[test]
+fn errors_point_at_the_offending_token() {
+    let err = parse_yal("let d = [0-9]\nrule t = dd { }").unwrap_err();
+    assert_eq!(err.to_string(), "2:10: undefined variable `dd`");
+
+    let err = parse_yal("let d [0-9]").unwrap_err();
+    assert_eq!(err.loc, Loc { line: 1, col: 7 });
+}
+
+#[test]
+fn project_lexer_still_reads() {
+    let data = read_yalex("grammar/lexer.yal");
+    assert_eq!(data.actions.len(), 21);
+    assert_eq!(data.actions[&20], "return \"WS\";");
+}
+
+#[test]
+fn unknown_characters_may_end_the_input() {
+    let config = Config::from_file("tests/corpus/quiet.json").unwrap();
+    let lex = LexAnalyzer::generate("tests/corpus/plain.yal", &config);
+    // Used to loop forever looking for a lexeme past the end
+    assert!(lex.simulate("x = 1; $$".to_string()).is_none());
+}
+
+#[test]
+fn char_literals_in_actions_and_sets() {
+    let src = "rule t = \"a\" { if c == '}' { return \"R\"; } }\n | \"b\" { let q: &'static str = \"{\"; f('{', '\\'') }";
+    let file = parse_yal(src).unwrap();
+    let entries = &file.rules[0].entries;
+    assert_eq!(entries[0].action, "if c == '}' { return \"R\"; }");
+    assert_eq!(entries[1].action, "let q: &'static str = \"{\"; f('{', '\\'')");
+
+    let file = parse_yal("rule t = ['\"'] { } | ['+''-''*'] { } | ['a'-'c'] { }").unwrap();
+    let sets: Vec<&Regex> = file.rules[0].entries.iter().map(|e| &e.regex).collect();
+    assert_eq!(sets[0], &Regex::Set(vec![SetItem::Char('"')]));
+    assert_eq!(sets[1], &Regex::Set(vec![SetItem::Char('+'), SetItem::Char('-'), SetItem::Char('*')]));
+    assert_eq!(sets[2], &Regex::Set(vec![SetItem::Range('a', 'c')]));
+}
@@ -0,0 +1,51 @@
+use syntax_analyzer::{
+    lex::yl_parser::Loc,
+    syn::{
+        yp_parser::{parse_yalp, Item},
+        yp_reader::{grammar_info, read_yalpar},
+    },
+};
+
+#[test]
+fn one_line_productions_keep_their_body() {
+    let file = parse_yalp("%token A B\n%start T\n%%\nS: A ; T: S B | %empty ;\n").unwrap();
+    match &file.productions[1].alternatives[0].items[1] {
+        Item::Symbol(sym) => assert_eq!(sym.loc, Loc { line: 4, col: 13 }),
+        item => panic!("expected a symbol, found {:?}", item),
+    }
+
+    let grammar = grammar_info(&file).unwrap();
+    assert_eq!(grammar.productions["S"], vec![vec!["A".to_string()]]);
+    assert_eq!(grammar.productions["T"], vec![vec!["S".to_string(), "B".to_string()], vec![]]);
+    assert_eq!(grammar.init_symbol, "T");
+}
+
+#[test]
+fn quoted_symbols_may_contain_colons() {
+    let file = parse_yalp("%token '::' A\n%%\nS: A '::' A ;").unwrap();
+    let grammar = grammar_info(&file).unwrap();
+    assert!(grammar.terminals.contains("'::'"));
+    assert_eq!(grammar.productions["S"][0].len(), 3);
+}
+
+#[test]
+fn unknown_symbols_are_left_to_the_validation() {
+    let file = parse_yalp("%token A\n%%\nS: A B ;").unwrap();
+    let grammar = grammar_info(&file).unwrap();
+    assert!(grammar.non_terminals.contains("B"));
+    assert!(!grammar.productions.contains_key("B"));
+}
+
+#[test]
+fn missing_semicolon_is_a_syntax_error() {
+    let err = parse_yalp("%token A\n%%\nS: A\nT: A ;").unwrap_err();
+    assert_eq!(err.loc, Loc { line: 4, col: 1 });
+}
+
+#[test]
+fn project_grammar_still_reads() {
+    let grammar = read_yalpar("grammar/test_grammar.yalp");
+    assert_eq!(grammar.init_symbol, "S");
+    assert_eq!(grammar.non_terminals.len(), 9);
+    assert_eq!(grammar.productions["L"].len(), 4);
+}